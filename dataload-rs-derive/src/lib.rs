@@ -0,0 +1,119 @@
+//! Procedural macros for dataload-rs. Use them through the `derive` feature of `dataload-rs`,
+//! which re-exports them.
+use proc_macro::TokenStream;
+use quote::quote;
+use syn::{parse_macro_input, spanned::Spanned, Error, FnArg, ItemFn, ReturnType, Type};
+
+/// Turns a free async function into a `BatchFunction`.
+///
+/// The function must have the shape `async fn name(keys: &[K], context: &C) -> Vec<(K, V)>`, or
+/// return a `HashMap<K, V>`. It is replaced by a unit struct of the same name implementing
+/// `BatchFunction<K, V, Context = C>`, which can be passed to `Loader::new`:
+///
+/// ```ignore
+/// #[batch_fn]
+/// async fn users(keys: &[i64], db: &Db) -> Vec<(i64, User)> {
+///     db.users(keys).await
+/// }
+///
+/// let loader = Loader::new(users, db);
+/// ```
+#[proc_macro_attribute]
+pub fn batch_fn(args: TokenStream, item: TokenStream) -> TokenStream {
+    if !args.is_empty() {
+        let args = proc_macro2::TokenStream::from(args);
+        return Error::new(args.span(), "#[batch_fn] takes no arguments").to_compile_error().into();
+    }
+    let item = parse_macro_input!(item as ItemFn);
+    expand(item).unwrap_or_else(Error::into_compile_error).into()
+}
+
+fn expand(item: ItemFn) -> syn::Result<proc_macro2::TokenStream> {
+    let ItemFn { attrs, vis, sig, block } = item;
+    if sig.asyncness.is_none() {
+        return Err(Error::new_spanned(sig.fn_token, "#[batch_fn] requires an async fn"));
+    }
+    if !sig.generics.params.is_empty() || sig.generics.where_clause.is_some() {
+        return Err(Error::new_spanned(&sig.generics, "#[batch_fn] does not support generics"));
+    }
+    let inputs = sig.inputs.iter().collect::<Vec<_>>();
+    let [keys, context] = inputs[..] else {
+        return Err(Error::new_spanned(
+            &sig.inputs,
+            "#[batch_fn] expects `keys: &[K]` and `context: &C` parameters",
+        ));
+    };
+    let (keys_pat, keys_ty) = typed_arg(keys)?;
+    let (context_pat, context_ty) = typed_arg(context)?;
+    let key = match referent(keys_ty) {
+        Some(Type::Slice(slice)) => &*slice.elem,
+        _ => return Err(Error::new_spanned(keys_ty, "expected keys of type `&[K]`")),
+    };
+    let context = match referent(context_ty) {
+        Some(context) => context,
+        None => return Err(Error::new_spanned(context_ty, "expected a context of type `&C`")),
+    };
+    let value = match &sig.output {
+        ReturnType::Type(_, ty) => loaded_value(ty),
+        ReturnType::Default => None,
+    };
+    let Some((value, loaded)) = value else {
+        return Err(Error::new_spanned(
+            &sig.output,
+            "expected a return type of `Vec<(K, V)>` or `HashMap<K, V>`",
+        ));
+    };
+    let name = &sig.ident;
+
+    Ok(quote! {
+        #(#attrs)*
+        #[allow(non_camel_case_types)]
+        #[derive(Clone, Copy, Debug, Default)]
+        #vis struct #name;
+
+        #[::dataload_rs::__private::async_trait]
+        impl ::dataload_rs::BatchFunction<#key, #value> for #name {
+            type Context = #context;
+            type Loaded = #loaded;
+
+            async fn load(&self, #keys_pat: &[#key], #context_pat: &#context) -> #loaded #block
+        }
+    })
+}
+
+/// Splits a `pat: Type` parameter, rejecting receivers.
+fn typed_arg(arg: &FnArg) -> syn::Result<(&syn::Pat, &Type)> {
+    match arg {
+        FnArg::Typed(arg) => Ok((&arg.pat, &arg.ty)),
+        FnArg::Receiver(receiver) => {
+            Err(Error::new_spanned(receiver, "#[batch_fn] must be applied to a free function"))
+        }
+    }
+}
+
+/// Returns `T` for a shared reference `&T`.
+fn referent(ty: &Type) -> Option<&Type> {
+    match ty {
+        Type::Reference(reference) if reference.mutability.is_none() => Some(&reference.elem),
+        _ => None,
+    }
+}
+
+/// Returns `V` for `Vec<(K, V)>` and `HashMap<K, V>`, along with the return type itself.
+fn loaded_value(ty: &Type) -> Option<(&Type, &Type)> {
+    let Type::Path(path) = ty else { return None };
+    let segment = path.path.segments.last()?;
+    let syn::PathArguments::AngleBracketed(args) = &segment.arguments else { return None };
+    let mut args = args.args.iter();
+    let value = match (segment.ident.to_string().as_str(), args.next()?) {
+        ("Vec", syn::GenericArgument::Type(Type::Tuple(tuple))) if tuple.elems.len() == 2 => {
+            tuple.elems.last()?
+        }
+        ("HashMap", _) => match args.next()? {
+            syn::GenericArgument::Type(value) => value,
+            _ => return None,
+        },
+        _ => return None,
+    };
+    Some((value, ty))
+}
@@ -0,0 +1,80 @@
+//! An async-graphql schema whose resolvers load authors through a `Loader`, so that resolving the
+//! author of every book costs a single query instead of one per book.
+//!
+//! Run with `cargo run --example graphql_server --features testing`.
+use std::time::Duration;
+
+use async_graphql::{Context, EmptyMutation, EmptySubscription, Object, Request, Schema};
+use dataload_rs::{
+    testing::{FakeTable, TableLoader},
+    Loader,
+};
+
+#[derive(Debug, Clone)]
+struct Author {
+    name: String,
+}
+
+#[Object]
+impl Author {
+    async fn name(&self) -> &str {
+        &self.name
+    }
+}
+
+#[derive(Debug, Clone)]
+struct Book {
+    title: String,
+    author_id: i64,
+}
+
+#[Object]
+impl Book {
+    async fn title(&self) -> &str {
+        &self.title
+    }
+
+    /// Enqueues the author's id on the request's loader. Sibling books resolve concurrently, so
+    /// their ids are batched into a single query.
+    async fn author(&self, ctx: &Context<'_>) -> Option<Author> {
+        ctx.data_unchecked::<Loader<i64, Author>>().load(self.author_id).await
+    }
+}
+
+struct Query;
+
+#[Object]
+impl Query {
+    async fn books(&self, ctx: &Context<'_>) -> Vec<Book> {
+        ctx.data_unchecked::<Vec<Book>>().clone()
+    }
+}
+
+#[tokio::main]
+async fn main() {
+    let authors: FakeTable<i64, Author> = FakeTable::new([
+        (1, Author { name: "Ursula K. Le Guin".to_owned() }),
+        (2, Author { name: "Octavia E. Butler".to_owned() }),
+    ]);
+    let books = vec![
+        Book { title: "The Dispossessed".to_owned(), author_id: 1 },
+        Book { title: "Kindred".to_owned(), author_id: 2 },
+        Book { title: "The Left Hand of Darkness".to_owned(), author_id: 1 },
+        Book { title: "Parable of the Sower".to_owned(), author_id: 2 },
+    ];
+    let schema = Schema::build(Query, EmptyMutation, EmptySubscription).data(books).finish();
+
+    // Loaders are created per request, so that cached values never outlive the request. The
+    // short dispatch delay lets every sibling resolver enqueue its key before the batch runs, even
+    // though the worker runs on another thread.
+    let loader = Loader::builder(TableLoader, authors.clone())
+        .dispatch_after(Duration::from_millis(2))
+        .build();
+    let request = Request::new("{ books { title author { name } } }").data(loader);
+    let response = schema.execute(request).await;
+    assert!(response.errors.is_empty(), "{:?}", response.errors);
+    println!("{}", response.data);
+
+    // Four books, two authors, one query.
+    assert_eq!(authors.queries(), vec![vec![1, 2]]);
+}
@@ -10,8 +10,9 @@ struct MyBatchFn;
 #[async_trait]
 impl BatchFunction<i64, String> for MyBatchFn {
     type Context = HashMap<i64, String>;
+    type Loaded = Vec<(i64, String)>;
 
-    async fn load(keys: &[i64], context: &Self::Context) -> Vec<(i64, String)> {
+    async fn load(&self, keys: &[i64], context: &Self::Context) -> Vec<(i64, String)> {
         keys.iter().filter_map(|k| context.get(k).cloned().map(|v| (*k, v))).collect()
     }
 }
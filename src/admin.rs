@@ -0,0 +1,41 @@
+//! Helpers for exposing loader diagnostics on an internal admin route.
+use serde::Serialize;
+
+use crate::{health::LoaderHealth, registry::Loaders};
+
+/// The content type of the body returned by [`render_json`].
+pub const CONTENT_TYPE: &str = "application/json";
+
+#[derive(Serialize)]
+struct LoaderReport<'a> {
+    name: &'a str,
+    health: &'a LoaderHealth,
+    hit_rate: f64,
+    average_batch_size: f64,
+}
+
+#[derive(Serialize)]
+struct Report<'a> {
+    loaders: Vec<LoaderReport<'a>>,
+}
+
+/// Renders the state of every loader in the registry as a JSON document of the form
+/// `{"loaders": [{"name": ..., "health": {...}, "hit_rate": ..., "average_batch_size": ...}]}`.
+///
+/// The output is framework agnostic; mount it on a route with [`CONTENT_TYPE`] as the response's
+/// content type.
+pub fn render_json(loaders: &Loaders) -> String {
+    let health = loaders.health();
+    let report = Report {
+        loaders: health
+            .iter()
+            .map(|(name, health)| LoaderReport {
+                name,
+                health,
+                hit_rate: health.hit_rate(),
+                average_batch_size: health.average_batch_size(),
+            })
+            .collect(),
+    };
+    serde_json::to_string(&report).expect("loader report is always serializable")
+}
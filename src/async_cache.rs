@@ -0,0 +1,127 @@
+use std::time::Duration;
+
+use async_trait::async_trait;
+
+use crate::cache::{Cache, CachePolicy};
+
+/// Storage backing a [`crate::Loader`] whose operations may await, e.g. a cache that lives in a
+/// remote service.
+///
+/// The worker awaits every cache read and write, so a slow cache delays the whole frame. Every
+/// [`Cache`] is also an `AsyncCache` whose operations complete immediately, and either kind can be
+/// supplied with [`crate::LoaderBuilder::cache`].
+#[async_trait]
+pub trait AsyncCache: Send {
+    type K: Send + Sync;
+    type V: Send;
+
+    /// Returns all the values associated with the provided keys, in key order.
+    async fn get(&mut self, keys: &[Self::K]) -> Vec<Option<Self::V>>;
+
+    /// Returns the values of expired entries that may still be served. See [`Cache::get_stale`].
+    async fn get_stale(&mut self, keys: &[Self::K], max_stale: Duration) -> Vec<Option<Self::V>> {
+        let _ = max_stale;
+        keys.iter().map(|_| None).collect()
+    }
+
+    /// Stores a value, replacing any value previously stored for the key.
+    async fn insert(&mut self, key: Self::K, value: Self::V);
+
+    /// Stores the values of a completed batch.
+    async fn insert_many(&mut self, key_vals: Vec<(Self::K, Self::V)>);
+
+    /// Removes the values for the keys, if present.
+    async fn remove(&mut self, keys: &[Self::K]);
+
+    /// Returns the number of entries currently held by the cache, as known to this loader.
+    /// Remote caches that cannot cheaply count their entries may return 0.
+    fn len(&self) -> usize;
+
+    fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Removes every entry.
+    async fn flush(&mut self);
+
+    /// Removes the entries for which `keep` returns false. See [`Cache::retain`].
+    async fn retain(
+        &mut self,
+        keep: &mut (dyn for<'k, 'v> FnMut(&'k Self::K, &'v Self::V) -> bool + Send),
+    ) {
+        let _ = keep;
+        tracing::warn!("cache cannot enumerate its entries");
+    }
+
+    /// Returns the keys to reload ahead of their expiry. See [`Cache::refresh_candidates`].
+    async fn refresh_candidates(&mut self, within: Duration) -> Vec<Self::K> {
+        let _ = within;
+        Vec::new()
+    }
+
+    /// Performs periodic maintenance. See [`Cache::sweep`].
+    async fn sweep(&mut self) {}
+
+    /// Applies the supported parameters of `policy`. See [`Cache::reconfigure`].
+    async fn reconfigure(&mut self, policy: &CachePolicy) {
+        let _ = policy;
+    }
+}
+
+#[async_trait]
+impl<C> AsyncCache for C
+where
+    C: Cache + Send,
+    C::K: Send + Sync,
+    C::V: Send,
+{
+    type K = C::K;
+    type V = C::V;
+
+    async fn get(&mut self, keys: &[Self::K]) -> Vec<Option<Self::V>> {
+        Cache::get(self, keys)
+    }
+
+    async fn get_stale(&mut self, keys: &[Self::K], max_stale: Duration) -> Vec<Option<Self::V>> {
+        Cache::get_stale(self, keys, max_stale)
+    }
+
+    async fn insert(&mut self, key: Self::K, value: Self::V) {
+        Cache::insert(self, key, value);
+    }
+
+    async fn insert_many(&mut self, key_vals: Vec<(Self::K, Self::V)>) {
+        Cache::insert_many(self, key_vals);
+    }
+
+    async fn remove(&mut self, keys: &[Self::K]) {
+        Cache::remove(self, keys);
+    }
+
+    fn len(&self) -> usize {
+        Cache::len(self)
+    }
+
+    async fn flush(&mut self) {
+        Cache::flush(self);
+    }
+
+    async fn retain(
+        &mut self,
+        keep: &mut (dyn for<'k, 'v> FnMut(&'k Self::K, &'v Self::V) -> bool + Send),
+    ) {
+        Cache::retain(self, keep);
+    }
+
+    async fn refresh_candidates(&mut self, within: Duration) -> Vec<Self::K> {
+        Cache::refresh_candidates(self, within)
+    }
+
+    async fn sweep(&mut self) {
+        Cache::sweep(self);
+    }
+
+    async fn reconfigure(&mut self, policy: &CachePolicy) {
+        Cache::reconfigure(self, policy);
+    }
+}
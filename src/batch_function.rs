@@ -1,20 +1,184 @@
+use std::any::Any;
+use std::collections::{BTreeMap, BTreeSet};
+use std::error::Error;
+use std::future::Future;
+use std::marker::PhantomData;
+use std::sync::{Arc, Mutex};
+
 use async_trait::async_trait;
 
+use crate::{
+    cancellation::CancellationToken,
+    error::BackendError,
+    receipt::FrameId,
+    resources::{BatchResources, Lease},
+};
+
 /// A `BatchFunction` defines the method through which some `Loader` may fetch
 /// batched data from some resource. The `BatchFunction` receives a slice of keys
 /// that have been requested during the `Loader`'s most recent execution frame, and some user
 /// defined context struct.
 ///
+/// The `BatchFunction` passed to the loader constructors is retained by the loader's worker, so
+/// that it can carry instance state (e.g. a connection pool or its configuration). Loaders
+/// started with [`crate::Loader::split_isolated`] share it with the original loader.
+///
 /// Unlike the reference facebook dataloader implementation, the BatchFunction is not required to
 /// return a result for all keys that were provided. Instead, it can return any set of loaded key
 /// value pairs, in any order it chooses. Requesters of keys whose values are not returned by the
-/// `BatchFunction` will receive a `None`. Error handling and reporting is expected to be done
-/// within the BatchFunction (i.e. through some error sink in the context).
+/// `BatchFunction` will receive a `None`. Failures can be reported to the requesters through
+/// [`BatchInfo::fail`], which callers observe through [`crate::Loader::try_load`] and the detailed
+/// load APIs.
 ///
 /// Multiple `BatchFunctions` (and therefore loaders) can share the same context (likely through an
 /// `Arc`).
 #[async_trait]
-pub trait BatchFunction<K, V> {
+pub trait BatchFunction<K, V>: Send + Sync {
     type Context;
-    async fn load(keys: &[K], context: &Self::Context) -> Vec<(K, V)>;
+    /// The collection of loaded pairs, e.g. `Vec<(K, V)>`, or `HashMap<K, V>` for
+    /// implementations that naturally build maps.
+    type Loaded: IntoIterator<Item = (K, V)> + Send;
+
+    async fn load(&self, keys: &[K], context: &Self::Context) -> Self::Loaded;
+
+    /// Variant of [`BatchFunction::load`] that additionally receives a [`BatchInfo`] describing the
+    /// execution frame. The worker always invokes this method; the default implementation ignores
+    /// the info and delegates to `load`.
+    async fn load_with_info(
+        &self,
+        keys: &[K],
+        context: &Self::Context,
+        info: BatchInfo<K, V>,
+    ) -> Self::Loaded
+    where
+        K: Send + Sync + 'static,
+        V: Send + 'static,
+        Self::Context: Sync,
+    {
+        let _ = info;
+        self.load(keys, context).await
+    }
+}
+
+/// A [`BatchFunction`] defined by an async closure, which receives the requested keys and a clone
+/// of the context. Created by [`crate::Loader::from_fn`], or with [`FnBatch::new`] to configure
+/// the loader through [`crate::Loader::builder`].
+pub struct FnBatch<F, ContextT>(F, PhantomData<fn(ContextT)>);
+
+impl<F, ContextT> FnBatch<F, ContextT> {
+    pub fn new(load: F) -> Self {
+        Self(load, PhantomData)
+    }
+}
+
+#[async_trait]
+impl<K, V, F, Fut, ContextT> BatchFunction<K, V> for FnBatch<F, ContextT>
+where
+    K: Clone + Send + Sync + 'static,
+    F: Fn(Vec<K>, ContextT) -> Fut + Send + Sync,
+    Fut: Future + Send,
+    Fut::Output: IntoIterator<Item = (K, V)> + Send,
+    ContextT: Clone + Send + Sync,
+{
+    type Context = ContextT;
+    type Loaded = Fut::Output;
+
+    async fn load(&self, keys: &[K], context: &ContextT) -> Fut::Output {
+        (self.0)(keys.to_vec(), context.clone()).await
+    }
+}
+
+/// Information about the execution frame in which a [`BatchFunction`] is invoked.
+#[derive(Debug)]
+pub struct BatchInfo<K, V> {
+    frame: FrameId,
+    cached_neighbors: BTreeMap<K, V>,
+    cancellation: CancellationToken,
+    outcome: Arc<Mutex<BatchOutcome<K>>>,
+    /// A `Lease` from the loader's resource pool, if one is attached.
+    resource: Option<Box<dyn Any + Send>>,
+}
+
+/// What a `BatchFunction` reported through its [`BatchInfo`], besides the values it returned.
+#[derive(Debug)]
+pub(crate) struct BatchOutcome<K> {
+    pub filtered: BTreeSet<K>,
+    pub error: Option<BackendError>,
+}
+
+impl<K> Default for BatchOutcome<K> {
+    fn default() -> Self {
+        Self { filtered: BTreeSet::new(), error: None }
+    }
+}
+
+impl<K: Ord, V> BatchInfo<K, V> {
+    pub(crate) fn new(
+        frame: FrameId,
+        cached_neighbors: BTreeMap<K, V>,
+        cancellation: CancellationToken,
+        outcome: Arc<Mutex<BatchOutcome<K>>>,
+        resource: Option<Box<dyn Any + Send>>,
+    ) -> Self {
+        Self { frame, cached_neighbors, cancellation, outcome, resource }
+    }
+
+    /// Returns the resource leased for this batch from the pool attached with
+    /// [`crate::LoaderBuilder::batch_resources`], or `None` if the loader has no pool of type
+    /// `R`.
+    ///
+    /// The resource is returned to the pool when the `BatchInfo` is dropped.
+    pub fn resource<R: BatchResources>(&mut self) -> Option<&mut R::Resource> {
+        let lease = self.resource.as_mut()?.downcast_mut::<Lease<R>>()?;
+        Some(&mut **lease)
+    }
+
+    /// Reports that the batch failed (e.g. because the database was unreachable).
+    ///
+    /// Requested keys for which no value was returned fail with [`crate::BatchError::Backend`]
+    /// through the detailed APIs and [`crate::Loader::try_load`], instead of resolving as
+    /// missing. Values that were returned are still delivered and cached. If called more than
+    /// once, the last error is reported.
+    pub fn fail<E>(&self, error: E)
+    where
+        E: Into<Box<dyn Error + Send + Sync>>,
+    {
+        self.outcome.lock().unwrap().error = Some(BackendError::new(error));
+    }
+
+    /// Marks a requested key as deliberately withheld (e.g. soft-deleted or unauthorized), as
+    /// opposed to simply absent.
+    ///
+    /// Filtered keys resolve to `None` through the plain load APIs, and fail with
+    /// [`crate::FailureReason::Filtered`] rather than [`crate::FailureReason::Missing`] through
+    /// [`crate::Loader::load_many_detailed`]. Values returned for filtered keys are discarded, and
+    /// nothing is cached for them.
+    pub fn filter(&self, key: K) {
+        self.outcome.lock().unwrap().filtered.insert(key);
+    }
+
+    /// The execution frame being served, as reported to callers through
+    /// [`crate::LoadReceipt::frame`].
+    pub fn frame(&self) -> FrameId {
+        self.frame
+    }
+
+    /// Token that is cancelled if the worker abandons this invocation (see
+    /// [`crate::LoaderBuilder::batch_timeout`]).
+    pub fn cancellation(&self) -> &CancellationToken {
+        &self.cancellation
+    }
+
+    /// Returns the cached value of a neighbor of the requested keys, as determined by the
+    /// neighborhood function configured through [`crate::LoaderBuilder::neighbors`].
+    ///
+    /// The values are a read-only snapshot of the cache taken when the frame was dispatched.
+    pub fn cached(&self, key: &K) -> Option<&V> {
+        self.cached_neighbors.get(key)
+    }
+
+    /// Iterates over all cached neighbors in key order.
+    pub fn cached_neighbors(&self) -> impl Iterator<Item = (&K, &V)> {
+        self.cached_neighbors.iter()
+    }
 }
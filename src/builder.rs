@@ -0,0 +1,708 @@
+use std::collections::HashMap;
+use std::fmt::Debug;
+use std::hash::Hash;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use tokio::sync::mpsc;
+
+use crate::{
+    async_cache::AsyncCache,
+    batch_function::BatchFunction,
+    cache::{LruCache, TtlCache},
+    group::GroupLoader,
+    health::HealthGauges,
+    in_flight::InFlightStore,
+    invalidation::InvalidationSource,
+    isolation::IsolationGroup,
+    loader::Loader,
+    loader_op::LoaderOp,
+    loader_worker::LoaderWorker,
+    observer::{Limit, LoaderObserver},
+    policy::{
+        ClosedPolicy, CostFn, DedupStrategy, DispatchStrategy, DropPolicy, NeighborsFn,
+        OverflowPolicy, PrimePolicy, ResolutionOrder, TraceSampling, TransformFn,
+        UnrequestedKeysPolicy, VersionFn,
+    },
+    prefetch::{PrefetchKey, MAX_PREFETCH_WINDOW},
+    pump::LoaderPump,
+    resources::{BatchResources, LeaseSource, ResourcePool},
+    sampler::{BatchSample, SampleFn},
+    scoped::{Scoped, ScopedLoader},
+    snapshot::CacheSnapshot,
+    versioned::Versioned,
+};
+
+/// Tunable behavior shared between a `Loader` and its `LoaderWorker`.
+#[derive(Clone)]
+pub(crate) struct LoaderOptions<K, V> {
+    /// Identifies the loader in spans, stats and logs, instead of its key and value types.
+    pub name: Option<&'static str>,
+    pub prime_policy: PrimePolicy<V>,
+    pub version_of: Option<VersionFn<V>>,
+    /// Applied in order to loaded and primed values.
+    pub transforms: Vec<TransformFn<K, V>>,
+    pub max_pending_requests: Option<usize>,
+    pub max_staged_keys: Option<usize>,
+    /// Maximum total cost of the keys staged in a frame, and the per-key cost estimate.
+    pub cost_budget: Option<(u64, CostFn<K>)>,
+    pub overflow_policy: OverflowPolicy,
+    pub neighbors: Option<NeighborsFn<K>>,
+    pub prefetch: Option<NeighborsFn<K>>,
+    pub closed_policy: ClosedPolicy,
+    pub drop_policy: DropPolicy,
+    pub urgent_deadline_threshold: Option<Duration>,
+    pub observer: Option<Arc<dyn LoaderObserver>>,
+    pub soft_limits: Vec<(Limit, usize)>,
+    pub batch_timeout: Option<Duration>,
+    pub dedup_strategy: DedupStrategy,
+    pub unrequested_keys: UnrequestedKeysPolicy,
+    pub dispatch_strategy: DispatchStrategy,
+    pub resolution_order: ResolutionOrder,
+    /// Minimum hit rate, and the number of requested keys over which it is measured.
+    pub adaptive_caching: Option<(f64, u64)>,
+    /// Number of cold frames, their maximum batch size, and the delay between their batches.
+    pub cold_start: Option<(u64, usize, Duration)>,
+    pub negative_caching: Option<Duration>,
+    /// How long before their expiry hot entries are reloaded.
+    pub refresh_ahead: Option<Duration>,
+    /// How long after their expiry entries may be served while they are reloaded.
+    pub stale_while_revalidate: Option<Duration>,
+    /// Fraction of batches to sample, and the callback receiving them.
+    pub sampling: Option<(f64, SampleFn<K, V>)>,
+    pub in_flight: Option<Arc<dyn InFlightStore<K, V>>>,
+    pub invalidation_source: Option<Arc<dyn InvalidationSource<K>>>,
+    pub resources: Option<Arc<dyn LeaseSource>>,
+    pub isolation_group: Option<IsolationGroup>,
+    pub max_concurrent_batches: usize,
+    pub trace_sampling: TraceSampling,
+    #[cfg(feature = "fault-injection")]
+    pub faults: Option<Arc<crate::faults::Faults>>,
+}
+
+impl<K, V> Default for LoaderOptions<K, V> {
+    fn default() -> Self {
+        Self {
+            name: None,
+            prime_policy: PrimePolicy::default(),
+            version_of: None,
+            transforms: Vec::new(),
+            max_pending_requests: None,
+            max_staged_keys: None,
+            cost_budget: None,
+            overflow_policy: OverflowPolicy::default(),
+            neighbors: None,
+            prefetch: None,
+            closed_policy: ClosedPolicy::default(),
+            drop_policy: DropPolicy::default(),
+            urgent_deadline_threshold: None,
+            observer: None,
+            soft_limits: Vec::new(),
+            batch_timeout: None,
+            dedup_strategy: DedupStrategy::default(),
+            unrequested_keys: UnrequestedKeysPolicy::default(),
+            dispatch_strategy: DispatchStrategy::default(),
+            resolution_order: ResolutionOrder::default(),
+            adaptive_caching: None,
+            cold_start: None,
+            negative_caching: None,
+            refresh_ahead: None,
+            stale_while_revalidate: None,
+            sampling: None,
+            in_flight: None,
+            invalidation_source: None,
+            resources: None,
+            isolation_group: None,
+            max_concurrent_batches: 1,
+            trace_sampling: TraceSampling::default(),
+            #[cfg(feature = "fault-injection")]
+            faults: None,
+        }
+    }
+}
+
+/// Configures and constructs a [`Loader`].
+///
+/// Created through [`Loader::builder`]. Options that are not explicitly set retain the behavior of
+/// [`Loader::new`].
+pub struct LoaderBuilder<K, V, F, ContextT, CacheT = HashMap<K, V>> {
+    batch_fn: F,
+    context: ContextT,
+    cache: CacheT,
+    options: LoaderOptions<K, V>,
+    /// Entries imported by every worker before it processes any other op.
+    restore: Vec<(K, V)>,
+    /// Pairs primed by every worker once the restored entries are imported.
+    warm: Vec<(K, V)>,
+}
+
+impl<K, V, F, ContextT> LoaderBuilder<K, V, F, ContextT>
+where
+    K: 'static + Eq + Debug + Ord + Clone + Hash + Send + Sync,
+    V: 'static + Send + Debug + Clone,
+    ContextT: Send + Sync + 'static,
+    F: 'static + BatchFunction<K, V, Context = ContextT> + Send,
+{
+    pub(crate) fn new(batch_fn: F, context: ContextT) -> Self {
+        Self {
+            batch_fn,
+            context,
+            cache: HashMap::new(),
+            options: LoaderOptions::default(),
+            restore: Vec::new(),
+            warm: Vec::new(),
+        }
+    }
+}
+
+impl<K, V, F, ContextT, CacheT> LoaderBuilder<K, V, F, ContextT, CacheT>
+where
+    K: 'static + Eq + Debug + Ord + Clone + Send + Sync,
+    V: 'static + Send + Debug + Clone,
+    ContextT: Send + Sync + 'static,
+    F: 'static + BatchFunction<K, V, Context = ContextT> + Send,
+    CacheT: 'static + AsyncCache<K = K, V = V> + Send,
+{
+    /// Replaces the loader's backing cache, which defaults to an unbounded `HashMap`. Accepts any
+    /// [`crate::Cache`], or an [`AsyncCache`] for caches that live outside the process.
+    ///
+    /// With the `cached` feature enabled, the `SizedCache` and `TimedCache` stores from the
+    /// `cached` crate can be used here.
+    pub fn cache<C>(self, cache: C) -> LoaderBuilder<K, V, F, ContextT, C>
+    where
+        C: 'static + AsyncCache<K = K, V = V> + Send,
+    {
+        LoaderBuilder {
+            batch_fn: self.batch_fn,
+            context: self.context,
+            cache,
+            options: self.options,
+            restore: self.restore,
+            warm: self.warm,
+        }
+    }
+
+    /// Replaces the loader's backing cache with an [`LruCache`] holding at most `max_entries`
+    /// entries.
+    pub fn lru_cache(self, max_entries: usize) -> LoaderBuilder<K, V, F, ContextT, LruCache<K, V>>
+    where
+        K: Hash,
+    {
+        self.cache(LruCache::new(max_entries))
+    }
+
+    /// Replaces the loader's backing cache with a [`TtlCache`] whose entries expire `ttl` after
+    /// they were inserted.
+    pub fn ttl_cache(self, ttl: Duration) -> LoaderBuilder<K, V, F, ContextT, TtlCache<K, V>>
+    where
+        K: Hash,
+    {
+        self.cache(TtlCache::new(ttl))
+    }
+
+    /// Sets how primes for keys that are already cached are resolved.
+    pub fn prime_policy(mut self, prime_policy: PrimePolicy<V>) -> Self {
+        self.options.prime_policy = prime_policy;
+        self
+    }
+
+    /// Sets the version extractor consulted when values returned by the `BatchFunction` are
+    /// inserted into the cache. A loaded value never overwrites a cached value with a strictly
+    /// greater version.
+    pub fn version_of<VF>(mut self, version_of: VF) -> Self
+    where
+        VF: Fn(&V) -> u64 + Send + Sync + 'static,
+    {
+        self.options.version_of = Some(Arc::new(version_of));
+        self
+    }
+
+    /// Appends `transform` to the pipeline applied to values before they are cached (e.g. to
+    /// redact or normalize fields), so that the same transformations apply whether a value was
+    /// returned by the `BatchFunction` or primed.
+    ///
+    /// Transforms run in the order they were added, before the [`PrimePolicy`] and version checks.
+    /// Batch samples contain the values as returned by the `BatchFunction`.
+    pub fn transform_values<TF>(mut self, transform: TF) -> Self
+    where
+        TF: Fn(&K, V) -> V + Send + Sync + 'static,
+    {
+        self.options.transforms.push(Arc::new(transform));
+        self
+    }
+
+    /// Caps the number of load requests staged in a single execution frame.
+    ///
+    /// Requests beyond the cap are handled according to the [`OverflowPolicy`]. A cap of 0 is
+    /// treated as 1.
+    pub fn max_pending_requests(mut self, max_pending_requests: usize) -> Self {
+        self.options.max_pending_requests = Some(max_pending_requests.max(1));
+        self
+    }
+
+    /// Caps the number of keys (not necessarily unique) staged in a single execution frame.
+    ///
+    /// The cap is checked before each request is staged, so a single request with many keys may
+    /// still exceed it. Requests beyond the cap are handled according to the [`OverflowPolicy`].
+    /// A cap of 0 is treated as 1.
+    pub fn max_staged_keys(mut self, max_staged_keys: usize) -> Self {
+        self.options.max_staged_keys = Some(max_staged_keys.max(1));
+        self
+    }
+
+    /// Caps the total estimated cost of the keys staged in a single execution frame, so that
+    /// batches of expensive keys (e.g. huge documents) are split across frames rather than sized
+    /// by key count alone.
+    ///
+    /// Like [`LoaderBuilder::max_staged_keys`], the budget is checked before each request is
+    /// staged, so a single request whose keys cost more than the budget is still loaded. Requests
+    /// beyond the budget are handled according to the [`OverflowPolicy`].
+    pub fn batch_cost_budget<CF>(mut self, budget: u64, cost_of: CF) -> Self
+    where
+        CF: Fn(&K) -> u64 + Send + Sync + 'static,
+    {
+        self.options.cost_budget = Some((budget, Arc::new(cost_of)));
+        self
+    }
+
+    /// Sets how load requests beyond the frame caps are handled.
+    pub fn overflow_policy(mut self, overflow_policy: OverflowPolicy) -> Self {
+        self.options.overflow_policy = overflow_policy;
+        self
+    }
+
+    /// Sets the neighborhood function used to build the cache snapshot passed to the
+    /// `BatchFunction` through [`crate::BatchInfo`].
+    ///
+    /// For each staged key, the worker includes every neighbor returned by this function that is
+    /// present in the cache.
+    pub fn neighbors<NF>(mut self, neighbors: NF) -> Self
+    where
+        NF: Fn(&K) -> Vec<K> + Send + Sync + 'static,
+    {
+        self.options.neighbors = Some(Arc::new(neighbors));
+        self
+    }
+
+    /// Stages the keys within `window` steps of each requested key that misses the cache along
+    /// with it, so that sequential access patterns need fewer batches.
+    ///
+    /// Prefetched keys that are already cached or staged are skipped. Their values are cached but
+    /// not returned to any caller. The window is capped at 256 keys on either side, and prefetched
+    /// keys are only staged while the frame is below [`LoaderBuilder::max_staged_keys`].
+    pub fn prefetch_window(mut self, window: usize) -> Self
+    where
+        K: PrefetchKey,
+    {
+        let window = window.min(MAX_PREFETCH_WINDOW);
+        self.options.prefetch = Some(Arc::new(move |key: &K| key.window(window)));
+        self
+    }
+
+    /// Names the loader in its worker's spans, stats, metrics and log messages, which otherwise
+    /// identify it by its key and value types. Useful to tell apart loaders sharing those types
+    /// (e.g. `"users_by_id"` and `"users_by_email_hash"`).
+    pub fn name(mut self, name: &'static str) -> Self {
+        self.options.name = Some(name);
+        self
+    }
+
+    /// Sets how the loader behaves if its worker stops running.
+    pub fn closed_policy(mut self, closed_policy: ClosedPolicy) -> Self {
+        self.options.closed_policy = closed_policy;
+        self
+    }
+
+    /// Sets what happens to the worker once every clone of the loader has been dropped.
+    pub fn drop_policy(mut self, drop_policy: DropPolicy) -> Self {
+        self.options.drop_policy = drop_policy;
+        self
+    }
+
+    /// Dispatches load requests whose deadlines fall within `threshold` of the frame's dispatch
+    /// time in their own frame, ahead of the remaining requests.
+    ///
+    /// Keeps nearly-expired requests from waiting on a large batch assembled around them.
+    pub fn urgent_deadline_threshold(mut self, threshold: Duration) -> Self {
+        self.options.urgent_deadline_threshold = Some(threshold);
+        self
+    }
+
+    /// Bounds how long the worker waits on a single `BatchFunction` invocation.
+    ///
+    /// When the timeout elapses, the invocation's [`crate::CancellationToken`] is cancelled, its
+    /// future is dropped, and the frame's pending requests resolve as missing (or with
+    /// [`crate::BatchError::Timeout`] through the detailed APIs).
+    pub fn batch_timeout(mut self, timeout: Duration) -> Self {
+        self.options.batch_timeout = Some(timeout);
+        self
+    }
+
+    /// Sets how the staged keys are ordered and deduplicated before being passed to the
+    /// `BatchFunction`.
+    pub fn dedup_strategy(mut self, dedup_strategy: DedupStrategy) -> Self {
+        self.options.dedup_strategy = dedup_strategy;
+        self
+    }
+
+    /// Sets how pairs returned by the `BatchFunction` for keys it was not asked to load are
+    /// handled.
+    pub fn unrequested_keys(mut self, policy: UnrequestedKeysPolicy) -> Self {
+        self.options.unrequested_keys = policy;
+        self
+    }
+
+    /// Sets when staged requests are dispatched to the `BatchFunction`.
+    pub fn dispatch_strategy(mut self, dispatch_strategy: DispatchStrategy) -> Self {
+        self.options.dispatch_strategy = dispatch_strategy;
+        self
+    }
+
+    /// Collects requests for up to `delay` before dispatching each frame. Shorthand for
+    /// [`DispatchStrategy::Delay`].
+    pub fn dispatch_after(self, delay: Duration) -> Self {
+        self.dispatch_strategy(DispatchStrategy::Delay(delay))
+    }
+
+    /// Holds each frame until at least `min_keys` keys are staged, or until `max_wait` has
+    /// elapsed. Shorthand for [`DispatchStrategy::MinBatchSize`].
+    pub fn min_batch_size(self, min_keys: usize, max_wait: Duration) -> Self {
+        self.dispatch_strategy(DispatchStrategy::MinBatchSize(min_keys, max_wait))
+    }
+
+    /// Sets the order in which the requests served by a batch are resolved.
+    pub fn resolution_order(mut self, resolution_order: ResolutionOrder) -> Self {
+        self.options.resolution_order = resolution_order;
+        self
+    }
+
+    /// Remembers keys that the `BatchFunction` did not return for `ttl`, resolving them to `None`
+    /// without loading them again until the entry expires.
+    ///
+    /// Keys that were filtered, or that failed because of a [`crate::BatchError`], are not
+    /// remembered. Priming or clearing a key forgets its miss.
+    pub fn negative_caching(mut self, ttl: Duration) -> Self {
+        self.options.negative_caching = Some(ttl);
+        self
+    }
+
+    /// Reloads cached entries that were read since they were loaded and that expire within
+    /// `within`, so that hot keys are refreshed in the background rather than missing the cache
+    /// once they expire.
+    ///
+    /// The worker looks for such entries at the end of every frame and loads them together on a
+    /// batch of their own, or along with the next frame's keys when requests are already staged.
+    /// Only caches whose entries expire, such as [`TtlCache`], report entries to refresh (see
+    /// [`crate::Cache::refresh_candidates`]).
+    pub fn refresh_ahead(mut self, within: Duration) -> Self {
+        self.options.refresh_ahead = Some(within);
+        self
+    }
+
+    /// Resolves load requests whose missed keys all have entries that expired no more than
+    /// `max_stale` ago with those stale values immediately, while the keys are reloaded in the
+    /// background, trading strict freshness for latency.
+    ///
+    /// Requests with any key that has no stale value wait for the reload as usual. Only caches
+    /// whose entries expire, such as [`TtlCache`], hold stale values (see
+    /// [`crate::Cache::get_stale`]); the loader configures them to hold expired entries for
+    /// `max_stale` through [`crate::CachePolicy::with_stale_window`].
+    pub fn stale_while_revalidate(mut self, max_stale: Duration) -> Self {
+        self.options.stale_while_revalidate = Some(max_stale);
+        self
+    }
+
+    /// Stops caching loaded values once the hit rate measured over a window of `window` requested
+    /// keys falls below `min_hit_rate`, saving memory for loaders whose keys rarely repeat (e.g.
+    /// random UUIDs). Batching is unaffected.
+    ///
+    /// The hit rate is measured over consecutive, non-overlapping windows. Once caching is
+    /// disabled it stays disabled for the lifetime of the worker; values already cached, and
+    /// values added through primes, continue to be served. The switch is logged, reported through
+    /// [`LoaderObserver::on_caching_disabled`] and [`crate::LoaderHealth::caching_disabled`].
+    pub fn adaptive_caching(mut self, min_hit_rate: f64, window: u64) -> Self {
+        self.options.adaptive_caching = Some((min_hit_rate, window.max(1)));
+        self
+    }
+
+    /// Smooths the load on the backend while the cache is cold, e.g. right after a deploy.
+    ///
+    /// The first `frames` batches are capped at `max_batch_size` keys. Keys staged beyond the cap
+    /// are loaded in subsequent batches, spaced `delay` apart, and the frame's requests are
+    /// resolved once the last of them completes. Smoothing is skipped once caching has been
+    /// disabled by [`LoaderBuilder::adaptive_caching`].
+    pub fn cold_start_smoothing(
+        mut self,
+        frames: u64,
+        max_batch_size: usize,
+        delay: Duration,
+    ) -> Self {
+        self.options.cold_start = Some((frames, max_batch_size.max(1), delay));
+        self
+    }
+
+    /// Passes a fraction of batches, along with the values the `BatchFunction` returned for them,
+    /// to `callback` (e.g. to run data-quality or schema-drift checks on production results).
+    ///
+    /// Batches are selected evenly: a `fraction` of 0.1 samples every tenth batch. The callback
+    /// runs on a separate task, off the worker's hot path; if it falls behind, samples are dropped
+    /// rather than delaying the worker.
+    pub fn sample_batches<SF>(mut self, fraction: f64, callback: SF) -> Self
+    where
+        SF: Fn(BatchSample<K, V>) + Send + Sync + 'static,
+    {
+        self.options.sampling = Some((fraction, Arc::new(callback)));
+        self
+    }
+
+    /// Shares in-flight keys with other loaders through `store`, so that keys already being
+    /// fetched by another loader sharing the store are awaited rather than fetched again.
+    pub fn in_flight_store(mut self, store: Arc<dyn InFlightStore<K, V>>) -> Self {
+        self.options.in_flight = Some(store);
+        self
+    }
+
+    /// Clears the keys yielded by `source` (e.g. a pub/sub channel shared by the replicas of a
+    /// service), which the worker consumes alongside its op queue.
+    ///
+    /// Each worker subscribes to the source once, so loaders created with
+    /// [`Loader::split_isolated`] receive the invalidations too. Dependents registered with
+    /// [`Loader::add_dependent`] are not cleared.
+    pub fn invalidation_source(mut self, source: Arc<dyn InvalidationSource<K>>) -> Self {
+        self.options.invalidation_source = Some(source);
+        self
+    }
+
+    /// Fills the loader's cache with the entries of `snapshot` (see [`Loader::export_cache`])
+    /// before any load is processed, so that a restarted process starts warm.
+    ///
+    /// Loaders created with [`Loader::split_isolated`] start from the same snapshot.
+    pub fn restore(mut self, snapshot: CacheSnapshot<K, V>) -> Self {
+        self.restore = snapshot.entries;
+        self
+    }
+
+    /// Primes the loader's cache with `key_vals` before any load is processed, so that reference
+    /// data already in memory does not have to be fetched by a thundering first frame.
+    ///
+    /// The pairs are primed like [`Loader::prime_many`], after any snapshot given to
+    /// [`LoaderBuilder::restore`], and loaders created with [`Loader::split_isolated`] are warmed
+    /// with them too. Calling this again adds more pairs.
+    pub fn warm_with<I>(mut self, key_vals: I) -> Self
+    where
+        I: IntoIterator<Item = (K, V)>,
+    {
+        self.warm.extend(key_vals);
+        self
+    }
+
+    /// Attaches fault points that tests can arm to simulate internal failures of the worker.
+    #[cfg(feature = "fault-injection")]
+    pub fn faults(mut self, faults: Arc<crate::faults::Faults>) -> Self {
+        self.options.faults = Some(faults);
+        self
+    }
+
+    /// Leases a resource from `pool` to each batch, available to the `BatchFunction` through
+    /// [`crate::BatchInfo::resource`].
+    pub fn batch_resources<R: BatchResources>(mut self, pool: ResourcePool<R>) -> Self {
+        self.options.resources = Some(Arc::new(pool));
+        self
+    }
+
+    /// Sets how many batches the loader may execute at once, which defaults to one.
+    ///
+    /// While fewer batches are executing, a frame is dispatched as soon as the request queue has
+    /// been drained, instead of once the previous batch completes. To bound concurrent batches
+    /// across loaders (e.g. ones sharing a database pool), add them to an [`IsolationGroup`].
+    pub fn max_concurrent_batches(mut self, max_concurrent_batches: usize) -> Self {
+        self.options.max_concurrent_batches = max_concurrent_batches.max(1);
+        self
+    }
+
+    /// Limits op-level tracing to the frames selected by `trace_sampling`, so that detailed
+    /// tracing can stay enabled for high-throughput loaders at a bounded cost.
+    pub fn trace_sampling(mut self, trace_sampling: TraceSampling) -> Self {
+        self.options.trace_sampling = trace_sampling;
+        self
+    }
+
+    /// Adds the loader to `group`, so that its batches count towards the group's limit on
+    /// concurrent batches. A batch waits for room in the group before it is dispatched.
+    pub fn isolation_group(mut self, group: IsolationGroup) -> Self {
+        self.options.isolation_group = Some(group);
+        self
+    }
+
+    /// Sets the observer notified of notable worker events.
+    pub fn observer(mut self, observer: Arc<dyn LoaderObserver>) -> Self {
+        self.options.observer = Some(observer);
+        self
+    }
+
+    /// Sets a soft threshold for a limit. When the limit's value reaches the threshold, the
+    /// worker logs a warning and notifies the observer via [`LoaderObserver::on_soft_limit`],
+    /// giving an early signal before any corresponding hard limit starts failing requests.
+    pub fn soft_limit(mut self, limit: Limit, threshold: usize) -> Self {
+        self.options.soft_limits.retain(|(l, _)| *l != limit);
+        self.options.soft_limits.push((limit, threshold));
+        self
+    }
+
+    /// Spawns the loader's worker and returns the `Loader`.
+    ///
+    /// The configured cache is retained (before any values are loaded into it) as the template for
+    /// the caches of pipelines created by [`Loader::split_isolated`].
+    pub fn build(self) -> Loader<K, V>
+    where
+        CacheT: Clone,
+    {
+        self.pipeline().spawn()
+    }
+
+    /// Returns the `Loader` along with a [`LoaderPump`] that drives its worker on the calling task,
+    /// instead of spawning the worker.
+    ///
+    /// Loaders created from it with [`Loader::split_isolated`] spawn their workers as usual.
+    pub fn build_with_pump(self) -> (Loader<K, V>, LoaderPump<K, V>)
+    where
+        CacheT: Clone,
+    {
+        self.pipeline().pumped()
+    }
+
+    fn pipeline(self) -> Arc<Pipeline<K, V, F, ContextT, CacheT>> {
+        Arc::new(Pipeline {
+            batch_fn: Arc::new(self.batch_fn),
+            context: Arc::new(self.context),
+            cache: Mutex::new(self.cache),
+            options: self.options,
+            restore: Mutex::new(self.restore),
+            warm: Mutex::new(self.warm),
+        })
+    }
+}
+
+/// Everything needed to spawn a worker, retained by its loaders so that additional isolated
+/// pipelines can be started from the same configuration.
+struct Pipeline<K, V, F, ContextT, CacheT> {
+    batch_fn: Arc<F>,
+    context: Arc<ContextT>,
+    cache: Mutex<CacheT>,
+    options: LoaderOptions<K, V>,
+    restore: Mutex<Vec<(K, V)>>,
+    warm: Mutex<Vec<(K, V)>>,
+}
+
+impl<K, V, F, ContextT, CacheT> Pipeline<K, V, F, ContextT, CacheT>
+where
+    K: 'static + Eq + Debug + Ord + Clone + Send + Sync,
+    V: 'static + Send + Debug + Clone,
+    ContextT: Send + Sync + 'static,
+    F: 'static + BatchFunction<K, V, Context = ContextT> + Send,
+    CacheT: 'static + AsyncCache<K = K, V = V> + Clone + Send,
+{
+    fn spawn(self: Arc<Self>) -> Loader<K, V> {
+        let (tx, rx) = mpsc::unbounded_channel();
+        let health = Arc::new(HealthGauges::default());
+        let worker = self.worker(rx, health.clone());
+        let load_task_handle = tokio::task::spawn(worker.start());
+        // A draining worker exits on its own once the request queue closes.
+        let load_task_handle = match self.options.drop_policy {
+            DropPolicy::Abort => Some(load_task_handle),
+            DropPolicy::Drain => None,
+        };
+        self.loader(tx, health, load_task_handle)
+    }
+
+    fn pumped(self: Arc<Self>) -> (Loader<K, V>, LoaderPump<K, V>) {
+        let (tx, rx) = mpsc::unbounded_channel();
+        let health = Arc::new(HealthGauges::default());
+        let worker = self.worker(rx, health.clone());
+        (self.loader(tx, health, None), LoaderPump::new(Box::new(worker)))
+    }
+
+    fn worker(
+        &self,
+        rx: mpsc::UnboundedReceiver<LoaderOp<K, V>>,
+        health: Arc<HealthGauges>,
+    ) -> LoaderWorker<K, V, F, CacheT, ContextT> {
+        let cache = self.cache.lock().unwrap().clone();
+        LoaderWorker::new(
+            cache,
+            rx,
+            self.batch_fn.clone(),
+            self.context.clone(),
+            self.options.clone(),
+            health,
+        )
+    }
+
+    fn loader(
+        self: Arc<Self>,
+        tx: mpsc::UnboundedSender<LoaderOp<K, V>>,
+        health: Arc<HealthGauges>,
+        load_task_handle: Option<tokio::task::JoinHandle<()>>,
+    ) -> Loader<K, V> {
+        let name = self.options.name.unwrap_or_else(std::any::type_name::<(K, V)>);
+        let closed_policy = self.options.closed_policy;
+        let restore = self.restore.lock().unwrap().clone();
+        if !restore.is_empty() {
+            let _ = health.enqueue(&tx, LoaderOp::ImportCache(restore));
+        }
+        let warm = self.warm.lock().unwrap().clone();
+        if !warm.is_empty() {
+            let _ = health.enqueue(&tx, LoaderOp::PrimeMany(warm));
+        }
+        let isolate = Arc::new(move || self.clone().spawn());
+        Loader::from_parts(tx, load_task_handle, health, name, closed_policy, isolate)
+    }
+}
+
+impl<K, T, F, ContextT, CacheT> LoaderBuilder<K, Versioned<T>, F, ContextT, CacheT>
+where
+    K: 'static + Eq + Debug + Ord + Clone + Send + Sync,
+    T: 'static + Send + Debug + Clone,
+    ContextT: Send + Sync + 'static,
+    F: 'static + BatchFunction<K, Versioned<T>, Context = ContextT> + Send,
+    CacheT: 'static + AsyncCache<K = K, V = Versioned<T>> + Send,
+{
+    /// Resolves both primes and loads of [`Versioned`] values by their version.
+    ///
+    /// Equivalent to configuring [`PrimePolicy::KeepNewestByVersion`] and
+    /// [`LoaderBuilder::version_of`] with [`Versioned::version`].
+    pub fn versioned(self) -> Self {
+        self.prime_policy(PrimePolicy::keep_newest_by_version(Versioned::version))
+            .version_of(Versioned::version)
+    }
+}
+
+impl<K, V, F, ContextT, CacheT> LoaderBuilder<Scoped<K>, V, F, ContextT, CacheT>
+where
+    K: 'static + Eq + Debug + Ord + Clone + Send + Sync,
+    V: 'static + Send + Debug + Clone,
+    ContextT: Send + Sync + 'static,
+    F: 'static + BatchFunction<Scoped<K>, V, Context = ContextT> + Send,
+    CacheT: 'static + AsyncCache<K = Scoped<K>, V = V> + Clone + Send,
+{
+    /// Spawns the loader's worker and returns a [`ScopedLoader`], which combines every key with the
+    /// [`crate::AuthScope`] of the calling task.
+    pub fn build_scoped(self) -> ScopedLoader<K, V> {
+        ScopedLoader::new(self.build())
+    }
+}
+
+impl<K, V, F, ContextT, CacheT> LoaderBuilder<K, Vec<V>, F, ContextT, CacheT>
+where
+    K: 'static + Eq + Debug + Ord + Clone + Send + Sync,
+    V: 'static + Send + Debug + Clone,
+    ContextT: Send + Sync + 'static,
+    F: 'static + BatchFunction<K, Vec<V>, Context = ContextT> + Send,
+    CacheT: 'static + AsyncCache<K = K, V = Vec<V>> + Clone + Send,
+{
+    /// Spawns the loader's worker and returns a [`GroupLoader`], whose callers receive an empty
+    /// `Vec` rather than `None` for keys without rows.
+    pub fn build_grouped(self) -> GroupLoader<K, V> {
+        GroupLoader::from_loader(self.build())
+    }
+}
@@ -1,43 +1,177 @@
-use std::collections::HashMap;
+use std::collections::{BTreeMap, HashMap};
 use std::hash::{BuildHasher, Hash};
+use std::time::{Duration, Instant};
 
+/// Storage backing a [`crate::Loader`]. Implementations are owned and accessed exclusively by the
+/// loader's worker, so they need no internal synchronization.
+///
+/// Implemented for `HashMap` (the default), [`LruCache`] and [`TtlCache`]. Custom implementations
+/// can be supplied with [`crate::Loader::with_cache`] or [`crate::LoaderBuilder::cache`].
 pub trait Cache {
     type K;
     type V;
 
     /// Returns all the values associated with the provided keys in order with their respective
     /// keys.
-    fn get(&self, keys: &[Self::K]) -> Vec<Option<&Self::V>>;
+    ///
+    /// Takes `&mut self` so that implementations may update bookkeeping (recency, expiry) on reads.
+    ///
+    /// Requests served entirely from the cache are answered with the returned values as is, so a
+    /// cache hit costs one clone of each value, which is a plain copy for `Copy` values. Values
+    /// that are expensive to clone can be stored as `Arc<T>` and read with
+    /// [`crate::Loader::load_ref`], which only bumps a reference count.
+    fn get(&mut self, keys: &[Self::K]) -> Vec<Option<Self::V>>;
+
+    /// Returns the values of the provided keys whose entries expired no more than `max_stale`
+    /// ago but are still held, in key order, for serving while they are reloaded. See
+    /// [`crate::LoaderBuilder::stale_while_revalidate`].
+    ///
+    /// The default implementation is for caches whose entries do not expire, and returns no
+    /// values.
+    fn get_stale(&mut self, keys: &[Self::K], max_stale: Duration) -> Vec<Option<Self::V>> {
+        let _ = max_stale;
+        keys.iter().map(|_| None).collect()
+    }
 
     /// Returns key value pairs for the requested keys.
-    fn get_key_vals<'cache, 'a>(
-        &'cache self,
-        keys: &'a [Self::K],
-    ) -> Vec<(&'a Self::K, Option<&'cache Self::V>)>;
+    fn get_key_vals<'a>(&mut self, keys: &'a [Self::K]) -> Vec<(&'a Self::K, Option<Self::V>)> {
+        keys.iter().zip(self.get(keys)).collect::<Vec<_>>()
+    }
 
+    /// Stores a value, replacing any value previously stored for the key.
     fn insert(&mut self, key: Self::K, value: Self::V);
+
+    /// Stores the values of a completed batch.
     fn insert_many<I: IntoIterator<Item = (Self::K, Self::V)>>(&mut self, key_vals: I);
 
+    /// Removes the values for the keys, if present.
     fn remove(&mut self, keys: &[Self::K]);
+
+    /// Returns the number of entries currently held by the cache.
+    fn len(&self) -> usize;
+
+    fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Removes every entry.
     fn flush(&mut self);
+
+    /// Removes the entries for which `keep` returns false. See [`crate::Loader::clear_matching`].
+    /// Also used to enumerate the entries for [`crate::Loader::export_cache`].
+    ///
+    /// The default implementation is for caches that cannot enumerate their entries: it logs a
+    /// warning and keeps every entry.
+    fn retain(&mut self, keep: &mut dyn FnMut(&Self::K, &Self::V) -> bool) {
+        let _ = keep;
+        tracing::warn!("cache cannot enumerate its entries");
+    }
+
+    /// Returns the keys of the entries that were read since they were inserted and that expire
+    /// within `within`, for the worker to reload ahead of their expiry. See
+    /// [`crate::LoaderBuilder::refresh_ahead`].
+    ///
+    /// An entry is returned once per read, so that a key the `BatchFunction` no longer returns is
+    /// not reloaded every frame until it expires.
+    ///
+    /// The default implementation is for caches whose entries do not expire, and returns no keys.
+    fn refresh_candidates(&mut self, within: Duration) -> Vec<Self::K> {
+        let _ = within;
+        Vec::new()
+    }
+
+    /// Performs periodic maintenance, such as dropping expired entries. Called by the worker at
+    /// the end of every frame, so implementations should return quickly when there is nothing to
+    /// do.
+    fn sweep(&mut self) {}
+
+    /// Applies the parameters set in `policy` that the implementation supports, leaving the
+    /// others unchanged. See [`crate::Loader::reconfigure`].
+    fn reconfigure(&mut self, policy: &CachePolicy) {
+        let _ = policy;
+    }
+}
+
+/// Cache parameters that can be changed while a loader is running, through
+/// [`crate::Loader::reconfigure`].
+///
+/// Parameters that are not set are left unchanged, as are parameters the loader's cache does not
+/// support.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct CachePolicy {
+    ttl: Option<Duration>,
+    max_entries: Option<usize>,
+    negative_ttl: Option<Option<Duration>>,
+    stale_window: Option<Duration>,
+}
+
+impl CachePolicy {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Sets how long entries remain valid after they were inserted. Applies to entries already
+    /// in the cache.
+    pub fn with_ttl(mut self, ttl: Duration) -> Self {
+        self.ttl = Some(ttl);
+        self
+    }
+
+    /// Sets the maximum number of entries. Excess entries are evicted immediately.
+    pub fn with_max_entries(mut self, max_entries: usize) -> Self {
+        self.max_entries = Some(max_entries);
+        self
+    }
+
+    /// Enables negative caching with the given TTL, or changes its TTL. See
+    /// [`crate::LoaderBuilder::negative_caching`]. Applies to misses recorded from now on.
+    pub fn with_negative_caching(mut self, ttl: Duration) -> Self {
+        self.negative_ttl = Some(Some(ttl));
+        self
+    }
+
+    /// Disables negative caching, forgetting every remembered miss.
+    pub fn without_negative_caching(mut self) -> Self {
+        self.negative_ttl = Some(None);
+        self
+    }
+
+    /// Sets how long expired entries are held, for serving by
+    /// [`crate::LoaderBuilder::stale_while_revalidate`], before they are evicted.
+    pub fn with_stale_window(mut self, window: Duration) -> Self {
+        self.stale_window = Some(window);
+        self
+    }
+
+    /// Returns `Some` if the policy changes negative caching, holding the new TTL or `None` if
+    /// negative caching is disabled.
+    pub fn negative_ttl(&self) -> Option<Option<Duration>> {
+        self.negative_ttl
+    }
+
+    pub fn ttl(&self) -> Option<Duration> {
+        self.ttl
+    }
+
+    pub fn max_entries(&self) -> Option<usize> {
+        self.max_entries
+    }
+
+    pub fn stale_window(&self) -> Option<Duration> {
+        self.stale_window
+    }
 }
 
 impl<K, V, S: BuildHasher> Cache for HashMap<K, V, S>
 where
     K: Eq + Hash,
+    V: Clone,
 {
     type K = K;
     type V = V;
 
-    fn get(&self, keys: &[Self::K]) -> Vec<Option<&Self::V>> {
-        keys.iter().map(|k| self.get(k)).collect::<Vec<_>>()
-    }
-
-    fn get_key_vals<'cache, 'a>(
-        &'cache self,
-        keys: &'a [Self::K],
-    ) -> Vec<(&'a Self::K, Option<&'cache Self::V>)> {
-        keys.iter().map(|k| (k, self.get(k))).collect::<Vec<_>>()
+    fn get(&mut self, keys: &[Self::K]) -> Vec<Option<Self::V>> {
+        keys.iter().map(|k| HashMap::get(self, k).cloned()).collect::<Vec<_>>()
     }
 
     fn insert(&mut self, key: Self::K, value: Self::V) {
@@ -56,7 +190,292 @@ where
         }
     }
 
+    fn len(&self) -> usize {
+        HashMap::len(self)
+    }
+
     fn flush(&mut self) {
         self.clear();
     }
+
+    fn retain(&mut self, keep: &mut dyn FnMut(&K, &V) -> bool) {
+        HashMap::retain(self, |key, value| keep(key, value));
+    }
+}
+
+/// A cache holding at most a fixed number of entries, evicting the least recently used entry when
+/// full.
+///
+/// Both loads and primes count as uses. Select it with [`crate::LoaderBuilder::lru_cache`] or
+/// [`crate::LoaderBuilder::cache`].
+#[derive(Debug, Clone)]
+pub struct LruCache<K, V> {
+    max_entries: usize,
+    /// Each entry's value and the tick of its last use.
+    entries: HashMap<K, (V, u64)>,
+    /// Keys ordered by the tick of their last use.
+    recency: BTreeMap<u64, K>,
+    tick: u64,
+}
+
+impl<K, V> LruCache<K, V>
+where
+    K: Eq + Hash + Clone,
+{
+    /// Creates a cache holding at most `max_entries` entries (and at least one).
+    pub fn new(max_entries: usize) -> Self {
+        Self {
+            max_entries: max_entries.max(1),
+            entries: HashMap::new(),
+            recency: BTreeMap::new(),
+            tick: 0,
+        }
+    }
+
+    /// Marks `key` as the most recently used entry, returning its value.
+    fn touch(&mut self, key: &K) -> Option<&V> {
+        let (value, last_used) = self.entries.get_mut(key)?;
+        self.recency.remove(last_used);
+        self.tick += 1;
+        *last_used = self.tick;
+        self.recency.insert(self.tick, key.clone());
+        Some(value)
+    }
+
+    /// Evicts the least recently used entries until at most `max_entries` remain.
+    fn evict_excess(&mut self) {
+        while self.entries.len() > self.max_entries {
+            let (_, evicted) = self.recency.pop_first().expect("recency tracks every entry");
+            self.entries.remove(&evicted);
+        }
+    }
+}
+
+impl<K, V> Cache for LruCache<K, V>
+where
+    K: Eq + Hash + Clone,
+    V: Clone,
+{
+    type K = K;
+    type V = V;
+
+    fn get(&mut self, keys: &[K]) -> Vec<Option<V>> {
+        keys.iter().map(|k| self.touch(k).cloned()).collect::<Vec<_>>()
+    }
+
+    fn insert(&mut self, key: K, value: V) {
+        self.tick += 1;
+        if let Some((_, last_used)) = self.entries.insert(key.clone(), (value, self.tick)) {
+            self.recency.remove(&last_used);
+        }
+        self.recency.insert(self.tick, key);
+        self.evict_excess();
+    }
+
+    fn insert_many<I: IntoIterator<Item = (K, V)>>(&mut self, key_vals: I) {
+        for (key, value) in key_vals.into_iter() {
+            Cache::insert(self, key, value);
+        }
+    }
+
+    fn remove(&mut self, keys: &[K]) {
+        for key in keys.iter() {
+            if let Some((_, last_used)) = self.entries.remove(key) {
+                self.recency.remove(&last_used);
+            }
+        }
+    }
+
+    fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    fn flush(&mut self) {
+        self.entries.clear();
+        self.recency.clear();
+    }
+
+    fn retain(&mut self, keep: &mut dyn FnMut(&K, &V) -> bool) {
+        let recency = &mut self.recency;
+        self.entries.retain(|key, (value, last_used)| {
+            let kept = keep(key, value);
+            if !kept {
+                recency.remove(last_used);
+            }
+            kept
+        });
+    }
+
+    fn reconfigure(&mut self, policy: &CachePolicy) {
+        if let Some(max_entries) = policy.max_entries {
+            self.max_entries = max_entries.max(1);
+            self.evict_excess();
+        }
+    }
+}
+
+/// A cache whose entries expire a fixed duration after they were inserted.
+///
+/// Expiry is checked lazily when entries are read. Expired entries that are never read again
+/// continue to count towards [`crate::LoaderHealth::cache_entries`] until they are swept, which
+/// only happens if a sweep interval is configured with [`TtlCache::with_sweep_interval`]. Expired
+/// entries are held for a further stale window (none by default, or the `max_stale` of
+/// [`crate::LoaderBuilder::stale_while_revalidate`]) before they are evicted, so that they can be
+/// served stale. Select it with [`crate::LoaderBuilder::ttl_cache`] or
+/// [`crate::LoaderBuilder::cache`].
+#[derive(Debug, Clone)]
+pub struct TtlCache<K, V> {
+    ttl: Duration,
+    /// Each entry's value, the instant it was inserted, and whether it was read since.
+    entries: HashMap<K, (V, Instant, bool)>,
+    sweep_interval: Option<Duration>,
+    last_sweep: Instant,
+    stale_window: Duration,
+}
+
+impl<K, V> TtlCache<K, V>
+where
+    K: Eq + Hash,
+{
+    /// Creates a cache whose entries expire `ttl` after they were inserted.
+    pub fn new(ttl: Duration) -> Self {
+        Self {
+            ttl,
+            entries: HashMap::new(),
+            sweep_interval: None,
+            last_sweep: Instant::now(),
+            stale_window: Duration::ZERO,
+        }
+    }
+
+    /// Drops all expired entries at the end of the first worker frame after each `interval`.
+    pub fn with_sweep_interval(mut self, interval: Duration) -> Self {
+        self.sweep_interval = Some(interval);
+        self
+    }
+
+    /// Holds expired entries for a further `window` before evicting them, so that they can be
+    /// served stale.
+    pub fn with_stale_window(mut self, window: Duration) -> Self {
+        self.stale_window = window;
+        self
+    }
+
+    /// Returns how long entries are held after they were inserted, or `None` if that overflows,
+    /// in which case they are held until they are reloaded, cleared or evicted.
+    fn held_for(&self) -> Option<Duration> {
+        self.ttl.checked_add(self.stale_window)
+    }
+}
+
+impl<K, V> Cache for TtlCache<K, V>
+where
+    K: Eq + Hash + Clone,
+    V: Clone,
+{
+    type K = K;
+    type V = V;
+
+    fn get(&mut self, keys: &[K]) -> Vec<Option<V>> {
+        let (ttl, held_for) = (self.ttl, self.held_for());
+        keys.iter()
+            .map(|k| match self.entries.get_mut(k) {
+                Some((value, inserted_at, read)) if inserted_at.elapsed() < ttl => {
+                    *read = true;
+                    Some(value.clone())
+                }
+                Some((_, inserted_at, _)) => {
+                    if held_for.is_some_and(|held_for| inserted_at.elapsed() >= held_for) {
+                        self.entries.remove(k);
+                    }
+                    None
+                }
+                None => None,
+            })
+            .collect::<Vec<_>>()
+    }
+
+    fn get_stale(&mut self, keys: &[K], max_stale: Duration) -> Vec<Option<V>> {
+        let held_for = self.ttl.checked_add(max_stale);
+        keys.iter()
+            .map(|k| match self.entries.get(k) {
+                Some((value, inserted_at, _))
+                    if held_for.is_none_or(|held_for| inserted_at.elapsed() < held_for) =>
+                {
+                    Some(value.clone())
+                }
+                _ => None,
+            })
+            .collect::<Vec<_>>()
+    }
+
+    fn insert(&mut self, key: K, value: V) {
+        self.entries.insert(key, (value, Instant::now(), false));
+    }
+
+    fn insert_many<I: IntoIterator<Item = (K, V)>>(&mut self, key_vals: I) {
+        let inserted_at = Instant::now();
+        for (key, value) in key_vals.into_iter() {
+            self.entries.insert(key, (value, inserted_at, false));
+        }
+    }
+
+    fn remove(&mut self, keys: &[K]) {
+        for key in keys.iter() {
+            self.entries.remove(key);
+        }
+    }
+
+    fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    fn flush(&mut self) {
+        self.entries.clear();
+    }
+
+    fn retain(&mut self, keep: &mut dyn FnMut(&K, &V) -> bool) {
+        self.entries.retain(|key, (value, _, _)| keep(key, value));
+    }
+
+    /// Scans every entry, so refreshing ahead is best suited to caches of moderate size.
+    fn refresh_candidates(&mut self, within: Duration) -> Vec<K> {
+        let ttl = self.ttl;
+        self.entries
+            .iter_mut()
+            .filter_map(|(key, (_, inserted_at, read))| {
+                let age = inserted_at.elapsed();
+                if !*read || age >= ttl || ttl.saturating_sub(age) > within {
+                    return None;
+                }
+                *read = false;
+                Some(key.clone())
+            })
+            .collect()
+    }
+
+    fn sweep(&mut self) {
+        let interval = match self.sweep_interval {
+            Some(interval) => interval,
+            None => return,
+        };
+        let now = Instant::now();
+        if now.duration_since(self.last_sweep) < interval {
+            return;
+        }
+        self.last_sweep = now;
+        let held_for = self.held_for();
+        self.entries.retain(|_, (_, inserted_at, _)| {
+            held_for.is_none_or(|held_for| now.duration_since(*inserted_at) < held_for)
+        });
+    }
+
+    fn reconfigure(&mut self, policy: &CachePolicy) {
+        if let Some(ttl) = policy.ttl {
+            self.ttl = ttl;
+        }
+        if let Some(window) = policy.stale_window {
+            self.stale_window = window;
+        }
+    }
 }
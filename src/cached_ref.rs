@@ -0,0 +1,51 @@
+use std::borrow::Borrow;
+use std::ops::Deref;
+use std::sync::Arc;
+
+/// A shared, read-only handle to a value held in a loader's cache.
+///
+/// Returned by [`crate::Loader::load_ref`] for loaders whose values are stored as `Arc<T>`. The
+/// handle points at the same allocation as the cache entry, so obtaining one only bumps a
+/// reference count regardless of the size of `T`. The value remains alive for as long as the
+/// handle does, even if the entry is later cleared from the cache.
+#[derive(Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct CachedRef<T>(Arc<T>);
+
+impl<T> CachedRef<T> {
+    /// Returns the underlying shared pointer.
+    pub fn into_arc(self) -> Arc<T> {
+        self.0
+    }
+}
+
+impl<T> Clone for CachedRef<T> {
+    fn clone(&self) -> Self {
+        Self(self.0.clone())
+    }
+}
+
+impl<T> From<Arc<T>> for CachedRef<T> {
+    fn from(value: Arc<T>) -> Self {
+        Self(value)
+    }
+}
+
+impl<T> Deref for CachedRef<T> {
+    type Target = T;
+
+    fn deref(&self) -> &T {
+        &self.0
+    }
+}
+
+impl<T> AsRef<T> for CachedRef<T> {
+    fn as_ref(&self) -> &T {
+        &self.0
+    }
+}
+
+impl<T> Borrow<T> for CachedRef<T> {
+    fn borrow(&self) -> &T {
+        &self.0
+    }
+}
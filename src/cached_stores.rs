@@ -0,0 +1,66 @@
+//! [`Cache`] implementations for the stores provided by the `cached` crate.
+use std::hash::Hash;
+
+use cached::{Cached, SizedCache, TimedCache};
+
+use crate::cache::Cache;
+
+macro_rules! impl_cache_for_cached_store {
+    ($store:ident, |$this:ident| $entries:expr) => {
+        impl<K, V> Cache for $store<K, V>
+        where
+            K: Eq + Hash + Clone,
+            V: Clone,
+        {
+            type K = K;
+            type V = V;
+
+            fn get(&mut self, keys: &[Self::K]) -> Vec<Option<Self::V>> {
+                keys.iter().map(|k| self.cache_get(k).cloned()).collect::<Vec<_>>()
+            }
+
+            fn insert(&mut self, key: Self::K, value: Self::V) {
+                self.cache_set(key, value);
+            }
+
+            fn insert_many<I: IntoIterator<Item = (Self::K, Self::V)>>(&mut self, key_vals: I) {
+                for (key, value) in key_vals.into_iter() {
+                    self.cache_set(key, value);
+                }
+            }
+
+            fn remove(&mut self, keys: &[Self::K]) {
+                for key in keys.iter() {
+                    self.cache_remove(key);
+                }
+            }
+
+            fn len(&self) -> usize {
+                self.cache_size()
+            }
+
+            fn flush(&mut self) {
+                self.cache_clear();
+            }
+
+            fn retain(&mut self, keep: &mut dyn FnMut(&Self::K, &Self::V) -> bool) {
+                let removed = {
+                    let $this = &*self;
+                    $entries
+                        .filter(|(k, v)| !keep(k, v))
+                        .map(|(k, _)| k.clone())
+                        .collect::<Vec<_>>()
+                };
+                for key in removed.iter() {
+                    self.cache_remove(key);
+                }
+            }
+        }
+    };
+}
+
+impl_cache_for_cached_store!(SizedCache, |store| store.key_order().zip(store.value_order()));
+impl_cache_for_cached_store!(TimedCache, |store| store
+    .get_store()
+    .iter()
+    .map(|(k, (_, v))| (k, v)));
@@ -0,0 +1,64 @@
+use std::sync::Arc;
+
+use tokio::sync::watch;
+
+/// Signals that the [`crate::BatchFunction`] invocation it was handed to has been abandoned by the
+/// worker, either because it exceeded the loader's batch timeout or because the loader was
+/// dropped.
+///
+/// Batch functions that issue backend calls which outlive their own future (e.g. queries spawned
+/// onto a connection pool) can watch the token and cancel that work promptly instead of leaking
+/// it.
+#[derive(Debug, Clone)]
+pub struct CancellationToken {
+    cancel_tx: Arc<watch::Sender<bool>>,
+    cancel_rx: watch::Receiver<bool>,
+}
+
+impl CancellationToken {
+    pub(crate) fn new() -> Self {
+        let (cancel_tx, cancel_rx) = watch::channel(false);
+        Self { cancel_tx: Arc::new(cancel_tx), cancel_rx }
+    }
+
+    pub(crate) fn cancel(&self) {
+        let _ = self.cancel_tx.send(true);
+    }
+
+    /// Returns true once the batch has been cancelled.
+    pub fn is_cancelled(&self) -> bool {
+        *self.cancel_rx.borrow()
+    }
+
+    /// Resolves once the batch has been cancelled.
+    pub async fn cancelled(&self) {
+        let mut cancel_rx = self.cancel_rx.clone();
+        while !*cancel_rx.borrow() {
+            if cancel_rx.changed().await.is_err() {
+                return;
+            }
+        }
+    }
+
+    /// Returns a guard that cancels the token when dropped, unless it is disarmed first.
+    pub(crate) fn cancel_on_drop(&self) -> CancelOnDrop {
+        CancelOnDrop(Some(self.clone()))
+    }
+}
+
+/// Cancels its token on drop, covering the case where the worker task is aborted mid-batch.
+pub(crate) struct CancelOnDrop(Option<CancellationToken>);
+
+impl CancelOnDrop {
+    pub fn disarm(mut self) {
+        self.0 = None;
+    }
+}
+
+impl Drop for CancelOnDrop {
+    fn drop(&mut self) {
+        if let Some(token) = self.0.take() {
+            token.cancel();
+        }
+    }
+}
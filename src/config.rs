@@ -0,0 +1,109 @@
+//! Runtime-tunable loader settings that can be read from configuration files.
+use std::fmt::Debug;
+use std::hash::Hash;
+use std::time::Duration;
+
+use serde::Deserialize;
+
+use crate::{
+    batch_function::BatchFunction, builder::LoaderBuilder, cache::TtlCache, loader::Loader,
+    AsyncCache, LruCache,
+};
+
+/// The backing cache selected by a [`LoaderConfig`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Deserialize)]
+#[serde(tag = "kind", rename_all = "snake_case", deny_unknown_fields)]
+pub enum CacheConfig {
+    /// An unbounded `HashMap`, as used by [`Loader::new`]. This is the default.
+    #[default]
+    Unbounded,
+    /// An [`LruCache`] holding at most `max_entries` entries.
+    Lru { max_entries: usize },
+    /// A [`TtlCache`] whose entries expire `ttl_ms` milliseconds after they were inserted.
+    Ttl { ttl_ms: u64 },
+}
+
+/// Bundles the loader settings that deployments commonly tune, so that they can be read from
+/// configuration files (e.g. `{"cache": {"kind": "lru", "max_entries": 10000},
+/// "max_staged_keys": 500, "dispatch_after_ms": 2}`).
+///
+/// Settings that are not set keep the behavior of [`Loader::new`]. Durations are expressed in
+/// milliseconds.
+#[derive(Debug, Clone, PartialEq, Eq, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct LoaderConfig {
+    pub cache: CacheConfig,
+    /// See [`LoaderBuilder::max_pending_requests`].
+    pub max_pending_requests: Option<usize>,
+    /// See [`LoaderBuilder::max_staged_keys`].
+    pub max_staged_keys: Option<usize>,
+    /// See [`LoaderBuilder::max_concurrent_batches`].
+    pub max_concurrent_batches: Option<usize>,
+    /// See [`LoaderBuilder::dispatch_after`].
+    pub dispatch_after_ms: Option<u64>,
+    /// See [`LoaderBuilder::batch_timeout`].
+    pub batch_timeout_ms: Option<u64>,
+    /// See [`LoaderBuilder::urgent_deadline_threshold`].
+    pub urgent_deadline_threshold_ms: Option<u64>,
+    /// See [`LoaderBuilder::negative_caching`].
+    pub negative_caching_ttl_ms: Option<u64>,
+}
+
+impl<K, V, F, ContextT, CacheT> LoaderBuilder<K, V, F, ContextT, CacheT>
+where
+    K: 'static + Eq + Debug + Ord + Clone + Send + Sync,
+    V: 'static + Send + Debug + Clone,
+    ContextT: Send + Sync + 'static,
+    F: 'static + BatchFunction<K, V, Context = ContextT> + Send,
+    CacheT: 'static + AsyncCache<K = K, V = V> + Send,
+{
+    /// Applies the settings of `config`, other than its cache, which is selected by
+    /// [`Loader::from_config`].
+    pub fn config(mut self, config: &LoaderConfig) -> Self {
+        if let Some(max_pending_requests) = config.max_pending_requests {
+            self = self.max_pending_requests(max_pending_requests);
+        }
+        if let Some(max_staged_keys) = config.max_staged_keys {
+            self = self.max_staged_keys(max_staged_keys);
+        }
+        if let Some(max_concurrent_batches) = config.max_concurrent_batches {
+            self = self.max_concurrent_batches(max_concurrent_batches);
+        }
+        if let Some(delay) = config.dispatch_after_ms {
+            self = self.dispatch_after(Duration::from_millis(delay));
+        }
+        if let Some(timeout) = config.batch_timeout_ms {
+            self = self.batch_timeout(Duration::from_millis(timeout));
+        }
+        if let Some(threshold) = config.urgent_deadline_threshold_ms {
+            self = self.urgent_deadline_threshold(Duration::from_millis(threshold));
+        }
+        if let Some(ttl) = config.negative_caching_ttl_ms {
+            self = self.negative_caching(Duration::from_millis(ttl));
+        }
+        self
+    }
+}
+
+impl<K, V> Loader<K, V>
+where
+    K: 'static + Eq + Debug + Ord + Clone + Hash + Send + Sync,
+    V: 'static + Send + Debug + Clone,
+{
+    /// Creates a new Loader for the provided BatchFunction and Context type, configured by
+    /// `config`.
+    pub fn from_config<F, ContextT>(config: &LoaderConfig, batch_fn: F, context: ContextT) -> Self
+    where
+        ContextT: Send + Sync + 'static,
+        F: 'static + BatchFunction<K, V, Context = ContextT> + Send,
+    {
+        let builder = Self::builder(batch_fn, context).config(config);
+        match config.cache {
+            CacheConfig::Unbounded => builder.build(),
+            CacheConfig::Lru { max_entries } => builder.cache(LruCache::new(max_entries)).build(),
+            CacheConfig::Ttl { ttl_ms } => {
+                builder.cache(TtlCache::new(Duration::from_millis(ttl_ms))).build()
+            }
+        }
+    }
+}
@@ -0,0 +1,30 @@
+use std::sync::RwLock;
+
+type CascadeFn<K> = Box<dyn Fn(&[K]) + Send + Sync>;
+
+/// Registry of dependent loaders that must be cleared along with a parent loader's keys.
+///
+/// Each entry maps the parent's cleared keys onto the dependent loader's keys and clears them,
+/// which in turn cascades into the dependent loader's own dependents.
+pub(crate) struct Dependents<K> {
+    dependents: RwLock<Vec<CascadeFn<K>>>,
+}
+
+impl<K> Default for Dependents<K> {
+    fn default() -> Self {
+        Self { dependents: RwLock::new(Vec::new()) }
+    }
+}
+
+impl<K> Dependents<K> {
+    pub fn register(&self, cascade: CascadeFn<K>) {
+        self.dependents.write().unwrap().push(cascade);
+    }
+
+    /// Clears the dependents' entries that correspond to the parent's cleared keys.
+    pub fn cascade(&self, keys: &[K]) {
+        for cascade in self.dependents.read().unwrap().iter() {
+            cascade(keys);
+        }
+    }
+}
@@ -0,0 +1,203 @@
+use std::error::Error;
+use std::fmt::{self, Debug, Display};
+use std::sync::Arc;
+
+/// The reason a key could not be resolved to a value.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum FailureReason {
+    /// The `BatchFunction` did not return a value for the key.
+    Missing,
+    /// The `BatchFunction` deliberately withheld the key (e.g. because the entity is soft-deleted
+    /// or the caller is not authorized to see it) through [`crate::BatchInfo::filter`].
+    Filtered,
+    /// The key could not be loaded because of an error.
+    Batch(BatchError),
+}
+
+impl Display for FailureReason {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            FailureReason::Missing => f.write_str("not returned by the batch function"),
+            FailureReason::Filtered => f.write_str("filtered out by the batch function"),
+            FailureReason::Batch(error) => Display::fmt(error, f),
+        }
+    }
+}
+
+/// The category of error that prevented a request from being served.
+///
+/// Shared by the detailed and fallible load APIs, [`crate::LoaderObserver::on_batch_error`] and
+/// the worker statistics, so that error handling and alerting can match on stable categories.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum BatchError {
+    /// The `BatchFunction` did not complete within the loader's batch timeout.
+    Timeout,
+    /// The worker stopped before resolving the request.
+    Cancelled,
+    /// The `BatchFunction` reported an error through [`crate::BatchInfo::fail`].
+    Backend(BackendError),
+    /// The `BatchFunction` panicked.
+    Panic,
+    /// The request was rejected because the loader's execution frame was full (see
+    /// [`crate::OverflowPolicy::Reject`]).
+    Overloaded,
+}
+
+impl BatchError {
+    /// Returns a short, stable name for the category, suitable as a metric label.
+    pub fn category(&self) -> &'static str {
+        match self {
+            BatchError::Timeout => "timeout",
+            BatchError::Cancelled => "cancelled",
+            BatchError::Backend(_) => "backend",
+            BatchError::Panic => "panic",
+            BatchError::Overloaded => "overloaded",
+        }
+    }
+}
+
+impl Display for BatchError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            BatchError::Timeout => f.write_str("timed out waiting for the batch function"),
+            BatchError::Cancelled => f.write_str("cancelled before the load completed"),
+            BatchError::Backend(_) => f.write_str("batch function failed"),
+            BatchError::Panic => f.write_str("batch function panicked"),
+            BatchError::Overloaded => f.write_str("rejected by an overloaded loader"),
+        }
+    }
+}
+
+impl Error for BatchError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            BatchError::Backend(error) => Some(error),
+            _ => None,
+        }
+    }
+}
+
+impl From<BatchError> for FailureReason {
+    fn from(error: BatchError) -> Self {
+        FailureReason::Batch(error)
+    }
+}
+
+/// A key that could not be loaded, along with the reason.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LoadFailure<K> {
+    pub key: K,
+    pub reason: FailureReason,
+}
+
+impl<K: Debug> Display for LoadFailure<K> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "failed to load key {:?}: {}", self.key, self.reason)
+    }
+}
+
+impl<K: Debug> Error for LoadFailure<K> {}
+
+/// The keys that [`crate::Loader::load_many_strict`] or [`crate::Loader::load_strict`] could not
+/// load, in request order.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MissingKeys<K>(pub Vec<K>);
+
+impl<K: Debug> Display for MissingKeys<K> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "failed to load keys {:?}", self.0)
+    }
+}
+
+impl<K: Debug> Error for MissingKeys<K> {}
+
+/// An error reported by a `BatchFunction`, shared by every request served by the failed batch.
+///
+/// Two `BackendError`s are equal if they share the same underlying error.
+#[derive(Debug, Clone)]
+pub struct BackendError(Arc<dyn Error + Send + Sync>);
+
+impl BackendError {
+    pub fn new<E>(error: E) -> Self
+    where
+        E: Into<Box<dyn Error + Send + Sync>>,
+    {
+        Self(Arc::from(error.into()))
+    }
+
+    /// Returns the error reported by the `BatchFunction`.
+    pub fn inner(&self) -> &(dyn Error + Send + Sync + 'static) {
+        &*self.0
+    }
+}
+
+impl PartialEq for BackendError {
+    fn eq(&self, other: &Self) -> bool {
+        Arc::ptr_eq(&self.0, &other.0)
+    }
+}
+
+impl Eq for BackendError {}
+
+impl Display for BackendError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        Display::fmt(&self.0, f)
+    }
+}
+
+impl Error for BackendError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        // Transparent over the underlying error, whose message is already displayed.
+        self.0.source()
+    }
+}
+
+/// An error returned by the fallible load APIs, such as [`crate::Loader::try_load`].
+///
+/// Keys that the `BatchFunction` did not return (or filtered out) are not errors; they resolve to
+/// `None`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum LoaderError {
+    /// The request could not be served because of an error.
+    Batch(BatchError),
+    /// The worker is no longer running (e.g. because it panicked or was aborted).
+    Closed,
+}
+
+impl LoaderError {
+    /// Converts a failure into an error, or returns `None` for failures that resolve to `None`.
+    pub(crate) fn from_reason(reason: FailureReason) -> Option<Self> {
+        match reason {
+            FailureReason::Missing | FailureReason::Filtered => None,
+            FailureReason::Batch(error) => Some(LoaderError::Batch(error)),
+        }
+    }
+}
+
+impl From<BatchError> for LoaderError {
+    fn from(error: BatchError) -> Self {
+        LoaderError::Batch(error)
+    }
+}
+
+impl Display for LoaderError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            LoaderError::Batch(error) => Display::fmt(error, f),
+            LoaderError::Closed => f.write_str("loader worker is no longer running"),
+        }
+    }
+}
+
+impl Error for LoaderError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            // Transparent over the batch error, whose message is already displayed.
+            LoaderError::Batch(error) => error.source(),
+            LoaderError::Closed => None,
+        }
+    }
+}
@@ -0,0 +1,56 @@
+//! Fault points inside the loader's worker, for testing resilience against internal failures.
+//!
+//! Faults are armed on a shared [`Faults`] handle that is attached to a loader with
+//! [`crate::LoaderBuilder::faults`]. Each armed fault fires a fixed number of times and then
+//! disarms itself.
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Mutex;
+use std::time::Duration;
+
+/// Fault points that can be armed from tests.
+#[derive(Debug, Default)]
+pub struct Faults {
+    drop_frames: AtomicUsize,
+    drop_responses: AtomicUsize,
+    cache_insert_delay: Mutex<Option<Duration>>,
+}
+
+impl Faults {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Drops the next `count` frames in which the `BatchFunction` would have been invoked. The
+    /// batch is not executed and the frame's pending requests are discarded without a response.
+    pub fn drop_frames(&self, count: usize) {
+        self.drop_frames.store(count, Ordering::SeqCst);
+    }
+
+    /// Discards the next `count` responses to load requests served by a batch, as if the response
+    /// were lost.
+    pub fn drop_responses(&self, count: usize) {
+        self.drop_responses.store(count, Ordering::SeqCst);
+    }
+
+    /// Delays every insertion of loaded values into the cache by `delay`, or removes the delay.
+    pub fn delay_cache_inserts(&self, delay: Option<Duration>) {
+        *self.cache_insert_delay.lock().unwrap() = delay;
+    }
+
+    pub(crate) fn should_drop_frame(&self) -> bool {
+        take_one(&self.drop_frames)
+    }
+
+    pub(crate) fn should_drop_response(&self) -> bool {
+        take_one(&self.drop_responses)
+    }
+
+    pub(crate) fn cache_insert_delay(&self) -> Option<Duration> {
+        *self.cache_insert_delay.lock().unwrap()
+    }
+}
+
+/// Decrements `remaining` if it is positive, returning true if it was.
+fn take_one(remaining: &AtomicUsize) -> bool {
+    remaining.fetch_update(Ordering::SeqCst, Ordering::SeqCst, |n| n.checked_sub(1)).is_ok()
+}
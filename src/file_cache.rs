@@ -0,0 +1,163 @@
+//! A [`Cache`] persisted to a local file, so that rarely-changing reference data stays cached
+//! across restarts and deploys.
+use std::collections::HashMap;
+use std::fmt::{self, Debug};
+use std::fs::{self, File, OpenOptions};
+use std::hash::Hash;
+use std::io::{self, BufRead, BufReader, Write};
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+
+use crate::cache::Cache;
+
+/// A change to the cache, as appended to its file.
+#[derive(Serialize)]
+enum Change<'a, K, V> {
+    Insert(&'a K, &'a V),
+    Remove(&'a K),
+}
+
+/// A change read back from the cache's file.
+#[derive(Deserialize)]
+enum Replayed<K, V> {
+    Insert(K, V),
+    Remove(K),
+}
+
+/// A cache whose entries are held in memory and persisted to a file, from which they are restored
+/// when the cache is opened again.
+///
+/// Every change is appended to the file as a line of JSON, and the file is compacted when it is
+/// opened. Lines that fail to deserialize (e.g. because the value type changed) are skipped, and
+/// write errors are logged; neither fails the loader. Errors reading the file, however, fail
+/// [`FileCache::open`], as compacting a partially read file would lose the entries after the
+/// error.
+///
+/// Clones share the file, each appending its own changes, so the caches of pipelines created with
+/// [`crate::Loader::split_isolated`] are all persisted. A clone persists a flush as the removal
+/// of its own entries, leaving the entries persisted by other clones in place.
+#[derive(Clone)]
+pub struct FileCache<K, V> {
+    entries: HashMap<K, V>,
+    path: PathBuf,
+    file: Arc<Mutex<File>>,
+}
+
+impl<K, V> FileCache<K, V>
+where
+    K: Eq + Hash + Serialize + DeserializeOwned,
+    V: Serialize + DeserializeOwned,
+{
+    /// Opens the cache persisted at `path`, creating the file if it does not exist.
+    pub fn open(path: impl AsRef<Path>) -> io::Result<Self> {
+        let path = path.as_ref().to_path_buf();
+        let mut entries = HashMap::new();
+        if path.exists() {
+            for line in BufReader::new(File::open(&path)?).lines() {
+                match serde_json::from_str(&line?) {
+                    Ok(Replayed::Insert(key, value)) => {
+                        entries.insert(key, value);
+                    }
+                    Ok(Replayed::Remove(key)) => {
+                        entries.remove(&key);
+                    }
+                    Err(e) => tracing::warn!(%e, ?path, "skipping unreadable cache entry"),
+                }
+            }
+        }
+        let compacted = path.with_extension("compacting");
+        {
+            let mut file = File::create(&compacted)?;
+            for (key, value) in entries.iter() {
+                file.write_all(&encode(&Change::Insert(key, value))?)?;
+            }
+            file.sync_all()?;
+        }
+        fs::rename(&compacted, &path)?;
+        let file = OpenOptions::new().append(true).open(&path)?;
+        Ok(Self { entries, path, file: Arc::new(Mutex::new(file)) })
+    }
+
+    /// Appends a change to the file.
+    fn append(&self, change: Change<'_, K, V>) {
+        let written = encode(&change).and_then(|line| self.file.lock().unwrap().write_all(&line));
+        if let Err(e) = written {
+            tracing::warn!(%e, path = ?self.path, "failed to persist cache change");
+        }
+    }
+}
+
+/// Encodes a change as a line of JSON.
+fn encode<K: Serialize, V: Serialize>(change: &Change<'_, K, V>) -> io::Result<Vec<u8>> {
+    let mut line = serde_json::to_vec(change)?;
+    line.push(b'\n');
+    Ok(line)
+}
+
+impl<K, V> Debug for FileCache<K, V> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("FileCache")
+            .field("path", &self.path)
+            .field("entries", &self.entries.len())
+            .finish()
+    }
+}
+
+impl<K, V> Cache for FileCache<K, V>
+where
+    K: Eq + Hash + Serialize + DeserializeOwned,
+    V: Clone + Serialize + DeserializeOwned,
+{
+    type K = K;
+    type V = V;
+
+    fn get(&mut self, keys: &[K]) -> Vec<Option<V>> {
+        keys.iter().map(|key| self.entries.get(key).cloned()).collect()
+    }
+
+    fn insert(&mut self, key: K, value: V) {
+        self.append(Change::Insert(&key, &value));
+        self.entries.insert(key, value);
+    }
+
+    fn insert_many<I: IntoIterator<Item = (K, V)>>(&mut self, key_vals: I) {
+        for (key, value) in key_vals.into_iter() {
+            Cache::insert(self, key, value);
+        }
+    }
+
+    fn remove(&mut self, keys: &[K]) {
+        for key in keys.iter() {
+            if self.entries.remove(key).is_some() {
+                self.append(Change::Remove(key));
+            }
+        }
+    }
+
+    fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    fn flush(&mut self) {
+        self.retain(&mut |_, _| false);
+    }
+
+    fn retain(&mut self, keep: &mut dyn FnMut(&K, &V) -> bool) {
+        let mut removed = Vec::new();
+        self.entries.retain(|key, value| {
+            let kept = keep(key, value);
+            if !kept {
+                removed.push(encode(&Change::<K, V>::Remove(key)));
+            }
+            kept
+        });
+        let mut file = self.file.lock().unwrap();
+        for line in removed {
+            if let Err(e) = line.and_then(|line| file.write_all(&line)) {
+                tracing::warn!(%e, path = ?self.path, "failed to persist cache change");
+            }
+        }
+    }
+}
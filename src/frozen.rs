@@ -0,0 +1,54 @@
+use std::fmt::Debug;
+
+use crate::{health::LoaderHealth, loader::Loader};
+
+/// A read-only view of a [`Loader`] that serves values exclusively from its cache.
+///
+/// Misses resolve to `None` as soon as the worker processes the request, and no batches are ever
+/// dispatched on behalf of the view. Useful for post-request phases (e.g. response serialization
+/// or audit logging) that must not trigger new backend work.
+///
+/// Created with [`Loader::freeze`]. The view shares the loader's worker and cache, so values
+/// loaded or primed through the loader remain visible through the view.
+pub struct FrozenLoader<K, V>
+where
+    K: 'static + Eq + Debug + Send,
+    V: 'static + Send + Debug + Clone,
+{
+    loader: Loader<K, V>,
+}
+
+impl<K, V> Clone for FrozenLoader<K, V>
+where
+    K: 'static + Eq + Debug + Send,
+    V: 'static + Send + Debug + Clone,
+{
+    fn clone(&self) -> Self {
+        Self { loader: self.loader.clone() }
+    }
+}
+
+impl<K, V> FrozenLoader<K, V>
+where
+    K: 'static + Eq + Debug + Ord + Clone + Send + Sync,
+    V: 'static + Send + Debug + Clone,
+{
+    pub(crate) fn new(loader: Loader<K, V>) -> Self {
+        Self { loader }
+    }
+
+    /// Returns the cached value for a key, or `None` if it is not cached.
+    pub async fn load(&self, key: K) -> Option<V> {
+        self.load_many(vec![key]).await.pop().flatten()
+    }
+
+    /// Returns the cached values for the keys, with `None` for keys that are not cached.
+    pub async fn load_many(&self, keys: Vec<K>) -> Vec<Option<V>> {
+        self.loader.get_many_cached(keys).await
+    }
+
+    /// See [`Loader::health`].
+    pub fn health(&self) -> LoaderHealth {
+        self.loader.health()
+    }
+}
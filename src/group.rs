@@ -0,0 +1,131 @@
+use std::collections::BTreeMap;
+use std::fmt::Debug;
+use std::hash::Hash;
+
+use async_trait::async_trait;
+
+use crate::{batch_function::BatchFunction, loader::Loader};
+
+/// Defines how a [`GroupLoader`] fetches the rows associated with a batch of keys, for one-to-many
+/// associations such as the comments of a post.
+///
+/// Rows are returned as `(key, row)` pairs, any number of them per key and in any order. Every
+/// requested key resolves to the rows returned for it, in the order they were returned, or to an
+/// empty `Vec` if none were.
+#[async_trait]
+pub trait GroupBatchFunction<K, V>: Send + Sync {
+    type Context;
+    type Rows: IntoIterator<Item = (K, V)> + Send;
+
+    async fn load(&self, keys: &[K], context: &Self::Context) -> Self::Rows;
+}
+
+/// Adapts a [`GroupBatchFunction`] into a `BatchFunction` loading a `Vec` of rows per key, so that
+/// a grouped loader can be configured through [`Loader::builder`] and
+/// [`crate::LoaderBuilder::build_grouped`].
+pub struct Grouped<F>(F);
+
+impl<F> Grouped<F> {
+    pub fn new(batch_fn: F) -> Self {
+        Self(batch_fn)
+    }
+}
+
+#[async_trait]
+impl<K, V, F> BatchFunction<K, Vec<V>> for Grouped<F>
+where
+    K: Ord + Clone + Send + Sync + 'static,
+    V: Send + 'static,
+    F: GroupBatchFunction<K, V>,
+    F::Context: Sync,
+{
+    type Context = F::Context;
+    type Loaded = BTreeMap<K, Vec<V>>;
+
+    async fn load(&self, keys: &[K], context: &F::Context) -> BTreeMap<K, Vec<V>> {
+        let rows = self.0.load(keys, context).await;
+        // Keys without rows resolve (and are cached) as empty groups rather than as missing.
+        let mut groups =
+            keys.iter().map(|key| (key.clone(), Vec::new())).collect::<BTreeMap<_, _>>();
+        for (key, row) in rows {
+            groups.entry(key).or_default().push(row);
+        }
+        groups
+    }
+}
+
+/// A loader for one-to-many associations, whose callers receive every row associated with a key.
+///
+/// Wraps a `Loader<K, Vec<V>>`, so caching, batching and configuration work as they do for other
+/// loaders. Keys without rows resolve to an empty `Vec` rather than `None`.
+///
+/// Created with [`GroupLoader::new`], or with [`crate::LoaderBuilder::build_grouped`] from a
+/// builder of a [`Grouped`] batch function.
+pub struct GroupLoader<K, V>
+where
+    K: 'static + Eq + Debug + Send,
+    V: 'static + Send + Debug + Clone,
+{
+    loader: Loader<K, Vec<V>>,
+}
+
+impl<K, V> Clone for GroupLoader<K, V>
+where
+    K: 'static + Eq + Debug + Send,
+    V: 'static + Send + Debug + Clone,
+{
+    fn clone(&self) -> Self {
+        Self { loader: self.loader.clone() }
+    }
+}
+
+impl<K, V> GroupLoader<K, V>
+where
+    K: 'static + Eq + Debug + Ord + Clone + Hash + Send + Sync,
+    V: 'static + Send + Debug + Clone,
+{
+    /// Creates a new GroupLoader for the provided GroupBatchFunction and Context type.
+    pub fn new<F, ContextT>(batch_fn: F, context: ContextT) -> Self
+    where
+        ContextT: Send + Sync + 'static,
+        F: 'static + GroupBatchFunction<K, V, Context = ContextT>,
+    {
+        Self::from_loader(Loader::new(Grouped::new(batch_fn), context))
+    }
+}
+
+impl<K, V> GroupLoader<K, V>
+where
+    K: 'static + Eq + Debug + Ord + Clone + Send + Sync,
+    V: 'static + Send + Debug + Clone,
+{
+    /// Wraps a loader whose values are the rows associated with each key.
+    pub fn from_loader(loader: Loader<K, Vec<V>>) -> Self {
+        Self { loader }
+    }
+
+    /// Returns the underlying loader.
+    pub fn inner(&self) -> &Loader<K, Vec<V>> {
+        &self.loader
+    }
+
+    /// Loads the rows associated with a key. See [`Loader::load`].
+    pub async fn load(&self, key: K) -> Vec<V> {
+        self.loader.load(key).await.unwrap_or_default()
+    }
+
+    /// Loads the rows associated with many keys. See [`Loader::load_many`].
+    pub async fn load_many(&self, keys: Vec<K>) -> Vec<Vec<V>> {
+        self.loader.load_many(keys).await.into_iter().map(Option::unwrap_or_default).collect()
+    }
+
+    /// Adds the rows associated with a key to the cache. See [`Loader::prime`].
+    pub fn prime(&self, key: K, rows: Vec<V>) {
+        self.loader.prime(key, rows);
+    }
+
+    /// Removes the rows associated with a key from the cache. See [`Loader::clear`].
+    pub fn clear(&self, key: K) {
+        self.loader.clear(key);
+    }
+}
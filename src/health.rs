@@ -0,0 +1,136 @@
+use std::sync::atomic::{AtomicBool, AtomicU64, AtomicUsize, Ordering};
+
+use tokio::sync::mpsc::{error::SendError, UnboundedSender};
+
+/// Point-in-time view of a loader's worker, as returned by [`crate::Loader::health`].
+///
+/// Besides the size of the current execution frame, the health includes cumulative counters that
+/// can be compared across loaders (or across the workers of a key-partitioned deployment) to detect
+/// skewed key distributions.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[cfg_attr(feature = "admin", derive(serde::Serialize))]
+pub struct LoaderHealth {
+    /// Number of load requests staged for the current execution frame.
+    pub pending_requests: usize,
+    /// Number of keys (not necessarily unique) staged for the current execution frame.
+    pub staged_keys: usize,
+    /// Whether the worker is currently awaiting at least one `BatchFunction` invocation.
+    pub batch_in_flight: bool,
+    /// Number of ops enqueued by the loader that the worker has not yet processed.
+    pub queue_depth: usize,
+    /// Number of entries in the loader's cache as of the end of the worker's last frame.
+    pub cache_entries: usize,
+    /// Total number of keys requested through load requests.
+    pub keys_requested: u64,
+    /// Total number of requested keys that were found in the cache.
+    pub cache_hits: u64,
+    /// Total number of `BatchFunction` invocations.
+    pub batches: u64,
+    /// Total number of unique keys passed to the `BatchFunction`.
+    pub batched_keys: u64,
+    /// The largest number of unique keys passed to a single `BatchFunction` invocation.
+    pub max_batch_size: u64,
+    /// Whether the worker has stopped caching loaded values because of a persistently low hit
+    /// rate (see [`crate::LoaderBuilder::adaptive_caching`]).
+    pub caching_disabled: bool,
+}
+
+impl LoaderHealth {
+    /// Fraction of requested keys that were served from the cache.
+    pub fn hit_rate(&self) -> f64 {
+        if self.keys_requested == 0 {
+            0.0
+        } else {
+            self.cache_hits as f64 / self.keys_requested as f64
+        }
+    }
+
+    /// Average number of unique keys passed to the `BatchFunction`.
+    pub fn average_batch_size(&self) -> f64 {
+        if self.batches == 0 {
+            0.0
+        } else {
+            self.batched_keys as f64 / self.batches as f64
+        }
+    }
+}
+
+/// Gauges written by the `LoaderWorker` and read by its `Loader`.
+///
+/// These are kept outside of the op queue so that health can be reported even while the worker is
+/// blocked on a slow or hung batch function.
+#[derive(Debug, Default)]
+pub(crate) struct HealthGauges {
+    pending_requests: AtomicUsize,
+    staged_keys: AtomicUsize,
+    batch_in_flight: AtomicBool,
+    queue_depth: AtomicUsize,
+    cache_entries: AtomicUsize,
+    keys_requested: AtomicU64,
+    cache_hits: AtomicU64,
+    batches: AtomicU64,
+    batched_keys: AtomicU64,
+    max_batch_size: AtomicU64,
+    caching_disabled: AtomicBool,
+}
+
+impl HealthGauges {
+    pub fn set_staged(&self, pending_requests: usize, staged_keys: usize) {
+        self.pending_requests.store(pending_requests, Ordering::Relaxed);
+        self.staged_keys.store(staged_keys, Ordering::Relaxed);
+    }
+
+    pub fn set_batch_in_flight(&self, in_flight: bool) {
+        self.batch_in_flight.store(in_flight, Ordering::Relaxed);
+    }
+
+    /// Sends an op to the worker. The op is counted before it is sent, so that the worker never
+    /// dequeues an op that was not counted yet.
+    pub fn enqueue<T>(&self, request_tx: &UnboundedSender<T>, op: T) -> Result<(), SendError<T>> {
+        self.queue_depth.fetch_add(1, Ordering::Relaxed);
+        let sent = request_tx.send(op);
+        if sent.is_err() {
+            self.queue_depth.fetch_sub(1, Ordering::Relaxed);
+        }
+        sent
+    }
+
+    pub fn op_dequeued(&self) {
+        self.queue_depth.fetch_sub(1, Ordering::Relaxed);
+    }
+
+    pub fn set_cache_entries(&self, cache_entries: usize) {
+        self.cache_entries.store(cache_entries, Ordering::Relaxed);
+    }
+
+    pub fn record_load_request(&self, keys_requested: usize, cache_hits: usize) {
+        self.keys_requested.fetch_add(keys_requested as u64, Ordering::Relaxed);
+        self.cache_hits.fetch_add(cache_hits as u64, Ordering::Relaxed);
+    }
+
+    pub fn record_batch(&self, batch_size: usize) {
+        self.batches.fetch_add(1, Ordering::Relaxed);
+        self.batched_keys.fetch_add(batch_size as u64, Ordering::Relaxed);
+        self.max_batch_size.fetch_max(batch_size as u64, Ordering::Relaxed);
+    }
+
+    pub fn set_caching_disabled(&self) {
+        self.caching_disabled.store(true, Ordering::Relaxed);
+    }
+
+    pub fn snapshot(&self) -> LoaderHealth {
+        LoaderHealth {
+            pending_requests: self.pending_requests.load(Ordering::Relaxed),
+            staged_keys: self.staged_keys.load(Ordering::Relaxed),
+            batch_in_flight: self.batch_in_flight.load(Ordering::Relaxed),
+            queue_depth: self.queue_depth.load(Ordering::Relaxed),
+            cache_entries: self.cache_entries.load(Ordering::Relaxed),
+            keys_requested: self.keys_requested.load(Ordering::Relaxed),
+            cache_hits: self.cache_hits.load(Ordering::Relaxed),
+            batches: self.batches.load(Ordering::Relaxed),
+            batched_keys: self.batched_keys.load(Ordering::Relaxed),
+            max_batch_size: self.max_batch_size.load(Ordering::Relaxed),
+            caching_disabled: self.caching_disabled.load(Ordering::Relaxed),
+        }
+    }
+}
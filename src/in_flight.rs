@@ -0,0 +1,107 @@
+use std::collections::HashMap;
+use std::hash::Hash;
+use std::sync::{Arc, Mutex};
+
+use futures::future::{BoxFuture, FutureExt};
+use tokio::sync::oneshot;
+
+/// Tracks the keys currently being fetched by the loaders sharing the store, so that identical
+/// concurrent fetches are collapsed into one.
+///
+/// Configured through [`crate::LoaderBuilder::in_flight_store`]. Before dispatching a batch, the
+/// worker claims its keys: keys that another loader is already fetching are awaited instead of
+/// being passed to the `BatchFunction`, and the remaining keys are claimed by the worker until it
+/// releases them with the values it loaded.
+///
+/// [`SharedInFlight`] is an in-process implementation that can be shared across request-scoped
+/// loaders.
+pub trait InFlightStore<K, V>: Send + Sync {
+    /// Claims the keys that are not already in flight, and returns a future resolving to the
+    /// value of each key that is.
+    fn claim(&self, keys: &[K]) -> Vec<(K, BoxFuture<'static, Option<V>>)>;
+
+    /// Publishes the values loaded for previously claimed keys, and releases the claims. Claimed
+    /// keys without a value resolve to `None` for their waiters.
+    fn release(&self, keys: &[K], values: &[(K, V)]);
+}
+
+/// An in-process [`InFlightStore`].
+pub struct SharedInFlight<K, V> {
+    waiters: Mutex<HashMap<K, Vec<oneshot::Sender<Option<V>>>>>,
+}
+
+impl<K, V> SharedInFlight<K, V> {
+    pub fn new() -> Self {
+        Self { waiters: Mutex::new(HashMap::new()) }
+    }
+}
+
+impl<K, V> Default for SharedInFlight<K, V> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<K, V> InFlightStore<K, V> for SharedInFlight<K, V>
+where
+    K: Eq + Hash + Clone + Send,
+    V: Clone + Send + 'static,
+{
+    fn claim(&self, keys: &[K]) -> Vec<(K, BoxFuture<'static, Option<V>>)> {
+        let mut waiters = self.waiters.lock().unwrap();
+        let mut in_flight = Vec::new();
+        for key in keys {
+            match waiters.get_mut(key) {
+                Some(key_waiters) => {
+                    let (value_tx, value_rx) = oneshot::channel();
+                    key_waiters.push(value_tx);
+                    in_flight
+                        .push((key.clone(), value_rx.map(|value| value.ok().flatten()).boxed()));
+                }
+                None => {
+                    waiters.insert(key.clone(), Vec::new());
+                }
+            }
+        }
+        in_flight
+    }
+
+    fn release(&self, keys: &[K], values: &[(K, V)]) {
+        let mut waiters = self.waiters.lock().unwrap();
+        for (key, value) in values {
+            for value_tx in waiters.remove(key).into_iter().flatten() {
+                let _ = value_tx.send(Some(value.clone()));
+            }
+        }
+        for key in keys {
+            for value_tx in waiters.remove(key).into_iter().flatten() {
+                let _ = value_tx.send(None);
+            }
+        }
+    }
+}
+
+/// Keys claimed in an [`InFlightStore`] by a worker. The claim is released without values if the
+/// batch does not complete (e.g. because it timed out or panicked).
+pub(crate) struct InFlightClaim<K, V> {
+    store: Arc<dyn InFlightStore<K, V>>,
+    keys: Vec<K>,
+}
+
+impl<K, V> InFlightClaim<K, V> {
+    pub fn new(store: Arc<dyn InFlightStore<K, V>>, keys: Vec<K>) -> Self {
+        Self { store, keys }
+    }
+
+    pub fn release(mut self, values: &[(K, V)]) {
+        self.store.release(&std::mem::take(&mut self.keys), values);
+    }
+}
+
+impl<K, V> Drop for InFlightClaim<K, V> {
+    fn drop(&mut self) {
+        if !self.keys.is_empty() {
+            self.store.release(&self.keys, &[]);
+        }
+    }
+}
@@ -0,0 +1,58 @@
+use std::sync::{Arc, RwLock};
+
+use futures::stream::BoxStream;
+
+/// Clears a subscribed loader's entries for a tag, returning false once its worker is gone.
+type InvalidateFn<T> = Box<dyn Fn(&T) -> bool + Send + Sync>;
+
+/// An in-process bus broadcasting invalidation tags (e.g. `UserChanged(42)`) to the loaders
+/// subscribed to it with [`crate::Loader::subscribe_invalidations`], so that a single
+/// [`InvalidationBus::invalidate`] call clears the related entries of several loaders at once.
+///
+/// Clones share the same subscribers.
+pub struct InvalidationBus<T> {
+    subscribers: Arc<RwLock<Vec<InvalidateFn<T>>>>,
+}
+
+impl<T> InvalidationBus<T> {
+    pub fn new() -> Self {
+        Self { subscribers: Arc::new(RwLock::new(Vec::new())) }
+    }
+
+    /// Clears the entries that each subscribed loader maps `tag` onto. Subscribers whose loader
+    /// is no longer running are dropped.
+    pub fn invalidate(&self, tag: &T) {
+        self.subscribers.write().unwrap().retain(|invalidate| invalidate(tag));
+    }
+
+    /// Returns the number of subscribed loaders.
+    pub fn subscribers(&self) -> usize {
+        self.subscribers.read().unwrap().len()
+    }
+
+    pub(crate) fn subscribe(&self, invalidate: InvalidateFn<T>) {
+        self.subscribers.write().unwrap().push(invalidate);
+    }
+}
+
+impl<T> Default for InvalidationBus<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T> Clone for InvalidationBus<T> {
+    fn clone(&self) -> Self {
+        Self { subscribers: self.subscribers.clone() }
+    }
+}
+
+/// A source of keys to clear that originate outside the process, e.g. a pub/sub channel through
+/// which the replicas of a service propagate their clears. See
+/// [`crate::LoaderBuilder::invalidation_source`].
+pub trait InvalidationSource<K>: Send + Sync {
+    /// Returns a new stream of the keys to clear. Called once by each worker as it starts; the
+    /// worker stops listening once the stream ends, so sources backed by a connection should
+    /// reconnect rather than end.
+    fn subscribe(&self) -> BoxStream<'static, Vec<K>>;
+}
@@ -0,0 +1,50 @@
+use std::fmt;
+use std::sync::Arc;
+
+use tokio::sync::{OwnedSemaphorePermit, Semaphore};
+
+/// A named limit on the number of batches that may run concurrently across every loader in the
+/// group, e.g. to keep loaders that share a database pool from exhausting its connections.
+///
+/// Attach loaders to a group with [`crate::LoaderBuilder::isolation_group`]. Cloning the group is
+/// cheap, and clones share the same limit.
+#[derive(Clone)]
+pub struct IsolationGroup {
+    name: Arc<str>,
+    semaphore: Arc<Semaphore>,
+}
+
+impl IsolationGroup {
+    /// Creates a group named `name` that runs at most `max_concurrent_batches` batches at a time.
+    pub fn new(name: impl Into<Arc<str>>, max_concurrent_batches: usize) -> Self {
+        Self { name: name.into(), semaphore: Arc::new(Semaphore::new(max_concurrent_batches)) }
+    }
+
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// Number of batches that may currently start without waiting.
+    pub fn available(&self) -> usize {
+        self.semaphore.available_permits()
+    }
+
+    /// Waits until the group has room for another batch. The batch holds its slot until the
+    /// returned permit is dropped.
+    pub(crate) async fn acquire(&self) -> OwnedSemaphorePermit {
+        self.semaphore
+            .clone()
+            .acquire_owned()
+            .await
+            .expect("isolation group semaphore is never closed")
+    }
+}
+
+impl fmt::Debug for IsolationGroup {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("IsolationGroup")
+            .field("name", &self.name)
+            .field("available", &self.available())
+            .finish()
+    }
+}
@@ -0,0 +1,50 @@
+use std::iter::Map;
+use std::marker::PhantomData;
+use std::vec;
+
+/// Extracts the key of type `K` that a value was loaded by, e.g. the ID stored in a `User` row.
+///
+/// A value may be keyed by several key types (e.g. `KeyOf<UserId>` and `KeyOf<Email>`), one for
+/// each loader it is loaded through.
+pub trait KeyOf<K> {
+    fn key_of(&self) -> K;
+}
+
+/// Values returned by a `BatchFunction` without their keys, which are extracted through
+/// [`KeyOf`].
+///
+/// Batch functions fetching rows that already contain their key can declare
+/// `type Loaded = Keyed<K, V>` and return the rows as they were fetched (e.g. `rows.into()`),
+/// instead of pairing every row with its key.
+#[derive(Debug, Clone)]
+pub struct Keyed<K, V> {
+    values: Vec<V>,
+    phantom: PhantomData<fn() -> K>,
+}
+
+impl<K, V> Keyed<K, V> {
+    pub fn new(values: Vec<V>) -> Self {
+        Self { values, phantom: PhantomData }
+    }
+}
+
+impl<K, V> From<Vec<V>> for Keyed<K, V> {
+    fn from(values: Vec<V>) -> Self {
+        Self::new(values)
+    }
+}
+
+impl<K, V> FromIterator<V> for Keyed<K, V> {
+    fn from_iter<I: IntoIterator<Item = V>>(values: I) -> Self {
+        Self::new(values.into_iter().collect())
+    }
+}
+
+impl<K, V: KeyOf<K>> IntoIterator for Keyed<K, V> {
+    type Item = (K, V);
+    type IntoIter = Map<vec::IntoIter<V>, fn(V) -> (K, V)>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.values.into_iter().map(|value| (value.key_of(), value))
+    }
+}
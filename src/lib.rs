@@ -1,11 +1,110 @@
+mod async_cache;
 mod batch_function;
+mod builder;
 mod cache;
+mod cached_ref;
+mod cancellation;
+mod dependents;
+mod error;
+mod frozen;
+mod group;
+mod health;
+mod in_flight;
+mod invalidation;
+mod isolation;
+mod keyed;
 mod loader;
 mod loader_op;
 mod loader_worker;
+mod lookup;
+mod macros;
+mod mapped;
+mod negative;
+mod observer;
+mod policy;
+mod prefetch;
+mod progressive;
+mod pump;
+mod receipt;
+mod registry;
+mod resources;
+mod sampler;
+mod scoped;
+mod shared_context;
+mod snapshot;
+mod tiered_cache;
+mod versioned;
 
+#[cfg(feature = "admin")]
+pub mod admin;
+#[cfg(feature = "cached")]
+mod cached_stores;
+#[cfg(feature = "config")]
+mod config;
+#[cfg(feature = "fault-injection")]
+pub mod faults;
+#[cfg(feature = "file-cache")]
+mod file_cache;
+#[cfg(feature = "redis")]
+mod redis_cache;
+#[cfg(feature = "redis")]
+mod redis_invalidation;
+#[cfg(feature = "testing")]
+pub mod testing;
+#[cfg(feature = "metrics")]
+mod worker_metrics;
 #[cfg(feature = "stats")]
 mod worker_stats;
 
-pub use batch_function::BatchFunction;
+pub use async_cache::AsyncCache;
+pub use batch_function::{BatchFunction, BatchInfo, FnBatch};
+pub use builder::LoaderBuilder;
+pub use cache::{Cache, CachePolicy, LruCache, TtlCache};
+pub use cached_ref::CachedRef;
+pub use cancellation::CancellationToken;
+#[cfg(feature = "config")]
+pub use config::{CacheConfig, LoaderConfig};
+#[cfg(feature = "derive")]
+pub use dataload_rs_derive::batch_fn;
+pub use error::{BackendError, BatchError, FailureReason, LoadFailure, LoaderError, MissingKeys};
+#[cfg(feature = "file-cache")]
+pub use file_cache::FileCache;
+pub use frozen::FrozenLoader;
+pub use group::{GroupBatchFunction, GroupLoader, Grouped};
+pub use health::LoaderHealth;
+pub use in_flight::{InFlightStore, SharedInFlight};
+pub use invalidation::{InvalidationBus, InvalidationSource};
+pub use isolation::IsolationGroup;
+pub use keyed::{KeyOf, Keyed};
 pub use loader::Loader;
+pub use lookup::Lookup;
+pub use mapped::MappedLoader;
+pub use observer::{
+    BatchDispatch, BatchFailed, CachingDisabled, Limit, LoaderObserver, SoftLimitWarning,
+};
+pub use policy::{
+    ClosedPolicy, CostFn, DedupStrategy, DispatchStrategy, DropPolicy, NeighborsFn, OverflowPolicy,
+    PrimePolicy, ResolutionOrder, TraceSampling, TransformFn, UnrequestedKeysPolicy, VersionFn,
+};
+pub use prefetch::PrefetchKey;
+pub use progressive::ProgressiveLoad;
+pub use pump::LoaderPump;
+pub use receipt::{FrameId, LoadReceipt};
+#[cfg(feature = "redis")]
+pub use redis_cache::RedisCache;
+#[cfg(feature = "redis")]
+pub use redis_invalidation::RedisInvalidationSource;
+pub use registry::Loaders;
+pub use resources::{BatchResources, ResourcePool};
+pub use sampler::{BatchSample, SampleFn};
+pub use scoped::{AuthScope, Scoped, ScopedLoader};
+pub use shared_context::SharedContext;
+pub use snapshot::CacheSnapshot;
+pub use tiered_cache::TieredCache;
+pub use versioned::Versioned;
+
+#[doc(hidden)]
+pub mod __private {
+    pub use async_trait::async_trait;
+    pub use futures;
+}
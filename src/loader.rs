@@ -1,12 +1,31 @@
-use std::ops::Drop;
-use std::{collections::HashMap, fmt::Debug};
+use std::collections::HashMap;
+use std::fmt::Debug;
+use std::future::Future;
+use std::slice;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
 
-use tokio::sync::{mpsc, oneshot};
+use futures::stream::{Stream, StreamExt};
+use tokio::sync::{broadcast, mpsc, oneshot};
 
 use crate::{
-    batch_function::BatchFunction,
-    loader_op::{LoadRequest, LoaderOp},
-    loader_worker::LoaderWorker,
+    async_cache::AsyncCache,
+    batch_function::{BatchFunction, FnBatch},
+    builder::LoaderBuilder,
+    cache::CachePolicy,
+    cached_ref::CachedRef,
+    dependents::Dependents,
+    error::{BatchError, LoadFailure, LoaderError, MissingKeys},
+    frozen::FrozenLoader,
+    health::{HealthGauges, LoaderHealth},
+    invalidation::InvalidationBus,
+    loader_op::{EntryPredicate, LoadRequest, LoaderOp},
+    lookup::Lookup,
+    mapped::MappedLoader,
+    policy::ClosedPolicy,
+    progressive::ProgressiveLoad,
+    receipt::LoadReceipt,
+    snapshot::CacheSnapshot,
 };
 
 /// Batch loads values from some expensive resource, primarily intended for mitigating GraphQL's
@@ -21,22 +40,55 @@ use crate::{
 /// `Loader` from multiple parallel tasks, and the loader will enqueue the requested operations on
 /// the request queue for processing by its `LoaderWorker`. The worker processes the requests
 /// sequentially and provides results via response oneshot channels back to the Loader.
+///
+/// Cloning a `Loader` is cheap, and clones share the same worker: loads issued through any clone
+/// are coalesced into the same execution frames and served from the same cache. The worker is
+/// stopped once the last clone is dropped. Use [`Loader::split_isolated`] to obtain a loader with
+/// its own batching domain instead.
 pub struct Loader<K, V>
 where
     K: 'static + Eq + Debug + Send,
     V: 'static + Send + Debug + Clone,
 {
     request_tx: mpsc::UnboundedSender<LoaderOp<K, V>>,
-    load_task_handle: tokio::task::JoinHandle<()>,
+    worker: Arc<WorkerHandle>,
+    health: Arc<HealthGauges>,
+    name: &'static str,
+    closed_policy: ClosedPolicy,
+    dependents: Arc<Dependents<K>>,
+    isolate: IsolateFn<K, V>,
+}
+
+/// Spawns a new worker configured like the loader's own, for [`Loader::split_isolated`].
+pub(crate) type IsolateFn<K, V> = Arc<dyn Fn() -> Loader<K, V> + Send + Sync>;
+
+/// Aborts the worker task once every clone of the `Loader` has been dropped. Loaders driven by a
+/// [`crate::LoaderPump`] or configured with [`crate::DropPolicy::Drain`] have no task to abort.
+struct WorkerHandle(Option<tokio::task::JoinHandle<()>>);
+
+impl Drop for WorkerHandle {
+    fn drop(&mut self) {
+        if let Some(handle) = &self.0 {
+            handle.abort();
+        }
+    }
 }
 
-impl<K, V> Drop for Loader<K, V>
+impl<K, V> Clone for Loader<K, V>
 where
     K: 'static + Eq + Debug + Send,
     V: 'static + Send + Debug + Clone,
 {
-    fn drop(&mut self) {
-        self.load_task_handle.abort();
+    fn clone(&self) -> Self {
+        Self {
+            request_tx: self.request_tx.clone(),
+            worker: self.worker.clone(),
+            health: self.health.clone(),
+            name: self.name,
+            closed_policy: self.closed_policy,
+            dependents: self.dependents.clone(),
+            isolate: self.isolate.clone(),
+        }
     }
 }
 
@@ -46,21 +98,145 @@ where
     V: 'static + Send + Debug + Clone,
 {
     /// Creates a new Loader for the provided BatchFunction and Context type.
-    ///
-    /// Note: the batch function is passed in as a marker for type inference.
-    pub fn new<F, ContextT>(_: F, context: ContextT) -> Self
+    pub fn new<F, ContextT>(batch_fn: F, context: ContextT) -> Self
+    where
+        ContextT: Send + Sync + 'static,
+        F: 'static + BatchFunction<K, V, Context = ContextT> + Send,
+    {
+        Self::builder(batch_fn, context).build()
+    }
+
+    /// Creates a new Loader whose batch function is the async closure `load`, which receives the
+    /// requested keys and a clone of `context` (e.g. `|keys, db| async move { db.fetch(&keys)
+    /// .await }`), so that simple loaders can be defined inline.
+    pub fn from_fn<F, Fut, ContextT>(context: ContextT, load: F) -> Self
+    where
+        ContextT: Clone + Send + Sync + 'static,
+        F: 'static + Fn(Vec<K>, ContextT) -> Fut + Send + Sync,
+        Fut: Future + Send,
+        Fut::Output: IntoIterator<Item = (K, V)> + Send,
+    {
+        Self::new(FnBatch::new(load), context)
+    }
+
+    /// Creates a new Loader for the provided BatchFunction and Context type, identified by `name`
+    /// in diagnostics (see [`LoaderBuilder::name`]).
+    pub fn new_named<F, ContextT>(name: &'static str, batch_fn: F, context: ContextT) -> Self
+    where
+        ContextT: Send + Sync + 'static,
+        F: 'static + BatchFunction<K, V, Context = ContextT> + Send,
+    {
+        Self::builder(batch_fn, context).name(name).build()
+    }
+
+    /// Creates a new Loader for the provided BatchFunction and Context type, backed by `cache`
+    /// instead of the default `HashMap`.
+    pub fn with_cache<F, ContextT, CacheT>(batch_fn: F, context: ContextT, cache: CacheT) -> Self
+    where
+        ContextT: Send + Sync + 'static,
+        F: 'static + BatchFunction<K, V, Context = ContextT> + Send,
+        CacheT: 'static + AsyncCache<K = K, V = V> + Clone + Send,
+    {
+        Self::builder(batch_fn, context).cache(cache).build()
+    }
+
+    /// Returns a [`LoaderBuilder`] for configuring a Loader before it is started.
+    pub fn builder<F, ContextT>(batch_fn: F, context: ContextT) -> LoaderBuilder<K, V, F, ContextT>
     where
         ContextT: Send + Sync + 'static,
         F: 'static + BatchFunction<K, V, Context = ContextT> + Send,
     {
-        let (tx, rx) = mpsc::unbounded_channel();
+        LoaderBuilder::new(batch_fn, context)
+    }
+}
 
+impl<K, V> Loader<K, V>
+where
+    K: 'static + Eq + Debug + Send,
+    V: 'static + Send + Debug + Clone,
+{
+    pub(crate) fn from_parts(
+        request_tx: mpsc::UnboundedSender<LoaderOp<K, V>>,
+        load_task_handle: Option<tokio::task::JoinHandle<()>>,
+        health: Arc<HealthGauges>,
+        name: &'static str,
+        closed_policy: ClosedPolicy,
+        isolate: IsolateFn<K, V>,
+    ) -> Self {
         Self {
-            request_tx: tx,
-            load_task_handle: tokio::task::spawn(
-                LoaderWorker::<K, V, F, HashMap<K, V>, ContextT>::new(HashMap::new(), rx, context)
-                    .start(),
-            ),
+            request_tx,
+            worker: Arc::new(WorkerHandle(load_task_handle)),
+            health,
+            name,
+            closed_policy,
+            dependents: Arc::new(Dependents::default()),
+            isolate,
+        }
+    }
+
+    /// Returns a loader with its own worker, cache and execution frames, configured with the same
+    /// `BatchFunction`, context and options as this one.
+    ///
+    /// Loads issued through the returned loader (and its clones) are never coalesced with loads
+    /// issued through this one, which is useful when a sub-task deliberately wants its own
+    /// batching domain, e.g. a background export running inside a request. The new cache starts
+    /// out as this loader's cache was configured, without any values loaded since, and dependents
+    /// registered with [`Loader::add_dependent`] are not carried over.
+    pub fn split_isolated(&self) -> Self {
+        (self.isolate)()
+    }
+
+    /// Returns the name identifying the loader in diagnostics: the one set with
+    /// [`LoaderBuilder::name`], or its key and value types.
+    pub fn name(&self) -> &'static str {
+        self.name
+    }
+
+    /// Reports the size of the worker's current execution frame.
+    ///
+    /// Health is tracked outside of the request queue, so it remains available while the worker is
+    /// blocked on its `BatchFunction`.
+    pub fn health(&self) -> LoaderHealth {
+        self.health.snapshot()
+    }
+
+    pub(crate) fn health_gauges(&self) -> Arc<HealthGauges> {
+        self.health.clone()
+    }
+
+    /// Enqueues an op for the worker, returning false if the worker is gone and the
+    /// [`ClosedPolicy`] allows it.
+    pub(crate) fn send(&self, op: LoaderOp<K, V>) -> bool {
+        match self.health.enqueue(&self.request_tx, op) {
+            Ok(()) => true,
+            Err(_) => {
+                self.worker_closed();
+                false
+            }
+        }
+    }
+
+    /// Sends an op to the worker, resolving once the worker has applied it. Returns false if the
+    /// worker is no longer running.
+    pub(crate) async fn send_acked(&self, op: LoaderOp<K, V>) -> bool {
+        let (ack_tx, ack_rx) = oneshot::channel();
+        if !self.send(op) || !self.send(LoaderOp::Ack(ack_tx)) {
+            return false;
+        }
+        if ack_rx.await.is_err() {
+            self.worker_closed();
+            return false;
+        }
+        true
+    }
+
+    /// Applies the [`ClosedPolicy`] after a failed interaction with the worker.
+    pub(crate) fn worker_closed(&self) {
+        match self.closed_policy {
+            ClosedPolicy::Panic => panic!("loader worker `{}` is no longer running", self.name),
+            ClosedPolicy::ReturnNone => {
+                tracing::error!(loader = self.name, "loader worker is no longer running")
+            }
         }
     }
 }
@@ -79,9 +255,88 @@ where
     /// frame.
     #[inline]
     pub async fn load(&self, key: K) -> Option<V> {
+        self.send_load_one(key, None).await
+    }
+
+    /// Loads a value that is needed by the provided deadline.
+    ///
+    /// Otherwise behaves identically to [`Loader::load`]. If the loader is configured with an
+    /// urgent deadline threshold (see [`LoaderBuilder::urgent_deadline_threshold`]), requests whose
+    /// deadlines fall within the threshold are dispatched in an earlier, smaller frame.
+    pub async fn load_with_deadline(&self, key: K, deadline: Instant) -> Option<V> {
+        self.send_load_one(key, Some(deadline)).await
+    }
+
+    /// Loads a value, dispatching its frame as soon as the key is staged rather than waiting on the
+    /// configured [`crate::DispatchStrategy`] (e.g. a delay or a manual dispatch).
+    ///
+    /// Otherwise behaves identically to [`Loader::load`]: cached values are returned without
+    /// invoking the `BatchFunction`, and a key that is already being loaded is awaited rather than
+    /// fetched again. Requests staged in the same frame are dispatched along with it.
+    pub async fn load_now(&self, key: K) -> Option<V> {
+        let (response_tx, response_rx) = oneshot::channel();
+        let request = LoadRequest::one(key, response_tx).immediate();
+        if !self.send(LoaderOp::Load(request)) {
+            return None;
+        }
+        response_rx.await.unwrap_or_else(|_| {
+            self.worker_closed();
+            None
+        })
+    }
+
+    /// Loads a value, additionally reporting how the request was served.
+    ///
+    /// Otherwise behaves identically to [`Loader::load`]. Loads whose receipts report the same
+    /// [`LoadReceipt::frame`] were served by the same batch.
+    pub async fn load_with_receipt(&self, key: K) -> (Option<V>, LoadReceipt) {
+        let (response_tx, response_rx) = oneshot::channel();
+        let (receipt_tx, receipt_rx) = oneshot::channel();
+        let request = LoadRequest::one(key, response_tx).with_receipt(receipt_tx);
+        if !self.send(LoaderOp::Load(request)) {
+            return (None, LoadReceipt::default());
+        }
+        let value = response_rx.await.unwrap_or_else(|_| {
+            self.worker_closed();
+            None
+        });
+        (value, receipt_rx.await.unwrap_or_default())
+    }
+
+    /// Returns the cached value for a key, or `None` if it is not cached, without ever staging the
+    /// key for loading.
+    pub async fn get_cached(&self, key: K) -> Option<V> {
+        self.get_many_cached(vec![key]).await.pop().flatten()
+    }
+
+    /// Returns the cached values for the keys, with `None` for keys that are not cached. See
+    /// [`Loader::get_cached`].
+    pub async fn get_many_cached(&self, keys: Vec<K>) -> Vec<Option<V>> {
+        let len = keys.len();
         let (response_tx, response_rx) = oneshot::channel();
-        self.request_tx.send(LoaderOp::Load(LoadRequest::One(key, response_tx))).unwrap();
-        response_rx.await.unwrap()
+        if !self.send(LoaderOp::LoadCached(keys, response_tx)) {
+            return vec![None; len];
+        }
+        response_rx.await.unwrap_or_else(|_| {
+            self.worker_closed();
+            vec![None; len]
+        })
+    }
+
+    /// Returns a read-only view of this loader that serves values from the cache only, without
+    /// ever dispatching a batch.
+    pub fn freeze(&self) -> FrozenLoader<K, V> {
+        FrozenLoader::new(self.clone())
+    }
+
+    /// Returns a view of this loader that applies `map` to every value it returns, sharing this
+    /// loader's worker and cache (e.g. to serve `UserSummary` projections of a
+    /// `Loader<i64, User>`).
+    pub fn map_values<T, F>(&self, map: F) -> MappedLoader<K, V, T>
+    where
+        F: 'static + Fn(V) -> T + Send + Sync,
+    {
+        MappedLoader::new(self.clone(), Arc::new(map))
     }
 
     /// Loads many values at once.
@@ -91,23 +346,335 @@ where
     /// If all the values are already present in the laoder cache, they are returned as soon as the
     /// request is processed by the worker. Otherwise, the keys is enqueue for batch loading in the
     /// next loader execution frame.
+    ///
+    /// Accepts any collection of keys, e.g. a `Vec`, an array, or an iterator mapping caller items
+    /// to their keys.
     #[inline]
-    pub async fn load_many(&self, keys: Vec<K>) -> Vec<Option<V>> {
-        let (response_tx, response_rx) = oneshot::channel();
-        self.request_tx.send(LoaderOp::Load(LoadRequest::Many(keys, response_tx))).unwrap();
-        response_rx.await.unwrap()
+    pub async fn load_many<I>(&self, keys: I) -> Vec<Option<V>>
+    where
+        I: IntoIterator<Item = K>,
+    {
+        self.send_load_many(keys.into_iter().collect(), None).await
+    }
+
+    /// Loads many values at once, returning them keyed by their keys rather than by position.
+    /// Keys whose values could not be loaded are omitted.
+    ///
+    /// Useful when the results are joined against another collection.
+    pub async fn load_map<I>(&self, keys: I) -> HashMap<K, V>
+    where
+        I: IntoIterator<Item = K>,
+        K: std::hash::Hash,
+    {
+        let keys = keys.into_iter().collect::<Vec<_>>();
+        let values = self.send_load_many(keys.clone(), None).await;
+        keys.into_iter().zip(values).filter_map(|(key, value)| Some((key, value?))).collect()
+    }
+
+    /// Loads many values at once for borrowed keys, which are cloned. See [`Loader::load_many`].
+    pub async fn load_slice(&self, keys: &[K]) -> Vec<Option<V>> {
+        self.send_load_many(keys.to_vec(), None).await
+    }
+
+    /// Loads the values for a collection of caller items, returning each item paired with the value
+    /// for the key extracted from it, in order.
+    ///
+    /// Replaces the common pattern of collecting keys, calling [`Loader::load_many`], and zipping
+    /// the results back onto the items.
+    pub async fn load_grouped<I, T, KF>(&self, items: I, key_of: KF) -> Vec<(T, Option<V>)>
+    where
+        I: IntoIterator<Item = T>,
+        KF: Fn(&T) -> K,
+    {
+        let items = items.into_iter().collect::<Vec<_>>();
+        let values = self.load_many(items.iter().map(key_of)).await;
+        items.into_iter().zip(values).collect()
+    }
+
+    /// Loads many values that are needed by the provided deadline.
+    ///
+    /// Otherwise behaves identically to [`Loader::load_many`].
+    pub async fn load_many_with_deadline(&self, keys: Vec<K>, deadline: Instant) -> Vec<Option<V>> {
+        self.send_load_many(keys, Some(deadline)).await
     }
 
     /// Adds a value to the cache.
     #[inline]
     pub fn prime(&self, key: K, value: V) {
-        self.request_tx.send(LoaderOp::Prime(key, value)).unwrap();
+        self.send(LoaderOp::Prime(key, value));
+    }
+
+    /// Adds a value to the cache unless the key is already cached or is being loaded, so that
+    /// out-of-band priming only fills gaps and never clobbers fresher data.
+    #[inline]
+    pub fn prime_if_absent(&self, key: K, value: V) {
+        self.send(LoaderOp::PrimeIfAbsent(key, value));
+    }
+
+    /// Adds a value to the cache, resolving once the worker has applied the prime, so that
+    /// subsequent loads observe it.
+    pub async fn prime_acked(&self, key: K, value: V) {
+        self.send_acked(LoaderOp::Prime(key, value)).await;
     }
 
     /// Adds many values to the cache at once.
     #[inline]
     pub fn prime_many(&self, key_vals: Vec<(K, V)>) {
-        self.request_tx.send(LoaderOp::PrimeMany(key_vals)).unwrap();
+        self.send(LoaderOp::PrimeMany(key_vals));
+    }
+
+    /// Loads many values in two phases: the returned [`ProgressiveLoad`] holds whatever was already
+    /// cached, and [`ProgressiveLoad::complete`] waits for the missing keys to be batch loaded.
+    ///
+    /// Lets latency-sensitive callers render partial data as soon as the worker has processed the
+    /// request, without waiting on the `BatchFunction`.
+    pub async fn load_progressive(&self, keys: Vec<K>) -> ProgressiveLoad<V> {
+        let len = keys.len();
+        let (cached_tx, cached_rx) = oneshot::channel();
+        let (remaining_tx, remaining_rx) = oneshot::channel();
+        let cached = if self.send(LoaderOp::LoadProgressive(keys, cached_tx, remaining_tx)) {
+            cached_rx.await.unwrap_or_else(|_| {
+                self.worker_closed();
+                vec![None; len]
+            })
+        } else {
+            vec![None; len]
+        };
+        ProgressiveLoad::new(cached, remaining_rx)
+    }
+
+    /// Loads a value, falling back to `fallback` if it could not be loaded (e.g. to lazily create a
+    /// record that does not exist yet). The cache is primed with the fallback's value, which is
+    /// then returned.
+    pub async fn load_or_else<F, Fut>(&self, key: K, fallback: F) -> V
+    where
+        F: FnOnce() -> Fut,
+        Fut: Future<Output = V>,
+    {
+        if let Some(value) = self.load(key.clone()).await {
+            return value;
+        }
+        let value = fallback().await;
+        self.prime(key, value.clone());
+        value
+    }
+
+    /// Loads a value that is expected to exist, failing with [`MissingKeys`] instead of resolving
+    /// to `None` if it could not be loaded.
+    pub async fn load_strict(&self, key: K) -> Result<V, MissingKeys<K>> {
+        match self.send_load_one(key.clone(), None).await {
+            Some(value) => Ok(value),
+            None => Err(MissingKeys(vec![key])),
+        }
+    }
+
+    /// Loads many values that are expected to exist, for invariants such as "every order has a
+    /// customer".
+    ///
+    /// Fails with [`MissingKeys`] listing every key that could not be loaded (e.g. because the
+    /// `BatchFunction` did not return it) instead of resolving them to `None`.
+    pub async fn load_many_strict(&self, keys: Vec<K>) -> Result<Vec<V>, MissingKeys<K>> {
+        let mut values = self.send_load_many(keys.clone(), None).await.into_iter();
+        let mut loaded = Vec::with_capacity(keys.len());
+        let mut missing = Vec::new();
+        for key in keys {
+            match values.next().flatten() {
+                Some(value) => loaded.push(value),
+                None => missing.push(key),
+            }
+        }
+        if !missing.is_empty() {
+            return Err(MissingKeys(missing));
+        }
+        Ok(loaded)
+    }
+
+    /// Loads many values, reporting why each key that could not be loaded failed.
+    ///
+    /// Otherwise behaves identically to [`Loader::load_many`]. If the worker stops before
+    /// responding, every key fails with [`BatchError::Cancelled`] regardless of the
+    /// [`ClosedPolicy`].
+    pub async fn load_many_detailed(&self, keys: Vec<K>) -> Vec<Result<V, LoadFailure<K>>> {
+        match self.send_load_detailed(keys.clone()).await {
+            Some(results) => results,
+            None => keys
+                .into_iter()
+                .map(|key| Err(LoadFailure { key, reason: BatchError::Cancelled.into() }))
+                .collect(),
+        }
+    }
+
+    /// Loads a value, surfacing errors reported by the `BatchFunction` (see
+    /// [`crate::BatchInfo::fail`]) rather than resolving them as `None`.
+    ///
+    /// Keys that the `BatchFunction` did not return resolve to `Ok(None)`. If the worker is no
+    /// longer running, fails with [`LoaderError::Closed`] regardless of the [`ClosedPolicy`].
+    pub async fn try_load(&self, key: K) -> Result<Option<V>, LoaderError> {
+        Ok(self.try_load_many(vec![key]).await?.pop().flatten())
+    }
+
+    /// Loads many values, failing with the first error encountered among the keys.
+    ///
+    /// Otherwise behaves identically to [`Loader::try_load`].
+    pub async fn try_load_many(&self, keys: Vec<K>) -> Result<Vec<Option<V>>, LoaderError> {
+        self.send_load_detailed(keys)
+            .await
+            .ok_or(LoaderError::Closed)?
+            .into_iter()
+            .map(|result| match result {
+                Ok(value) => Ok(Some(value)),
+                Err(failure) => match LoaderError::from_reason(failure.reason) {
+                    Some(error) => Err(error),
+                    None => Ok(None),
+                },
+            })
+            .collect()
+    }
+
+    /// Invokes the `BatchFunction` with an empty key set, so that services can fail fast at
+    /// startup when a loader's backend is misconfigured.
+    ///
+    /// Fails with the error reported by the `BatchFunction`, with [`BatchError::Timeout`] if it
+    /// exceeds the batch timeout, or with [`LoaderError::Closed`] if the worker is not running
+    /// regardless of the [`ClosedPolicy`].
+    pub async fn probe(&self) -> Result<(), LoaderError> {
+        self.probe_with(Vec::new()).await.map(|_| ())
+    }
+
+    /// Invokes the `BatchFunction` with a set of canary keys and returns their values in key
+    /// order, bypassing the cache. Keys the `BatchFunction` did not return resolve to `None`.
+    ///
+    /// Fails like [`Loader::probe`].
+    pub async fn probe_with(&self, canary: Vec<K>) -> Result<Vec<Option<V>>, LoaderError> {
+        let (response_tx, response_rx) = oneshot::channel();
+        self.health
+            .enqueue(&self.request_tx, LoaderOp::Probe(canary, response_tx))
+            .map_err(|_| LoaderError::Closed)?;
+        Ok(response_rx.await.map_err(|_| LoaderError::Closed)??)
+    }
+
+    /// Primes the cache with the key value pairs yielded by a stream (e.g. a change-data-capture
+    /// feed or bulk export), returning the number of pairs primed.
+    ///
+    /// Pairs are sent to the worker in chunks of at most `chunk_size`, and the next chunk is not
+    /// pulled from the stream until the worker has applied the previous one, so a fast stream
+    /// cannot flood the request queue.
+    pub async fn prime_stream<S>(&self, stream: S, chunk_size: usize) -> usize
+    where
+        S: Stream<Item = (K, V)>,
+    {
+        let mut primed = 0;
+        let mut chunks = Box::pin(stream.ready_chunks(chunk_size.max(1)));
+        while let Some(chunk) = chunks.next().await {
+            let len = chunk.len();
+            if !self.send_acked(LoaderOp::PrimeMany(chunk)).await {
+                break;
+            }
+            primed += len;
+        }
+        primed
+    }
+
+    /// Dispatches the loads enqueued so far under [`crate::DispatchStrategy::Manual`], resolving
+    /// once their frame has run (and, unless the loader runs concurrent batches, once its batch
+    /// has completed). Under other strategies, resolves once the worker has run a frame for the
+    /// loads enqueued before it.
+    pub async fn dispatch(&self) {
+        let (dispatched_tx, dispatched_rx) = oneshot::channel();
+        if self.send(LoaderOp::Dispatch(dispatched_tx)) && dispatched_rx.await.is_err() {
+            self.worker_closed();
+        }
+    }
+
+    /// Shuts the worker down gracefully, resolving once it has exited.
+    ///
+    /// The worker stops accepting ops, so that later interactions through other clones of the
+    /// loader behave according to its [`ClosedPolicy`]. Ops enqueued before the shutdown are still
+    /// applied: their loads are batch loaded (regardless of the
+    /// [`crate::DispatchStrategy`]) and every pending request is resolved before the worker exits.
+    pub async fn shutdown(self) {
+        let (shutdown_tx, shutdown_rx) = oneshot::channel();
+        if self.send(LoaderOp::Shutdown(shutdown_tx)) && shutdown_rx.await.is_err() {
+            self.worker_closed();
+        }
+    }
+
+    /// Returns a receiver of the values of the key as they flow through the loader, so that
+    /// long-lived tasks (e.g. subscriptions) can react to data changes.
+    ///
+    /// The receiver yields the new value whenever the key is loaded or primed, and `None` whenever
+    /// it is cleared, starting with the changes applied after the ops already enqueued. Watching a
+    /// key does not load it; use [`Loader::get_cached`] for its current value. A receiver that
+    /// falls more than a few updates behind skips the oldest ones (see
+    /// [`broadcast::error::RecvError::Lagged`]). If the worker is no longer running, the receiver
+    /// is closed.
+    pub async fn watch(&self, key: K) -> broadcast::Receiver<Option<V>> {
+        let (response_tx, response_rx) = oneshot::channel();
+        if self.send(LoaderOp::Watch(key, response_tx)) {
+            match response_rx.await {
+                Ok(watch_rx) => return watch_rx,
+                Err(_) => self.worker_closed(),
+            }
+        }
+        broadcast::channel(1).1
+    }
+
+    /// Returns the number of entries held by the cache once the ops already enqueued have been
+    /// applied. Remote caches that cannot cheaply count their entries may report 0 (see
+    /// [`AsyncCache::len`]).
+    pub async fn cache_len(&self) -> usize {
+        let (response_tx, response_rx) = oneshot::channel();
+        if !self.send(LoaderOp::CacheLen(response_tx)) {
+            return 0;
+        }
+        response_rx.await.unwrap_or_else(|_| {
+            self.worker_closed();
+            0
+        })
+    }
+
+    /// Returns whether the cache holds a value for the key once the ops already enqueued have
+    /// been applied, without loading it.
+    pub async fn cache_contains(&self, key: K) -> bool {
+        let (response_tx, response_rx) = oneshot::channel();
+        if !self.send(LoaderOp::CacheContains(key, response_tx)) {
+            return false;
+        }
+        response_rx.await.unwrap_or_else(|_| {
+            self.worker_closed();
+            false
+        })
+    }
+
+    /// Returns the entries held by the cache once the ops already enqueued have been applied, e.g.
+    /// to persist a warm cache across restarts (see [`Loader::import_cache`] and
+    /// [`crate::LoaderBuilder::restore`]).
+    ///
+    /// Caches that cannot enumerate their entries (see [`crate::Cache::retain`]) export an empty
+    /// snapshot, as does a loader whose worker is no longer running.
+    pub async fn export_cache(&self) -> CacheSnapshot<K, V> {
+        let (response_tx, response_rx) = oneshot::channel();
+        if !self.send(LoaderOp::ExportCache(response_tx)) {
+            return CacheSnapshot::new(Vec::new());
+        }
+        CacheSnapshot::new(response_rx.await.unwrap_or_else(|_| {
+            self.worker_closed();
+            Vec::new()
+        }))
+    }
+
+    /// Adds the entries of `snapshot` to the cache, replacing the cached values of their keys.
+    ///
+    /// Unlike primes, imported values are cached as they were exported, without applying the
+    /// [`crate::PrimePolicy`] or transforms again.
+    pub fn import_cache(&self, snapshot: CacheSnapshot<K, V>) {
+        self.send(LoaderOp::ImportCache(snapshot.entries));
+    }
+
+    /// Changes the cache's runtime parameters (e.g. lengthening the TTL during a backend
+    /// brownout). The policy is applied by the worker after the ops already enqueued.
+    pub fn reconfigure(&self, policy: CachePolicy) {
+        self.send(LoaderOp::Reconfigure(policy));
     }
 
     /// Removes a value from the cache.
@@ -115,7 +682,29 @@ where
     /// This key will be reloaded when it is next requested.
     #[inline]
     pub fn clear(&self, key: K) {
-        self.request_tx.send(LoaderOp::Clear(key)).unwrap();
+        self.dependents.cascade(slice::from_ref(&key));
+        self.send(LoaderOp::Clear(key));
+    }
+
+    /// Removes a value from the cache, resolving once the worker has applied the clear, so that
+    /// subsequent loads reload the key.
+    ///
+    /// Dependents are cleared as with [`Loader::clear`], but are not awaited.
+    pub async fn clear_acked(&self, key: K) {
+        self.dependents.cascade(slice::from_ref(&key));
+        self.send_acked(LoaderOp::Clear(key)).await;
+    }
+
+    /// Removes a value from the cache once `delay` has elapsed, e.g. when a cached token is known
+    /// to expire at a specific time.
+    ///
+    /// The worker applies the clear on a timer, so the key is reloaded when it is next requested
+    /// after the delay. Unlike [`Loader::clear`], dependents are not cleared. A delay too long for
+    /// its instant to be represented never elapses, so the value is left cached.
+    pub fn clear_after(&self, key: K, delay: Duration) {
+        if let Some(at) = Instant::now().checked_add(delay) {
+            self.send(LoaderOp::ClearAt(key, at));
+        }
     }
 
     /// Removes multiple values from the cache at once.
@@ -123,6 +712,166 @@ where
     /// These keys will be reloaded when requested.
     #[inline]
     pub fn clear_many(&self, keys: Vec<K>) {
-        self.request_tx.send(LoaderOp::ClearMany(keys)).unwrap();
+        self.dependents.cascade(&keys);
+        self.send(LoaderOp::ClearMany(keys));
+    }
+
+    /// Removes every value from the cache (e.g. after a bulk import invalidated everything), so
+    /// that every key is reloaded when it is next requested.
+    ///
+    /// Dependents registered with [`Loader::add_dependent`] are not cleared, since the cleared
+    /// keys are not known.
+    pub fn clear_all(&self) {
+        self.send(LoaderOp::ClearAll);
+    }
+
+    /// Removes every cached value whose key and value match `predicate`, e.g. every key
+    /// belonging to a tenant, without tracking the individual keys.
+    ///
+    /// Values being loaded when the op is applied are cached as usual, and dependents registered
+    /// with [`Loader::add_dependent`] are not cleared. Caches that cannot enumerate their entries
+    /// (see [`crate::Cache::retain`]) keep them.
+    pub fn clear_matching<P>(&self, predicate: P)
+    where
+        P: 'static + Fn(&K, &V) -> bool + Send + Sync,
+    {
+        self.send(LoaderOp::ClearMatching(EntryPredicate(Box::new(predicate))));
+    }
+
+    /// Registers `dependent` so that clearing keys on this loader also clears the dependent
+    /// loader's keys returned by `dependent_keys` (e.g. clearing an org also clears the
+    /// users-by-org entry for that org).
+    ///
+    /// Clears cascade transitively through the dependent's own dependents, so dependency cycles
+    /// must be avoided. The registration does not keep the dependent's worker running (see
+    /// [`crate::DropPolicy::Drain`]): once every handle to the dependent is dropped, clears stop
+    /// cascading into it.
+    pub fn add_dependent<DK, DV, M>(&self, dependent: &Loader<DK, DV>, dependent_keys: M)
+    where
+        DK: 'static + Eq + Debug + Send + Sync,
+        DV: 'static + Send + Debug + Clone,
+        M: Fn(&K) -> Vec<DK> + Send + Sync + 'static,
+    {
+        let request_tx = dependent.request_tx.downgrade();
+        let health = dependent.health.clone();
+        let dependents = dependent.dependents.clone();
+        self.dependents.register(Box::new(move |keys: &[K]| {
+            let request_tx = match request_tx.upgrade() {
+                Some(request_tx) => request_tx,
+                None => return,
+            };
+            let keys = keys.iter().flat_map(&dependent_keys).collect::<Vec<_>>();
+            if keys.is_empty() {
+                return;
+            }
+            dependents.cascade(&keys);
+            let _ = health.enqueue(&request_tx, LoaderOp::ClearMany(keys));
+        }));
+    }
+
+    /// Subscribes this loader to `bus`, so that invalidating a tag clears the keys returned by
+    /// `keys_for` for that tag (e.g. `UserChanged(id)` clears both the user and their team's
+    /// member list, held by different loaders).
+    ///
+    /// Clears cascade into this loader's dependents, as with [`Loader::clear_many`]. The
+    /// subscription does not keep the worker running (see [`crate::DropPolicy::Drain`]), and is
+    /// dropped from the bus once every handle to this loader is dropped.
+    pub fn subscribe_invalidations<T, M>(&self, bus: &InvalidationBus<T>, keys_for: M)
+    where
+        M: Fn(&T) -> Vec<K> + Send + Sync + 'static,
+    {
+        let request_tx = self.request_tx.downgrade();
+        let health = self.health.clone();
+        let dependents = self.dependents.clone();
+        bus.subscribe(Box::new(move |tag: &T| {
+            let request_tx = match request_tx.upgrade() {
+                Some(request_tx) => request_tx,
+                None => return false,
+            };
+            let keys = keys_for(tag);
+            if keys.is_empty() {
+                return !request_tx.is_closed();
+            }
+            dependents.cascade(&keys);
+            health.enqueue(&request_tx, LoaderOp::ClearMany(keys)).is_ok()
+        }));
+    }
+
+    /// Sends a detailed load request, returning `None` if the worker is gone.
+    async fn send_load_detailed(&self, keys: Vec<K>) -> Option<Vec<Result<V, LoadFailure<K>>>> {
+        let (response_tx, response_rx) = oneshot::channel();
+        let request = LoadRequest::detailed(keys, response_tx);
+        self.health.enqueue(&self.request_tx, LoaderOp::Load(request)).ok()?;
+        response_rx.await.ok()
+    }
+
+    async fn send_load_one(&self, key: K, deadline: Option<Instant>) -> Option<V> {
+        let (response_tx, response_rx) = oneshot::channel();
+        let request = LoadRequest::one(key, response_tx).with_deadline(deadline);
+        if !self.send(LoaderOp::Load(request)) {
+            return None;
+        }
+        response_rx.await.unwrap_or_else(|_| {
+            self.worker_closed();
+            None
+        })
+    }
+
+    async fn send_load_many(&self, keys: Vec<K>, deadline: Option<Instant>) -> Vec<Option<V>> {
+        let len = keys.len();
+        let (response_tx, response_rx) = oneshot::channel();
+        let request = LoadRequest::many(keys, response_tx).with_deadline(deadline);
+        if !self.send(LoaderOp::Load(request)) {
+            return vec![None; len];
+        }
+        response_rx.await.unwrap_or_else(|_| {
+            self.worker_closed();
+            vec![None; len]
+        })
+    }
+}
+
+impl<K, T> Loader<K, Option<T>>
+where
+    K: 'static + Eq + Debug + Ord + Clone + Send + Sync,
+    T: 'static + Send + Debug + Clone,
+{
+    /// Loads an optional value, distinguishing a key cached as mapping to nothing
+    /// ([`Lookup::Absent`]) from a key that could not be loaded ([`Lookup::Missing`]).
+    ///
+    /// Otherwise behaves identically to [`Loader::load`].
+    #[inline]
+    pub async fn lookup(&self, key: K) -> Lookup<T> {
+        self.load(key).await.into()
+    }
+
+    /// Loads many optional values as [`Lookup`]s.
+    ///
+    /// Otherwise behaves identically to [`Loader::load_many`].
+    #[inline]
+    pub async fn lookup_many(&self, keys: Vec<K>) -> Vec<Lookup<T>> {
+        self.load_many(keys).await.into_iter().map(Lookup::from).collect()
+    }
+}
+
+impl<K, T> Loader<K, Arc<T>>
+where
+    K: 'static + Eq + Debug + Ord + Clone + Send + Sync,
+    T: 'static + Send + Sync + Debug,
+{
+    /// Loads a value as a [`CachedRef`] that shares the cached allocation instead of cloning it.
+    ///
+    /// Otherwise behaves identically to [`Loader::load`].
+    #[inline]
+    pub async fn load_ref(&self, key: K) -> Option<CachedRef<T>> {
+        self.load(key).await.map(CachedRef::from)
+    }
+
+    /// Loads many values as [`CachedRef`]s that share the cached allocations.
+    ///
+    /// Otherwise behaves identically to [`Loader::load_many`].
+    #[inline]
+    pub async fn load_many_ref(&self, keys: Vec<K>) -> Vec<Option<CachedRef<T>>> {
+        self.load_many(keys).await.into_iter().map(|v| v.map(CachedRef::from)).collect()
     }
 }
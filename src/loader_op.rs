@@ -1,6 +1,18 @@
+use std::fmt;
 use std::slice;
+#[cfg(feature = "stats")]
+use std::time::Duration;
+use std::time::Instant;
 
-use tokio::sync::oneshot;
+use tokio::sync::{broadcast, oneshot};
+use tracing::Span;
+
+use crate::{
+    batch_function::BatchOutcome,
+    cache::CachePolicy,
+    error::{BatchError, FailureReason, LoadFailure},
+    receipt::{FrameId, LoadReceipt},
+};
 
 /// Set of possible requests that can be sent to the [`LoaderWorker`]
 ///
@@ -13,42 +25,241 @@ pub enum LoaderOp<K, V> {
     /// Add values to the cache that were fetched from elsewhere.
     Prime(K, V),
     PrimeMany(Vec<(K, V)>),
+    /// Add a value to the cache unless the key is cached or being loaded.
+    PrimeIfAbsent(K, V),
     /// Remove values from the cache so that they will be reloaded when they are next requested.
     Clear(K),
     ClearMany(Vec<K>),
+    /// Remove a value from the cache once the instant has passed.
+    ClearAt(K, Instant),
+    /// Remove every value from the cache.
+    ClearAll,
+    /// Remove the values whose entries match the predicate from the cache.
+    ClearMatching(EntryPredicate<K, V>),
+    /// Immediately respond with the cached values for the keys, then load the missing keys and
+    /// respond with their values in key order.
+    LoadProgressive(Vec<K>, oneshot::Sender<Vec<Option<V>>>, oneshot::Sender<Vec<Option<V>>>),
+    /// Respond with the cached values for the keys without loading the missing ones.
+    LoadCached(Vec<K>, oneshot::Sender<Vec<Option<V>>>),
+    /// Respond with a receiver of the values of the key as it is loaded, primed and cleared.
+    Watch(K, oneshot::Sender<broadcast::Receiver<Option<V>>>),
+    /// Respond with the entries held by the cache.
+    ExportCache(oneshot::Sender<Vec<(K, V)>>),
+    /// Add exported entries to the cache, as they were when exported.
+    ImportCache(Vec<(K, V)>),
+    /// Respond with the number of entries held by the cache.
+    CacheLen(oneshot::Sender<usize>),
+    /// Respond with whether the cache holds a value for the key.
+    CacheContains(K, oneshot::Sender<bool>),
+    /// Invoke the `BatchFunction` with the keys, bypassing the cache, and respond with the values
+    /// in key order.
+    Probe(Vec<K>, oneshot::Sender<Result<Vec<Option<V>>, BatchError>>),
+    /// Replace the cache's runtime parameters.
+    Reconfigure(CachePolicy),
+    /// Responds once every op enqueued before it has been applied.
+    Ack(oneshot::Sender<()>),
+    /// Dispatches the staged requests under `DispatchStrategy::Manual`, responding once the frame
+    /// has run.
+    Dispatch(oneshot::Sender<()>),
+    /// Stops accepting ops, responding once every op enqueued before it has been applied and
+    /// every load has been resolved.
+    Shutdown(oneshot::Sender<()>),
+}
+
+type PredicateFn<K, V> = Box<dyn Fn(&K, &V) -> bool + Send + Sync>;
+
+/// Selects cache entries by key and value.
+pub struct EntryPredicate<K, V>(pub PredicateFn<K, V>);
+
+impl<K, V> fmt::Debug for EntryPredicate<K, V> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("EntryPredicate(..)")
+    }
 }
 
 #[derive(Debug)]
-pub enum LoadRequest<K, V> {
+pub struct LoadRequest<K, V> {
+    kind: LoadKind<K, V>,
+    /// The instant by which the requester needs a response, if any.
+    deadline: Option<Instant>,
+    /// Whether staging the request dispatches its frame without waiting on the dispatch strategy.
+    immediate: bool,
+    /// The requester's span when the request was created, linked from the span of the batch that
+    /// serves it.
+    span: Span,
+    /// The frame that served the request, reported through `receipt_tx` if requested.
+    frame: Option<FrameId>,
+    receipt_tx: Option<oneshot::Sender<LoadReceipt>>,
+    /// When the request was created, immediately before it is enqueued.
+    #[cfg(feature = "stats")]
+    enqueued_at: Instant,
+}
+
+#[derive(Debug)]
+enum LoadKind<K, V> {
     One(K, oneshot::Sender<Option<V>>),
     Many(Vec<K>, oneshot::Sender<Vec<Option<V>>>),
+    Detailed(Vec<K>, oneshot::Sender<Vec<Result<V, LoadFailure<K>>>>),
 }
 
 impl<K, V> LoadRequest<K, V>
 where
+    K: Clone + std::fmt::Debug,
     V: Send + Clone + std::fmt::Debug,
 {
+    pub fn one(key: K, response_tx: oneshot::Sender<Option<V>>) -> Self {
+        Self {
+            kind: LoadKind::One(key, response_tx),
+            deadline: None,
+            immediate: false,
+            span: Span::current(),
+            frame: None,
+            receipt_tx: None,
+            #[cfg(feature = "stats")]
+            enqueued_at: Instant::now(),
+        }
+    }
+
+    pub fn many(keys: Vec<K>, response_tx: oneshot::Sender<Vec<Option<V>>>) -> Self {
+        Self {
+            kind: LoadKind::Many(keys, response_tx),
+            deadline: None,
+            immediate: false,
+            span: Span::current(),
+            frame: None,
+            receipt_tx: None,
+            #[cfg(feature = "stats")]
+            enqueued_at: Instant::now(),
+        }
+    }
+
+    pub fn detailed(
+        keys: Vec<K>,
+        response_tx: oneshot::Sender<Vec<Result<V, LoadFailure<K>>>>,
+    ) -> Self {
+        Self {
+            kind: LoadKind::Detailed(keys, response_tx),
+            deadline: None,
+            immediate: false,
+            span: Span::current(),
+            frame: None,
+            receipt_tx: None,
+            #[cfg(feature = "stats")]
+            enqueued_at: Instant::now(),
+        }
+    }
+
+    pub fn with_deadline(mut self, deadline: Option<Instant>) -> Self {
+        self.deadline = deadline;
+        self
+    }
+
+    pub fn immediate(mut self) -> Self {
+        self.immediate = true;
+        self
+    }
+
+    pub fn with_receipt(mut self, receipt_tx: oneshot::Sender<LoadReceipt>) -> Self {
+        self.receipt_tx = Some(receipt_tx);
+        self
+    }
+
+    /// Records the frame whose batch served the request.
+    pub fn set_frame(&mut self, frame: FrameId) {
+        self.frame = Some(frame);
+    }
+
     pub fn keys(&self) -> &[K] {
-        match self {
-            LoadRequest::One(ref key, _) => slice::from_ref(key),
-            LoadRequest::Many(ref keys, _) => keys,
+        match &self.kind {
+            LoadKind::One(ref key, _) => slice::from_ref(key),
+            LoadKind::Many(ref keys, _) | LoadKind::Detailed(ref keys, _) => keys,
         }
     }
 
-    pub fn send_response<'a, I>(self, values: I)
+    pub fn deadline(&self) -> Option<Instant> {
+        self.deadline
+    }
+
+    pub fn is_immediate(&self) -> bool {
+        self.immediate
+    }
+
+    pub fn span(&self) -> &Span {
+        &self.span
+    }
+
+    /// How long the request has been waiting since it was enqueued.
+    #[cfg(feature = "stats")]
+    pub fn queue_wait(&self) -> Duration {
+        self.enqueued_at.elapsed()
+    }
+
+    /// Resolves the request with the values for its keys, in order. Missing values are reported
+    /// as [`FailureReason::Missing`].
+    pub fn send_response<I>(self, values: I)
+    where
+        I: IntoIterator<Item = Option<V>>,
+    {
+        self.send_results(values.into_iter().map(|value| value.ok_or(FailureReason::Missing)));
+    }
+
+    /// Resolves the request with the values for its keys after a batch, in order. Missing values
+    /// are reported as [`FailureReason::Filtered`] if the batch filtered the key, as
+    /// [`BatchError::Backend`] if the batch failed, and as [`FailureReason::Missing`]
+    /// otherwise.
+    pub fn send_loaded<I>(self, values: I, outcome: &BatchOutcome<K>)
+    where
+        I: IntoIterator<Item = Option<V>>,
+        K: Ord,
+    {
+        let results = self
+            .keys()
+            .iter()
+            .zip(values)
+            .map(|(key, value)| match value {
+                Some(value) => Ok(value),
+                None if outcome.filtered.contains(key) => Err(FailureReason::Filtered),
+                None => match &outcome.error {
+                    Some(error) => Err(BatchError::Backend(error.clone()).into()),
+                    None => Err(FailureReason::Missing),
+                },
+            })
+            .collect::<Vec<_>>();
+        self.send_results(results);
+    }
+
+    /// Resolves every key of the request with the same failure.
+    pub fn send_failure(self, reason: FailureReason) {
+        let len = self.keys().len();
+        self.send_results(std::iter::repeat_n(Err(reason), len));
+    }
+
+    fn send_results<I>(self, results: I)
     where
-        I: IntoIterator<Item = Option<&'a V>>,
-        V: Send + 'a,
+        I: IntoIterator<Item = Result<V, FailureReason>>,
     {
-        match self {
-            LoadRequest::One(_, response_tx) => {
-                let response = values.into_iter().next().flatten().cloned();
+        if let Some(receipt_tx) = self.receipt_tx {
+            let _ = receipt_tx.send(LoadReceipt::new(self.frame));
+        }
+        match self.kind {
+            LoadKind::One(_, response_tx) => {
+                let response = results.into_iter().next().and_then(Result::ok);
+                if let Err(e) = response_tx.send(response) {
+                    tracing::error!(?e, "receiver dropped");
+                }
+            }
+            LoadKind::Many(_, response_tx) => {
+                let response = results.into_iter().map(Result::ok).collect::<Vec<_>>();
                 if let Err(e) = response_tx.send(response) {
                     tracing::error!(?e, "receiver dropped");
                 }
             }
-            LoadRequest::Many(_, response_tx) => {
-                let response = values.into_iter().map(|opt| opt.cloned()).collect::<Vec<_>>();
+            LoadKind::Detailed(keys, response_tx) => {
+                let response = keys
+                    .into_iter()
+                    .zip(results)
+                    .map(|(key, result)| result.map_err(|reason| LoadFailure { key, reason }))
+                    .collect::<Vec<_>>();
                 if let Err(e) = response_tx.send(response) {
                     tracing::error!(?e, "receiver dropped");
                 }
@@ -1,19 +1,58 @@
+use std::collections::{BTreeMap, BTreeSet};
 use std::fmt::Debug;
-use std::marker::PhantomData;
+use std::panic::AssertUnwindSafe;
 use std::slice;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
 
-use futures::future::FutureExt;
-use tokio::sync::mpsc;
-use tracing::{span, Level};
+use futures::future::{self, BoxFuture, Either, FutureExt};
+use futures::stream::{BoxStream, FuturesUnordered, StreamExt};
+use tokio::sync::{broadcast, mpsc, oneshot};
+use tracing::{span, Instrument, Level, Span};
 
+#[cfg(feature = "metrics")]
+use crate::worker_metrics::WorkerMetrics;
 #[cfg(feature = "stats")]
 use crate::worker_stats::WorkerStats;
 use crate::{
-    batch_function::BatchFunction,
-    cache::Cache,
-    loader_op::{LoadRequest, LoaderOp},
+    async_cache::AsyncCache,
+    batch_function::{BatchFunction, BatchInfo, BatchOutcome},
+    builder::LoaderOptions,
+    cache::CachePolicy,
+    cancellation::{CancelOnDrop, CancellationToken},
+    error::BatchError,
+    health::HealthGauges,
+    in_flight::InFlightClaim,
+    loader_op::{EntryPredicate, LoadRequest, LoaderOp},
+    negative::NegativeCache,
+    observer::{BatchDispatch, BatchFailed, CachingDisabled, Limit, SoftLimitWarning},
+    policy::{DispatchStrategy, OverflowPolicy, UnrequestedKeysPolicy},
+    receipt::FrameId,
+    sampler::{BatchSample, Sampler},
 };
 
+/// Work that wakes an idle worker up besides its op queue and executing batches.
+enum Wakeup<K> {
+    /// The earliest scheduled clear is due.
+    ClearsDue,
+    /// The `InvalidationSource` yielded keys to clear, or ended.
+    Invalidated(Option<Vec<K>>),
+}
+
+/// Number of updates a watcher of a key may fall behind before missing some, see
+/// [`crate::Loader::watch`].
+const WATCH_CAPACITY: usize = 16;
+
+/// Emits a trace event about a single op if `$traced`, i.e. unless the current frame was left out
+/// by the loader's [`crate::TraceSampling`].
+macro_rules! trace_op {
+    ($traced:expr, $($arg:tt)+) => {
+        if $traced {
+            tracing::trace!($($arg)+);
+        }
+    };
+}
+
 /// A `LoaderWorker` is the "single-thread" worker task that actually does the loading work.
 ///
 /// Once started, it runs in a loop until the parent Loader aborts it's `JoinHandle` or drops the
@@ -41,24 +80,66 @@ use crate::{
 /// outstanding Load requests are resolved from the cache. If the `BatchFunction` did not return a
 /// that was requested (perhaps because of an error), the corresponding Load request is returned a
 /// NoneType on its response channel.
+///
+/// While the `BatchFunction` executes, the worker keeps processing the request queue as in (2), so
+/// that cache hits, primes and clears are not held up by a slow batch. Load requests whose missing
+/// keys are all being loaded by an executing batch are resolved along with it; other missing keys
+/// are staged for the next frame, which starts as soon as the batch completes. When the loader
+/// allows more than one concurrent batch, the next frame is instead dispatched as soon as the
+/// request queue is drained, as long as fewer batches than the maximum are executing.
 pub struct LoaderWorker<K, V, F, CacheT, ContextT>
 where
-    K: 'static + Eq + Debug + Ord + Send + Sync,
+    K: 'static + Eq + Debug + Clone + Ord + Send + Sync,
     V: 'static + Send + Debug + Clone,
     F: 'static + BatchFunction<K, V, Context = ContextT> + Send,
-    CacheT: Cache,
+    CacheT: AsyncCache,
     ContextT: Send + Sync + 'static,
 {
     cache: CacheT,
     request_rx: mpsc::UnboundedReceiver<LoaderOp<K, V>>,
     keys_to_load: Vec<K>,
+    /// The total cost of `keys_to_load` under the configured cost budget, kept up to date as keys
+    /// are staged and dispatched.
+    staged_cost: u64,
     pending_request: Vec<LoadRequest<K, V>>,
-    context: ContextT,
-    phantom_batch_function: PhantomData<F>,
+    context: Arc<ContextT>,
+    options: LoaderOptions<K, V>,
+    health: Arc<HealthGauges>,
+    /// Soft limits that are currently at or above their threshold.
+    soft_limits_reached: Vec<Limit>,
+    /// Number of frames in which the `BatchFunction` has been invoked.
+    frames: u64,
+    /// Number of frames run, and whether op-level tracing is emitted for the current one.
+    frames_run: u64,
+    traced: bool,
+    /// Responders of `LoaderOp::Dispatch` ops, answered once the next frame has run.
+    dispatch_requests: Vec<oneshot::Sender<()>>,
+    /// Responders of `LoaderOp::Shutdown` ops, answered once the worker has finished.
+    shutdown_requests: Vec<oneshot::Sender<()>>,
+    /// Set once an immediate request is staged, so that its frame skips the dispatch strategy.
+    dispatch_now: bool,
+    caching_disabled: bool,
+    /// Keys requested, and cache hits among them, in the current adaptive caching window.
+    window_requested: u64,
+    window_hits: u64,
+    sampler: Option<Sampler<K, V>>,
+    negative: NegativeCache<K>,
+    /// Batches whose `BatchFunction` is executing, in dispatch order, and their futures.
+    executing: Vec<ExecutingBatch<K, V>>,
+    running: FuturesUnordered<BoxFuture<'static, BatchResult<K, V>>>,
+    /// Channels publishing the values of watched keys, see `LoaderOp::Watch`.
+    watchers: BTreeMap<K, broadcast::Sender<Option<V>>>,
+    /// Keys to clear as they are received from the loader's `InvalidationSource`.
+    invalidations: Option<BoxStream<'static, Vec<K>>>,
+    /// Keys to clear once their instant has passed, see `LoaderOp::ClearAt`.
+    scheduled_clears: BTreeMap<Instant, Vec<K>>,
+    batch_fn: Arc<F>,
     debug_name: &'static str,
 
     #[cfg(feature = "stats")]
     stats: WorkerStats,
+    #[cfg(feature = "metrics")]
+    metrics: WorkerMetrics,
 }
 
 impl<K, V, F, CacheT, ContextT> LoaderWorker<K, V, F, CacheT, ContextT>
@@ -66,24 +147,57 @@ where
     K: 'static + Eq + Debug + Clone + Ord + Send + Sync,
     V: 'static + Send + Debug + Clone,
     F: 'static + BatchFunction<K, V, Context = ContextT> + Send,
-    CacheT: Cache<K = K, V = V>,
+    CacheT: AsyncCache<K = K, V = V>,
     ContextT: Send + Sync + 'static,
 {
     pub fn new(
         cache: CacheT,
         request_rx: mpsc::UnboundedReceiver<LoaderOp<K, V>>,
-        context: ContextT,
+        batch_fn: Arc<F>,
+        context: Arc<ContextT>,
+        options: LoaderOptions<K, V>,
+        health: Arc<HealthGauges>,
     ) -> Self {
+        let sampler = options
+            .sampling
+            .as_ref()
+            .map(|(fraction, callback)| Sampler::spawn(*fraction, callback.clone()));
+        let negative = NegativeCache::new(options.negative_caching);
+        let traced = options.trace_sampling.includes(1);
+        let debug_name = options.name.unwrap_or_else(std::any::type_name::<(K, V)>);
+        let invalidations = options.invalidation_source.as_ref().map(|source| source.subscribe());
         Self {
             cache,
             request_rx,
             keys_to_load: Vec::new(),
+            staged_cost: 0,
             pending_request: Vec::new(),
             context,
-            phantom_batch_function: PhantomData,
-            debug_name: std::any::type_name::<(K, V)>(),
+            options,
+            health,
+            soft_limits_reached: Vec::new(),
+            frames: 0,
+            frames_run: 0,
+            traced,
+            dispatch_requests: Vec::new(),
+            shutdown_requests: Vec::new(),
+            dispatch_now: false,
+            caching_disabled: false,
+            window_requested: 0,
+            window_hits: 0,
+            sampler,
+            negative,
+            executing: Vec::new(),
+            running: FuturesUnordered::new(),
+            watchers: BTreeMap::new(),
+            invalidations,
+            scheduled_clears: BTreeMap::new(),
+            batch_fn,
+            debug_name,
             #[cfg(feature = "stats")]
-            stats: WorkerStats::new(std::any::type_name::<(K, V)>()),
+            stats: WorkerStats::new(debug_name),
+            #[cfg(feature = "metrics")]
+            metrics: WorkerMetrics::new(debug_name),
         }
     }
 
@@ -91,72 +205,1297 @@ where
         let span = span!(Level::TRACE, "LoaderWorker", kv = self.debug_name,);
         let _enter = span.enter();
 
-        while let Some(first_op) = self.request_rx.recv().await {
-            self.mux_op(first_op);
-            // Flush remainder of the op queue before executing load.
-            while let Some(Some(op)) = self.request_rx.recv().now_or_never() {
-                self.mux_op(op);
+        // Expired entries are held for as long as they may be served stale.
+        if let Some(max_stale) = self.options.stale_while_revalidate {
+            self.cache.reconfigure(&CachePolicy::new().with_stale_window(max_stale)).await;
+        }
+        loop {
+            // Requests staged while the previous batch was executing start a frame right away.
+            if self.idle() {
+                match self.next_op().await {
+                    Some(op) => self.mux_op(op).await,
+                    None => break,
+                }
+            }
+            self.run_frame().await;
+        }
+        self.finish().await;
+    }
+
+    /// Returns true if there is no frame to run until another op arrives: nothing is staged, or
+    /// the staged requests await a manual dispatch.
+    fn idle(&self) -> bool {
+        self.dispatch_requests.is_empty()
+            && !self.dispatch_now
+            && (self.pending_request.is_empty()
+                || self.options.dispatch_strategy == DispatchStrategy::Manual)
+    }
+
+    /// Waits for the next op, completing executing batches as they finish.
+    async fn next_op(&mut self) -> Option<LoaderOp<K, V>> {
+        loop {
+            match self.next_event().await {
+                Either::Left((frame, loaded_keyvals)) => {
+                    self.complete_load(frame, loaded_keyvals).await
+                }
+                Either::Right(op) => return op,
+            }
+        }
+    }
+
+    /// Waits for either an executing batch to complete or the next op, applying scheduled clears
+    /// and invalidations as they arrive.
+    async fn next_event(&mut self) -> Either<BatchResult<K, V>, Option<LoaderOp<K, V>>> {
+        loop {
+            let due = self.scheduled_clears.keys().next().copied();
+            let invalidations = &mut self.invalidations;
+            let wakeup = Box::pin(async move {
+                let timer = Box::pin(async move {
+                    match due {
+                        Some(due) => tokio::time::sleep_until(due.into()).await,
+                        None => future::pending().await,
+                    }
+                });
+                let invalidated = Box::pin(async move {
+                    match invalidations {
+                        Some(invalidations) => invalidations.next().await,
+                        None => future::pending().await,
+                    }
+                });
+                match future::select(timer, invalidated).await {
+                    Either::Left(_) => Wakeup::ClearsDue,
+                    Either::Right((keys, _)) => Wakeup::Invalidated(keys),
+                }
+            });
+            let event = if self.running.is_empty() {
+                match future::select(wakeup, Box::pin(self.request_rx.recv())).await {
+                    Either::Left((wakeup, _)) => Either::Left(wakeup),
+                    Either::Right((op, _)) => Either::Right(Either::Right(op)),
+                }
+            } else {
+                let next = future::select(self.running.next(), Box::pin(self.request_rx.recv()));
+                match future::select(wakeup, next).await {
+                    Either::Left((wakeup, _)) => Either::Left(wakeup),
+                    Either::Right((Either::Left((result, _)), _)) => {
+                        Either::Right(Either::Left(result.expect("a batch was executing")))
+                    }
+                    Either::Right((Either::Right((op, _)), _)) => Either::Right(Either::Right(op)),
+                }
+            };
+            match event {
+                Either::Left(Wakeup::ClearsDue) => self.clear_due().await,
+                Either::Left(Wakeup::Invalidated(Some(keys))) => {
+                    trace_op!(self.traced, ?keys, "applying invalidation");
+                    self.clear(&keys).await;
+                }
+                Either::Left(Wakeup::Invalidated(None)) => {
+                    tracing::warn!(loader = self.debug_name, "invalidation source ended");
+                    self.invalidations = None;
+                }
+                Either::Right(event) => return event,
+            }
+        }
+    }
+
+    /// Once the request queue is closed, loads the requests still awaiting a manual dispatch and
+    /// completes every executing batch, then acknowledges the shutdown requests.
+    async fn finish(&mut self) {
+        if !self.pending_request.is_empty() {
+            self.execute_load().await;
+        }
+        while !self.running.is_empty() {
+            self.await_batch().await;
+        }
+        for shutdown_tx in self.shutdown_requests.drain(..) {
+            let _ = shutdown_tx.send(());
+        }
+    }
+
+    /// Processes the queued ops, along with any already staged requests, as a single execution
+    /// frame.
+    async fn run_frame(&mut self) {
+        // Flush remainder of the op queue before executing load.
+        self.drain_queue().await;
+        let dispatching = !self.pending_request.is_empty()
+            && (self.options.dispatch_strategy != DispatchStrategy::Manual
+                || !self.dispatch_requests.is_empty()
+                || self.dispatch_now);
+        if dispatching && !self.dispatch_now {
+            match self.options.dispatch_strategy {
+                DispatchStrategy::Immediate | DispatchStrategy::Manual => {}
+                DispatchStrategy::Delay(delay) => {
+                    self.collect_until(Instant::now() + delay, None).await
+                }
+                DispatchStrategy::MinBatchSize(min_keys, max_wait) => {
+                    self.collect_until(Instant::now() + max_wait, Some(min_keys)).await
+                }
+                DispatchStrategy::Yields(yields) => {
+                    for _ in 0..yields {
+                        if self.dispatch_now {
+                            break;
+                        }
+                        tokio::task::yield_now().await;
+                        self.drain_queue().await;
+                    }
+                }
+            }
+        }
+        self.health.set_staged(self.pending_request.len(), self.keys_to_load.len());
+        self.check_soft_limit(Limit::PendingRequests, self.pending_request.len());
+        self.check_soft_limit(Limit::StagedKeys, self.keys_to_load.len());
+        // Dispatch requests received while this frame's batches execute apply to the next frame.
+        let dispatch_requests = if dispatching || self.pending_request.is_empty() {
+            std::mem::take(&mut self.dispatch_requests)
+        } else {
+            Vec::new()
+        };
+        if dispatching {
+            self.dispatch_now = false;
+            if let Some(threshold) = self.options.urgent_deadline_threshold {
+                self.execute_urgent_load(threshold).await;
             }
             if !self.pending_request.is_empty() {
+                self.smooth_cold_start().await;
                 self.execute_load().await;
             }
         }
+        for dispatched_tx in dispatch_requests {
+            let _ = dispatched_tx.send(());
+        }
+        if let Some(within) = self.options.refresh_ahead {
+            self.refresh_ahead(within).await;
+        }
+        // Keys staged without a request (refreshed or revalidated keys) are loaded on a batch of
+        // their own.
+        if self.pending_request.is_empty() && !self.keys_to_load.is_empty() {
+            self.execute_load().await;
+        }
+        self.clear_due().await;
+        self.cache.sweep().await;
+        self.negative.purge();
+        self.health.set_cache_entries(self.cache.len());
+        #[cfg(feature = "metrics")]
+        self.metrics.set_cache_entries(self.cache.len());
+        self.check_soft_limit(Limit::CacheEntries, self.cache.len());
+        self.check_hit_rate();
+        self.frames_run += 1;
+        self.traced = self.options.trace_sampling.includes(self.frames_run + 1);
     }
 
-    #[tracing::instrument(skip(self))]
-    fn mux_op(&mut self, op: LoaderOp<K, V>) {
-        match op {
-            LoaderOp::Load(request) => {
-                #[cfg(feature = "stats")]
-                self.stats.record_load_request(request.keys().len() as u32);
+    /// Processes the ops already on the request queue. Under the spill overflow policy, a full
+    /// frame leaves the remaining ops queued for the next frame.
+    async fn drain_queue(&mut self) {
+        while !(self.frame_full() && self.options.overflow_policy == OverflowPolicy::Spill) {
+            match self.request_rx.recv().now_or_never() {
+                Some(Some(op)) => self.mux_op(op).await,
+                _ => break,
+            }
+        }
+    }
 
-                let cached = self.cache.get_key_vals(request.keys());
-                let keys_to_load = cached
-                    .iter()
-                    .filter_map(|(k, v)| if v.is_none() { Some((**k).clone()) } else { None })
-                    .collect::<Vec<_>>();
+    /// Keeps staging ops until `deadline`, until `min_keys` keys are staged, until an immediate
+    /// request is staged, or until the frame is full under the spill overflow policy, completing
+    /// executing batches as they finish.
+    async fn collect_until(&mut self, deadline: Instant, min_keys: Option<usize>) {
+        let mut sleep = Box::pin(tokio::time::sleep_until(deadline.into()));
+        while !(self.dispatch_now
+            || min_keys.is_some_and(|min_keys| self.keys_to_load.len() >= min_keys)
+            || (self.frame_full() && self.options.overflow_policy == OverflowPolicy::Spill))
+        {
+            let event = match future::select(&mut sleep, Box::pin(self.next_event())).await {
+                Either::Left(_) => break,
+                Either::Right((event, _)) => event,
+            };
+            match event {
+                Either::Left((frame, loaded_keyvals)) => {
+                    self.complete_load(frame, loaded_keyvals).await
+                }
+                Either::Right(Some(op)) => self.mux_op(op).await,
+                Either::Right(None) => break,
+            }
+        }
+    }
+
+    fn record_load_request(&mut self, keys_requested: usize, cache_hits: usize) {
+        self.health.record_load_request(keys_requested, cache_hits);
+        #[cfg(feature = "metrics")]
+        self.metrics.record_load_request(keys_requested, cache_hits);
+        self.window_requested += keys_requested as u64;
+        self.window_hits += cache_hits as u64;
+    }
+
+    /// Disables caching of loaded values once a window's hit rate falls below the configured
+    /// minimum.
+    fn check_hit_rate(&mut self) {
+        let (min_hit_rate, window) = match self.options.adaptive_caching {
+            Some(adaptive_caching) if !self.caching_disabled => adaptive_caching,
+            _ => return,
+        };
+        if self.window_requested < window {
+            return;
+        }
+        let hit_rate = self.window_hits as f64 / self.window_requested as f64;
+        self.window_requested = 0;
+        self.window_hits = 0;
+        if hit_rate >= min_hit_rate {
+            return;
+        }
+        self.caching_disabled = true;
+        self.health.set_caching_disabled();
+        tracing::warn!(loader = self.debug_name, ?hit_rate, ?min_hit_rate, "disabling caching");
+        if let Some(observer) = &self.options.observer {
+            observer.on_caching_disabled(&CachingDisabled { loader: self.debug_name, hit_rate });
+        }
+    }
+
+    /// Warns the observer when `limit` reaches its soft threshold.
+    fn check_soft_limit(&mut self, limit: Limit, current: usize) {
+        let threshold = match self.options.soft_limits.iter().find(|(l, _)| *l == limit) {
+            Some((_, threshold)) => *threshold,
+            None => return,
+        };
+        let was_reached = self.soft_limits_reached.contains(&limit);
+        if current < threshold {
+            if was_reached {
+                self.soft_limits_reached.retain(|l| *l != limit);
+            }
+            return;
+        }
+        if was_reached {
+            return;
+        }
+        self.soft_limits_reached.push(limit);
+        tracing::warn!(loader = self.debug_name, ?limit, threshold, current, "soft limit reached");
+        if let Some(observer) = &self.options.observer {
+            observer.on_soft_limit(&SoftLimitWarning {
+                loader: self.debug_name,
+                limit,
+                threshold,
+                current,
+            });
+        }
+    }
+
+    /// Counts a failure in the worker stats and notifies the observer.
+    fn report_batch_error(&mut self, frame: Option<FrameId>, keys: usize, error: BatchError) {
+        #[cfg(feature = "stats")]
+        self.stats.record_batch_error(&error);
+        #[cfg(feature = "metrics")]
+        self.metrics.record_batch_error(&error);
+        if let Some(observer) = &self.options.observer {
+            observer.on_batch_error(&BatchFailed { loader: self.debug_name, frame, keys, error });
+        }
+    }
+
+    /// Returns true once the current execution frame has reached one of its configured caps.
+    fn frame_full(&self) -> bool {
+        self.options.max_pending_requests.is_some_and(|max| self.pending_request.len() >= max)
+            || self.options.max_staged_keys.is_some_and(|max| self.keys_to_load.len() >= max)
+            || self
+                .options
+                .cost_budget
+                .as_ref()
+                .is_some_and(|(budget, _)| self.staged_cost >= *budget)
+    }
+
+    /// Stages keys for the next batch.
+    fn stage_keys(&mut self, keys: Vec<K>) {
+        if let Some((_, cost_of)) = &self.options.cost_budget {
+            self.staged_cost += keys.iter().map(|key| cost_of(key)).sum::<u64>();
+        }
+        self.keys_to_load.extend(keys);
+    }
+
+    /// Recomputes the cost of the staged keys after they were rearranged wholesale.
+    fn recount_staged_cost(&mut self) {
+        if let Some((_, cost_of)) = &self.options.cost_budget {
+            self.staged_cost = self.keys_to_load.iter().map(|key| cost_of(key)).sum();
+        }
+    }
 
-                #[cfg(feature = "stats")]
-                self.stats.record_cache_hits((cached.len() - keys_to_load.len()) as u32);
+    async fn mux_op(&mut self, op: LoaderOp<K, V>) {
+        let span = if self.traced { tracing::info_span!("mux_op", ?op) } else { Span::none() };
+        self.handle_op(op).instrument(span).await
+    }
 
-                tracing::trace!(requested_keys = ?request.keys(), ?keys_to_load);
-                if keys_to_load.is_empty() {
-                    let values = cached.into_iter().map(|(_k, v)| v).collect::<Vec<_>>();
-                    request.send_response(values);
+    async fn handle_op(&mut self, op: LoaderOp<K, V>) {
+        self.health.op_dequeued();
+        match op {
+            LoaderOp::Load(request) => self.stage_load(request).await,
+            LoaderOp::LoadProgressive(keys, cached_tx, remaining_tx) => {
+                let cached = self.cache.get(&keys).await;
+                let missing_keys = keys
+                    .into_iter()
+                    .zip(cached.iter())
+                    .filter_map(|(k, v)| if v.is_none() { Some(k) } else { None })
+                    .collect::<Vec<_>>();
+                let hits = cached.len() - missing_keys.len();
+                self.record_load_request(hits, hits);
+                if let Err(e) = cached_tx.send(cached) {
+                    tracing::error!(?e, "receiver dropped");
+                }
+                self.stage_load(LoadRequest::many(missing_keys, remaining_tx)).await;
+            }
+            LoaderOp::LoadCached(keys, response_tx) => {
+                let cached = self.cache.get(&keys).await;
+                let hits = cached.iter().filter(|v| v.is_some()).count();
+                self.record_load_request(cached.len(), hits);
+                if let Err(e) = response_tx.send(cached) {
+                    tracing::error!(?e, "receiver dropped");
+                }
+            }
+            LoaderOp::CacheLen(response_tx) => {
+                let _ = response_tx.send(self.cache.len());
+            }
+            LoaderOp::CacheContains(key, response_tx) => {
+                let cached = self.cache.get(slice::from_ref(&key)).await;
+                let _ = response_tx.send(cached.into_iter().any(|value| value.is_some()));
+            }
+            LoaderOp::Prime(key, value) => self.prime(key, value).await,
+            LoaderOp::PrimeMany(key_vals) => {
+                for (key, value) in key_vals {
+                    self.prime(key, value).await;
+                }
+            }
+            LoaderOp::PrimeIfAbsent(key, value) => {
+                let cached = self.cache.get(slice::from_ref(&key)).await;
+                let loading = self.executing.iter().any(|batch| batch.keys.contains(&key));
+                if cached.into_iter().any(|value| value.is_some()) || loading {
+                    trace_op!(self.traced, ?key, "prime discarded: key is present");
                 } else {
-                    self.keys_to_load.extend(keys_to_load);
-                    self.pending_request.push(request);
+                    self.prime(key, value).await;
+                }
+            }
+            LoaderOp::Clear(key) => self.clear(slice::from_ref(&key)).await,
+            LoaderOp::ClearMany(keys) => self.clear(&keys).await,
+            LoaderOp::ClearAt(key, at) => self.scheduled_clears.entry(at).or_default().push(key),
+            LoaderOp::ClearAll => {
+                tracing::debug!(loader = self.debug_name, "flushing cache");
+                for batch in &mut self.executing {
+                    batch.superseded.extend(batch.keys.iter().cloned());
+                }
+                self.negative.clear();
+                self.cache.flush().await;
+                let watched = self.watchers.keys().cloned().collect::<Vec<_>>();
+                for key in &watched {
+                    self.notify(key, None);
                 }
             }
-            LoaderOp::Prime(key, value) => self.cache.insert(key, value),
-            LoaderOp::PrimeMany(key_vals) => self.cache.insert_many(key_vals),
-            LoaderOp::Clear(key) => self.cache.remove(slice::from_ref(&key)),
-            LoaderOp::ClearMany(keys) => self.cache.remove(&keys),
+            LoaderOp::ClearMatching(EntryPredicate(predicate)) => {
+                let watching = !self.watchers.is_empty();
+                let mut removed = Vec::new();
+                self.cache
+                    .retain(&mut |key, value| {
+                        let matches = predicate(key, value);
+                        if matches && watching {
+                            removed.push(key.clone());
+                        }
+                        !matches
+                    })
+                    .await;
+                for key in &removed {
+                    self.notify(key, None);
+                }
+            }
+            LoaderOp::ExportCache(response_tx) => {
+                let mut entries = BTreeMap::new();
+                self.cache
+                    .retain(&mut |key, value| {
+                        entries.entry(key.clone()).or_insert_with(|| value.clone());
+                        true
+                    })
+                    .await;
+                let _ = response_tx.send(entries.into_iter().collect());
+            }
+            LoaderOp::ImportCache(entries) => self.import(entries).await,
+            LoaderOp::Watch(key, response_tx) => {
+                let watch_rx = match self.watchers.get(&key) {
+                    Some(watch_tx) => watch_tx.subscribe(),
+                    None => {
+                        let (watch_tx, watch_rx) = broadcast::channel(WATCH_CAPACITY);
+                        self.watchers.insert(key, watch_tx);
+                        watch_rx
+                    }
+                };
+                let _ = response_tx.send(watch_rx);
+            }
+            LoaderOp::Probe(keys, response_tx) => {
+                let result = self.probe(keys).await;
+                if let Err(e) = response_tx.send(result) {
+                    tracing::error!(?e, "receiver dropped");
+                }
+            }
+            LoaderOp::Reconfigure(policy) => {
+                tracing::debug!(?policy, "reconfiguring cache");
+                if let Some(negative_ttl) = policy.negative_ttl() {
+                    self.negative.set_ttl(negative_ttl);
+                }
+                self.cache.reconfigure(&policy).await;
+            }
+            LoaderOp::Ack(ack_tx) => {
+                let _ = ack_tx.send(());
+            }
+            LoaderOp::Dispatch(dispatched_tx) => self.dispatch_requests.push(dispatched_tx),
+            LoaderOp::Shutdown(shutdown_tx) => {
+                tracing::debug!(loader = self.debug_name, "shutting down");
+                // Ops already enqueued are still received.
+                self.request_rx.close();
+                self.shutdown_requests.push(shutdown_tx);
+            }
         }
     }
 
-    #[tracing::instrument(skip(self))]
-    async fn execute_load(&mut self) {
-        self.keys_to_load.sort();
+    /// Resolves a load request from the cache, or stages its missing keys for the next batch.
+    async fn stage_load(&mut self, request: LoadRequest<K, V>) {
+        #[cfg(feature = "stats")]
+        self.stats.record_load_request(request.keys().len() as u32, request.queue_wait());
+
+        let values = self.cache.get(request.keys()).await;
+        // Keys remembered as missing count as cache hits that resolve to `None`.
+        let keys_to_load = request
+            .keys()
+            .iter()
+            .zip(values.iter())
+            .filter(|(k, v)| v.is_none() && !self.negative.contains(k))
+            .map(|(k, _)| k.clone())
+            .collect::<Vec<_>>();
 
         #[cfg(feature = "stats")]
-        self.stats.record_load_exec(self.keys_to_load.len() as u32);
+        self.stats.record_cache_hits((values.len() - keys_to_load.len()) as u32);
+        let hits = values.len() - keys_to_load.len();
+        self.record_load_request(values.len(), hits);
+
+        trace_op!(self.traced, requested_keys = ?request.keys(), ?keys_to_load);
+        if keys_to_load.is_empty() {
+            // The values read from the cache are handed over as is.
+            request.send_response(values);
+        } else if let Some(stale) = self.serve_stale(&keys_to_load).await {
+            let values = request
+                .keys()
+                .iter()
+                .zip(values)
+                .map(|(key, value)| value.or_else(|| stale.get(key).cloned()))
+                .collect::<Vec<_>>();
+            request.send_response(values);
+        } else if let Some(batch) =
+            self.executing.iter_mut().find(|batch| batch.covers(&keys_to_load))
+        {
+            trace_op!(self.traced, ?keys_to_load, frame = ?batch.frame, "joining executing batch");
+            #[cfg(feature = "stats")]
+            self.stats.record_in_flight_hits(keys_to_load.len() as u32);
+            batch.span.follows_from(request.span());
+            batch.pending.push(request);
+        } else if self.frame_full() {
+            tracing::warn!(requested_keys = ?request.keys(), "frame full, rejecting load");
+            self.report_batch_error(None, request.keys().len(), BatchError::Overloaded);
+            request.send_failure(BatchError::Overloaded.into());
+        } else {
+            // Values of keys loaded by executing batches are read from the cache once they
+            // complete.
+            #[cfg(feature = "stats")]
+            let missing = keys_to_load.len();
+            let keys_to_load = if self.caching_disabled {
+                keys_to_load
+            } else {
+                keys_to_load.into_iter().filter(|key| !self.is_executing(key)).collect::<Vec<_>>()
+            };
+            #[cfg(feature = "stats")]
+            self.stats.record_in_flight_hits((missing - keys_to_load.len()) as u32);
+            self.stage_prefetch(&keys_to_load).await;
+            self.stage_keys(keys_to_load);
+            self.dispatch_now |= request.is_immediate();
+            self.pending_request.push(request);
+        }
+    }
 
-        self.keys_to_load.dedup();
-        let loaded_keyvals = F::load(&self.keys_to_load, &self.context).await;
-        tracing::trace!(load_size = loaded_keyvals.len(), ?loaded_keyvals);
+    /// Under stale-while-revalidate, returns the stale values of the missed keys, and stages the
+    /// ones that are not already being loaded for reloading. Returns `None` unless every missed
+    /// key has a stale value.
+    async fn serve_stale(&mut self, missed: &[K]) -> Option<BTreeMap<K, V>> {
+        let max_stale = self.options.stale_while_revalidate?;
+        let stale = self.cache.get_stale(missed, max_stale).await;
+        if stale.iter().any(|value| value.is_none()) {
+            return None;
+        }
+        let revalidated = missed
+            .iter()
+            .filter(|key| !self.is_executing(key) && !self.keys_to_load.contains(key))
+            .cloned()
+            .collect::<Vec<_>>();
+        trace_op!(self.traced, ?revalidated, "serving stale values");
+        self.stage_keys(revalidated);
+        Some(missed.iter().cloned().zip(stale.into_iter().flatten()).collect())
+    }
 
+    /// Stages the uncached keys within the configured prefetch window of the missed keys.
+    async fn stage_prefetch(&mut self, missed: &[K]) {
+        let prefetch = match &self.options.prefetch {
+            Some(prefetch) => prefetch,
+            None => return,
+        };
+        let mut candidates = missed.iter().flat_map(|key| prefetch(key)).collect::<Vec<_>>();
+        candidates.retain(|key| {
+            !missed.contains(key)
+                && !self.keys_to_load.contains(key)
+                && !self.negative.contains(key)
+                && !self.is_executing(key)
+        });
+        // The missed keys are staged after their prefetched neighbors.
+        if let Some(max_staged_keys) = self.options.max_staged_keys {
+            candidates
+                .truncate(max_staged_keys.saturating_sub(self.keys_to_load.len() + missed.len()));
+        }
+        if candidates.is_empty() {
+            return;
+        }
+        let cached = self.cache.get(&candidates).await;
+        let prefetched = candidates
+            .into_iter()
+            .zip(cached)
+            .filter_map(|(key, value)| if value.is_none() { Some(key) } else { None })
+            .collect::<Vec<_>>();
+        trace_op!(self.traced, ?prefetched, "prefetching keys");
+        self.stage_keys(prefetched);
+    }
+
+    /// Removes keys from the cache, so that they are reloaded when next requested.
+    async fn clear(&mut self, keys: &[K]) {
+        self.supersede(keys);
+        self.negative.forget(keys);
+        self.cache.remove(keys).await;
+        for key in keys {
+            self.notify(key, None);
+        }
+    }
+
+    /// Publishes the new value of a key to its watchers, if any, dropping the channel once every
+    /// receiver is gone.
+    fn notify(&mut self, key: &K, value: Option<&V>) {
+        if let Some(watch_tx) = self.watchers.get(key) {
+            if watch_tx.send(value.cloned()).is_err() {
+                self.watchers.remove(key);
+            }
+        }
+    }
+
+    /// Applies the scheduled clears that are due.
+    async fn clear_due(&mut self) {
+        let pending = self.scheduled_clears.split_off(&Instant::now());
+        let due = std::mem::replace(&mut self.scheduled_clears, pending);
+        for (_, keys) in due {
+            trace_op!(self.traced, ?keys, "applying scheduled clear");
+            self.clear(&keys).await;
+        }
+    }
+
+    /// Inserts exported entries into the cache, bypassing the `PrimePolicy` and transforms that
+    /// were applied before they were exported.
+    async fn import(&mut self, entries: Vec<(K, V)>) {
+        let keys = entries.iter().map(|(key, _)| key.clone()).collect::<Vec<_>>();
+        self.supersede(&keys);
+        self.negative.forget(&keys);
+        for (key, value) in &entries {
+            self.notify(key, Some(value));
+        }
+        self.cache.insert_many(entries).await;
+    }
+
+    /// Inserts a primed value into the cache, subject to the configured `PrimePolicy`.
+    async fn prime(&mut self, key: K, value: V) {
+        let value = self.transform(&key, value);
+        self.negative.forget(slice::from_ref(&key));
+        let cached = self.cache.get(slice::from_ref(&key)).await;
+        let replace = match cached.into_iter().next().flatten() {
+            Some(cached) => self.options.prime_policy.should_replace(&cached, &value),
+            None => true,
+        };
+        if replace {
+            self.supersede(slice::from_ref(&key));
+            self.notify(&key, Some(&value));
+            self.cache.insert(key, value).await;
+        } else {
+            trace_op!(self.traced, ?key, "prime discarded by policy");
+        }
+    }
+
+    /// Applies the `UnrequestedKeysPolicy` to the pairs the `BatchFunction` returned for keys
+    /// outside of `requested`.
+    fn handle_unrequested(
+        &mut self,
+        requested: &BTreeSet<K>,
+        loaded_keyvals: Vec<(K, V)>,
+    ) -> Vec<(K, V)> {
+        let unrequested = loaded_keyvals.iter().filter(|(key, _)| !requested.contains(key)).count();
+        if unrequested == 0 {
+            return loaded_keyvals;
+        }
         #[cfg(feature = "stats")]
-        self.stats.record_load_exec_completed(
-            self.keys_to_load.len() as u32,
-            loaded_keyvals.len() as u32,
+        self.stats.record_unrequested_items(unrequested as u32);
+        match self.options.unrequested_keys {
+            UnrequestedKeysPolicy::Accept => loaded_keyvals,
+            UnrequestedKeysPolicy::Warn => {
+                tracing::warn!(
+                    loader = self.debug_name,
+                    unrequested,
+                    "batch function returned unrequested keys"
+                );
+                loaded_keyvals
+            }
+            UnrequestedKeysPolicy::Reject => {
+                trace_op!(self.traced, unrequested, "discarding unrequested keys");
+                loaded_keyvals.into_iter().filter(|(key, _)| requested.contains(key)).collect()
+            }
+        }
+    }
+
+    /// Runs `value` through the configured transforms.
+    fn transform(&self, key: &K, value: V) -> V {
+        self.options.transforms.iter().fold(value, |value, transform| transform(key, value))
+    }
+
+    /// Stages the hot entries that expire within `within` for reloading.
+    async fn refresh_ahead(&mut self, within: Duration) {
+        let mut candidates = self.cache.refresh_candidates(within).await;
+        candidates.retain(|key| !self.is_executing(key) && !self.keys_to_load.contains(key));
+        if candidates.is_empty() {
+            return;
+        }
+        trace_op!(self.traced, ?candidates, "refreshing keys ahead of expiry");
+        self.stage_keys(candidates);
+    }
+
+    /// Returns true if `key` is being loaded by an executing batch.
+    fn is_executing(&self, key: &K) -> bool {
+        self.executing.iter().any(|batch| batch.keys.contains(key))
+    }
+
+    /// Moves the requests that are also waiting on keys being loaded by another executing batch
+    /// to that batch, which resolves them from the cache once it completes. Returns the requests
+    /// that can be resolved now.
+    fn hand_over(&mut self, pending: Vec<LoadRequest<K, V>>) -> Vec<LoadRequest<K, V>> {
+        let mut resolvable = Vec::new();
+        for request in pending {
+            let waiting_on = self
+                .executing
+                .iter_mut()
+                .find(|batch| request.keys().iter().any(|key| batch.keys.contains(key)));
+            match waiting_on {
+                Some(batch) => batch.pending.push(request),
+                None => resolvable.push(request),
+            }
+        }
+        resolvable
+    }
+
+    /// Keeps executing batches from caching their loaded values for `keys`, which were primed or
+    /// cleared after the batches started.
+    fn supersede(&mut self, keys: &[K]) {
+        for batch in &mut self.executing {
+            batch.superseded.extend(keys.iter().filter(|key| batch.keys.contains(key)).cloned());
+        }
+    }
+
+    /// Returns true if an armed fault point discards the next response.
+    #[cfg(feature = "fault-injection")]
+    fn drop_response(&self) -> bool {
+        let drop = self.options.faults.as_ref().is_some_and(|faults| faults.should_drop_response());
+        if drop {
+            tracing::warn!("fault injected: dropping response");
+        }
+        drop
+    }
+
+    #[cfg(not(feature = "fault-injection"))]
+    fn drop_response(&self) -> bool {
+        false
+    }
+
+    /// Inserts values returned by the `BatchFunction` into the cache.
+    ///
+    /// When a version extractor is configured, a loaded value never replaces a cached value with a
+    /// strictly greater version (e.g. one primed by a mutation while the batch was in flight).
+    ///
+    /// Returns the inserted values, so that the batch's requests are answered with them even if
+    /// the cache evicts or fails to store them.
+    async fn insert_loaded(&mut self, loaded_keyvals: Vec<(K, V)>) -> BTreeMap<K, V> {
+        let version_of = match &self.options.version_of {
+            Some(version_of) => version_of.clone(),
+            None => {
+                for (key, value) in &loaded_keyvals {
+                    self.notify(key, Some(value));
+                }
+                let inserted = loaded_keyvals.iter().cloned().collect();
+                self.cache.insert_many(loaded_keyvals).await;
+                return inserted;
+            }
+        };
+        let keys = loaded_keyvals.iter().map(|(key, _)| key.clone()).collect::<Vec<_>>();
+        let cached = self.cache.get(&keys).await;
+        let traced = self.traced;
+        let keyvals = loaded_keyvals
+            .into_iter()
+            .zip(cached)
+            .filter(|((key, value), cached)| match cached {
+                Some(cached) if version_of(cached) > version_of(value) => {
+                    trace_op!(traced, ?key, "loaded value is older than cached value");
+                    false
+                }
+                _ => true,
+            })
+            .map(|(keyval, _)| keyval)
+            .collect::<Vec<_>>();
+        for (key, value) in &keyvals {
+            self.notify(key, Some(value));
+        }
+        let inserted = keyvals.iter().cloned().collect();
+        self.cache.insert_many(keyvals).await;
+        inserted
+    }
+
+    /// Snapshots the cached neighbors of the staged keys for the `BatchFunction`.
+    async fn cached_neighbors(&mut self, keys: &[K]) -> BTreeMap<K, V> {
+        let mut cached_neighbors = BTreeMap::new();
+        if let Some(neighbors) = &self.options.neighbors {
+            let neighbor_keys = keys.iter().flat_map(|key| neighbors(key)).collect::<Vec<_>>();
+            let values = self.cache.get(&neighbor_keys).await;
+            for (k, v) in neighbor_keys.into_iter().zip(values) {
+                if let Some(v) = v {
+                    cached_neighbors.insert(k, v);
+                }
+            }
+        }
+        cached_neighbors
+    }
+
+    /// Invokes the `BatchFunction` with `keys` outside of the normal frame flow. Nothing is read
+    /// from or written to the cache, and the observer is not notified.
+    async fn probe(&mut self, keys: Vec<K>) -> Result<Vec<Option<V>>, BatchError> {
+        self.frames += 1;
+        let frame = FrameId::new(self.frames);
+        let cancellation = CancellationToken::new();
+        let outcome = Arc::new(Mutex::new(BatchOutcome::default()));
+        let info =
+            BatchInfo::new(frame, BTreeMap::new(), cancellation.clone(), outcome.clone(), None);
+        // Like a batch, a panicking probe fails rather than the worker.
+        let load = AssertUnwindSafe(self.batch_fn.load_with_info(&keys, &self.context, info))
+            .catch_unwind()
+            .map(|loaded| loaded.map_err(|_| BatchError::Panic));
+        let loaded = match self.options.batch_timeout {
+            Some(timeout) => {
+                tokio::time::timeout(timeout, load).await.unwrap_or(Err(BatchError::Timeout))
+            }
+            None => load.await,
+        };
+        let loaded = match loaded {
+            Ok(loaded) => loaded.into_iter().collect::<BTreeMap<_, _>>(),
+            Err(error) => {
+                cancellation.cancel();
+                return Err(error);
+            }
+        };
+        let outcome = std::mem::take(&mut *outcome.lock().unwrap());
+        if let Some(error) = outcome.error {
+            return Err(BatchError::Backend(error));
+        }
+        Ok(keys
+            .iter()
+            .map(|key| loaded.get(key).filter(|_| !outcome.filtered.contains(key)).cloned())
+            .collect())
+    }
+
+    /// Executes a separate, earlier frame for the staged requests whose deadlines fall within
+    /// `threshold`, leaving the remaining requests and their keys staged.
+    ///
+    /// Does nothing unless the staged requests contain both urgent and non-urgent requests.
+    async fn execute_urgent_load(&mut self, threshold: Duration) {
+        let cutoff = Instant::now() + threshold;
+        let (urgent, rest): (Vec<_>, Vec<_>) = self
+            .pending_request
+            .drain(..)
+            .partition(|request| request.deadline().is_some_and(|deadline| deadline <= cutoff));
+        if urgent.is_empty() || rest.is_empty() {
+            self.pending_request = if urgent.is_empty() { rest } else { urgent };
+            return;
+        }
+        tracing::trace!(urgent_requests = urgent.len(), "dispatching urgent frame");
+
+        let staged_keys = std::mem::take(&mut self.keys_to_load);
+        let mut urgent_keys = urgent
+            .iter()
+            .flat_map(|request| request.keys().iter().cloned())
+            .filter(|key| staged_keys.contains(key))
+            .collect::<Vec<_>>();
+        urgent_keys.sort();
+        urgent_keys.dedup();
+
+        self.pending_request = urgent;
+        self.keys_to_load = urgent_keys.clone();
+        self.execute_load().await;
+
+        // Requests staged while the urgent frame was executing follow the remaining requests.
+        let staged_during = std::mem::replace(&mut self.pending_request, rest);
+        self.pending_request.extend(staged_during);
+        let keys_staged_during = std::mem::replace(
+            &mut self.keys_to_load,
+            staged_keys.into_iter().filter(|key| urgent_keys.binary_search(key).is_err()).collect(),
         );
+        self.keys_to_load.extend(keys_staged_during);
+        self.recount_staged_cost();
+        self.health.set_staged(self.pending_request.len(), self.keys_to_load.len());
+    }
 
-        self.cache.insert_many(loaded_keyvals);
+    /// While the loader is cold, loads the staged keys beyond the configured batch size in
+    /// separate, spaced batches without requests, ahead of the frame's own batch. The frame's
+    /// requests are then resolved from the cache.
+    async fn smooth_cold_start(&mut self) {
+        let (frames, max_batch_size, delay) = match self.options.cold_start {
+            Some(cold_start) if !self.caching_disabled => cold_start,
+            _ => return,
+        };
+        self.options.dedup_strategy.apply(&mut self.keys_to_load);
+        self.recount_staged_cost();
+        while self.frames < frames && self.keys_to_load.len() > max_batch_size {
+            let rest = self.keys_to_load.split_off(max_batch_size);
+            let pending = std::mem::take(&mut self.pending_request);
+            tracing::debug!(keys = self.keys_to_load.len(), "dispatching cold start batch");
+            self.execute_load().await;
 
-        for request in self.pending_request.drain(..) {
-            let values = self.cache.get(request.keys());
-            request.send_response(values);
+            // Requests staged while the batch was executing follow the frame's requests.
+            let staged_during = std::mem::replace(&mut self.pending_request, pending);
+            self.pending_request.extend(staged_during);
+            let keys_staged_during = std::mem::replace(&mut self.keys_to_load, rest);
+            self.keys_to_load.extend(keys_staged_during);
+            self.recount_staged_cost();
+            self.collect_until(Instant::now() + delay, None).await;
+            self.options.dedup_strategy.apply(&mut self.keys_to_load);
+            self.recount_staged_cost();
         }
     }
+
+    /// Dispatches the staged requests as a batch, then waits for executing batches to complete
+    /// until fewer than the configured maximum remain.
+    async fn execute_load(&mut self) {
+        self.dispatch_load().await;
+        while self.running.len() >= self.options.max_concurrent_batches {
+            self.await_batch().await;
+        }
+    }
+
+    /// Invokes the `BatchFunction` with the staged keys on a new executing batch.
+    #[tracing::instrument(skip(self))]
+    async fn dispatch_load(&mut self) {
+        let mut keys_to_load = std::mem::take(&mut self.keys_to_load);
+        self.staged_cost = 0;
+        let pending = std::mem::take(&mut self.pending_request);
+        #[cfg(feature = "stats")]
+        self.stats.record_load_exec(keys_to_load.len() as u32);
+
+        #[cfg(feature = "fault-injection")]
+        if self.options.faults.as_ref().is_some_and(|faults| faults.should_drop_frame()) {
+            tracing::warn!(keys = ?keys_to_load, "fault injected: dropping frame");
+            self.health.set_staged(0, 0);
+            return;
+        }
+
+        self.options.dedup_strategy.apply(&mut keys_to_load);
+        // Keys already being fetched by another loader sharing the in-flight store are awaited
+        // rather than passed to the batch function.
+        let (claim, awaited) = match self.options.in_flight.clone() {
+            Some(store) => {
+                let awaited = store.claim(&keys_to_load);
+                keys_to_load.retain(|key| !awaited.iter().any(|(k, _)| k == key));
+                (Some(InFlightClaim::new(store, keys_to_load.clone())), awaited)
+            }
+            None => (None, Vec::new()),
+        };
+        let (awaited_keys, awaited): (Vec<_>, Vec<_>) = awaited.into_iter().unzip();
+        let requested_keys =
+            keys_to_load.iter().chain(awaited_keys.iter()).cloned().collect::<BTreeSet<_>>();
+        self.frames += 1;
+        let frame = FrameId::new(self.frames);
+        if let Some(observer) = &self.options.observer {
+            observer.on_batch(&BatchDispatch {
+                loader: self.debug_name,
+                frame,
+                keys: keys_to_load.len(),
+            });
+        }
+        self.health.record_batch(keys_to_load.len());
+        #[cfg(feature = "metrics")]
+        self.metrics.record_batch(keys_to_load.len());
+        self.health.set_batch_in_flight(true);
+        let cancellation = CancellationToken::new();
+        let outcome = Arc::new(Mutex::new(BatchOutcome::default()));
+        let cached_neighbors = self.cached_neighbors(&keys_to_load).await;
+        // The isolation group permit and the resource lease are awaited on the batch's future, so
+        // that the worker keeps completing its other batches while they are unavailable.
+        let (isolation_group, resources) = if keys_to_load.is_empty() {
+            (None, None)
+        } else {
+            (self.options.isolation_group.clone(), self.options.resources.clone())
+        };
+        let batch_keys = keys_to_load.clone();
+        let batch_fn = self.batch_fn.clone();
+        let context = self.context.clone();
+        let batch_timeout = self.options.batch_timeout;
+        let (info_cancellation, info_outcome) = (cancellation.clone(), outcome.clone());
+        let load = async move {
+            let permit = match &isolation_group {
+                Some(group) => Some(group.acquire().await),
+                None => None,
+            };
+            let resource = match &resources {
+                Some(pool) => Some(pool.lease().await),
+                None => None,
+            };
+            let info =
+                BatchInfo::new(frame, cached_neighbors, info_cancellation, info_outcome, resource);
+            let (batch_fn, batch_keys, context) = (&batch_fn, &batch_keys, &context);
+            let load = async move {
+                let mut loaded_keyvals = if batch_keys.is_empty() {
+                    Vec::new()
+                } else {
+                    batch_fn.load_with_info(batch_keys, context, info).await.into_iter().collect()
+                };
+                // The claim is released before awaiting the keys claimed by other loaders, which
+                // may in turn be awaiting ours.
+                if let Some(claim) = claim {
+                    claim.release(&loaded_keyvals);
+                }
+                let awaited = future::join_all(awaited).await;
+                loaded_keyvals.extend(
+                    awaited_keys.into_iter().zip(awaited).filter_map(|(k, v)| v.map(|v| (k, v))),
+                );
+                loaded_keyvals
+            };
+            // A panicking batch fails its own requests rather than the worker.
+            let load = AssertUnwindSafe(load)
+                .catch_unwind()
+                .map(|loaded_keyvals| loaded_keyvals.map_err(|_| BatchError::Panic));
+            let loaded_keyvals = match batch_timeout {
+                Some(timeout) => {
+                    tokio::time::timeout(timeout, load).await.unwrap_or(Err(BatchError::Timeout))
+                }
+                None => load.await,
+            };
+            drop(permit);
+            (frame, loaded_keyvals)
+        };
+        // Links the batch to the requesters' spans, so that traces show which requests it served.
+        let span = tracing::info_span!("batch", ?frame, keys = keys_to_load.len());
+        for request in &pending {
+            span.follows_from(request.span());
+        }
+        self.running.push(load.instrument(span.clone()).boxed());
+        self.executing.push(ExecutingBatch {
+            frame,
+            keys: requested_keys,
+            keys_to_load,
+            pending,
+            superseded: BTreeSet::new(),
+            cancel_on_drop: cancellation.cancel_on_drop(),
+            cancellation,
+            outcome,
+            span,
+            #[cfg(feature = "stats")]
+            dispatched_at: Instant::now(),
+        });
+    }
+
+    /// Waits for an executing batch to complete, processing ops from the request queue in the
+    /// meantime, and resolves its requests.
+    ///
+    /// Under the spill overflow policy, ops are left queued once the next frame is full.
+    async fn await_batch(&mut self) {
+        let (frame, loaded_keyvals) = loop {
+            if self.frame_full() && self.options.overflow_policy == OverflowPolicy::Spill {
+                break self.running.next().await.expect("a batch was executing");
+            }
+            match self.next_event().await {
+                Either::Left(result) => break result,
+                Either::Right(Some(op)) => self.mux_op(op).await,
+                Either::Right(None) => {
+                    break self.running.next().await.expect("a batch was executing")
+                }
+            }
+        };
+        self.complete_load(frame, loaded_keyvals).await;
+    }
+
+    /// Resolves the requests of the batch executed in `frame` with the values it loaded, or
+    /// fails them if it timed out or panicked.
+    async fn complete_load(
+        &mut self,
+        frame: FrameId,
+        loaded_keyvals: Result<Vec<(K, V)>, BatchError>,
+    ) {
+        let index = self
+            .executing
+            .iter()
+            .position(|batch| batch.frame == frame)
+            .expect("completed batch was executing");
+        let ExecutingBatch {
+            keys,
+            keys_to_load,
+            mut pending,
+            superseded: superseded_keys,
+            cancel_on_drop,
+            cancellation,
+            outcome,
+            #[cfg(feature = "stats")]
+            dispatched_at,
+            ..
+        } = self.executing.remove(index);
+        #[cfg(feature = "stats")]
+        self.stats.record_batch_duration(dispatched_at.elapsed());
+        cancel_on_drop.disarm();
+        self.health.set_batch_in_flight(!self.executing.is_empty());
+        self.options.resolution_order.apply(&mut pending);
+
+        let loaded_keyvals = match loaded_keyvals {
+            Ok(loaded_keyvals) => loaded_keyvals,
+            Err(error) => {
+                match error {
+                    BatchError::Panic => {
+                        tracing::error!(keys = ?keys_to_load, "batch function panicked")
+                    }
+                    _ => tracing::warn!(keys = ?keys_to_load, %error, "batch failed"),
+                }
+                cancellation.cancel();
+                self.report_batch_error(Some(frame), keys_to_load.len(), error.clone());
+                for mut request in pending {
+                    request.set_frame(frame);
+                    request.send_failure(error.clone().into());
+                }
+                self.health.set_staged(self.pending_request.len(), self.keys_to_load.len());
+                return;
+            }
+        };
+        trace_op!(self.traced, load_size = loaded_keyvals.len(), ?loaded_keyvals);
+
+        #[cfg(feature = "stats")]
+        self.stats
+            .record_load_exec_completed(keys_to_load.len() as u32, loaded_keyvals.len() as u32);
+
+        if let Some(sampler) = &mut self.sampler {
+            if sampler.should_sample() {
+                sampler.submit(BatchSample {
+                    frame,
+                    keys: keys_to_load.clone(),
+                    values: loaded_keyvals.clone(),
+                });
+            }
+        }
+
+        let loaded_keyvals = self.handle_unrequested(&keys, loaded_keyvals);
+        let loaded_keyvals = if self.options.transforms.is_empty() {
+            loaded_keyvals
+        } else {
+            loaded_keyvals
+                .into_iter()
+                .map(|(key, value)| {
+                    let value = self.transform(&key, value);
+                    (key, value)
+                })
+                .collect()
+        };
+
+        let outcome = std::mem::take(&mut *outcome.lock().unwrap());
+        if let Some(error) = &outcome.error {
+            tracing::warn!(%error, keys = ?keys_to_load, "batch function failed");
+            let error = BatchError::Backend(error.clone());
+            self.report_batch_error(Some(frame), keys_to_load.len(), error);
+        }
+        let loaded_keyvals = if outcome.filtered.is_empty() {
+            loaded_keyvals
+        } else {
+            trace_op!(self.traced, filtered = ?outcome.filtered, "batch function filtered keys");
+            loaded_keyvals.into_iter().filter(|(key, _)| !outcome.filtered.contains(key)).collect()
+        };
+
+        if self.caching_disabled {
+            for (key, value) in &loaded_keyvals {
+                self.notify(key, Some(value));
+            }
+            let loaded = loaded_keyvals.into_iter().collect::<BTreeMap<_, _>>();
+            for mut request in pending {
+                if self.drop_response() {
+                    continue;
+                }
+                request.set_frame(frame);
+                let values = self
+                    .cache
+                    .get(request.keys())
+                    .await
+                    .into_iter()
+                    .zip(request.keys())
+                    .map(|(value, key)| value.or_else(|| loaded.get(key).cloned()))
+                    .collect::<Vec<_>>();
+                request.send_loaded(values, &outcome);
+            }
+        } else {
+            #[cfg(feature = "fault-injection")]
+            if let Some(delay) = self.options.faults.as_ref().and_then(|f| f.cache_insert_delay()) {
+                tracing::warn!(?delay, "fault injected: delaying cache insert");
+                tokio::time::sleep(delay).await;
+            }
+            if outcome.error.is_none() {
+                let loaded = loaded_keyvals.iter().map(|(key, _)| key).collect::<BTreeSet<_>>();
+                let missing = keys_to_load
+                    .iter()
+                    .filter(|key| {
+                        !loaded.contains(key)
+                            && !outcome.filtered.contains(*key)
+                            && !superseded_keys.contains(*key)
+                    })
+                    .cloned()
+                    .collect::<Vec<_>>();
+                self.negative.record(missing);
+            }
+            // Values of keys primed or cleared during the batch are handed to its requests, but
+            // not cached.
+            let (superseded, loaded_keyvals): (Vec<_>, Vec<_>) =
+                loaded_keyvals.into_iter().partition(|(key, _)| superseded_keys.contains(key));
+            let superseded = superseded.into_iter().collect::<BTreeMap<_, _>>();
+            let inserted = self.insert_loaded(loaded_keyvals).await;
+            for mut request in self.hand_over(pending) {
+                if self.drop_response() {
+                    continue;
+                }
+                request.set_frame(frame);
+                // Values this batch loaded are handed over directly rather than read back, as the
+                // cache may already have evicted them (or failed to store them). Only keys loaded
+                // by other batches are read from the cache.
+                let others = request
+                    .keys()
+                    .iter()
+                    .filter(|key| !inserted.contains_key(key))
+                    .cloned()
+                    .collect::<Vec<_>>();
+                let mut cached = if others.is_empty() {
+                    Vec::new().into_iter()
+                } else {
+                    self.cache.get(&others).await.into_iter()
+                };
+                let values = request
+                    .keys()
+                    .iter()
+                    .map(|key| match inserted.get(key) {
+                        Some(value) => Some(value.clone()),
+                        None => cached.next().flatten().or_else(|| superseded.get(key).cloned()),
+                    })
+                    .collect::<Vec<_>>();
+                request.send_loaded(values, &outcome);
+            }
+        }
+        self.health.set_staged(self.pending_request.len(), self.keys_to_load.len());
+    }
+}
+
+/// The frame of a batch, and its loaded values or the error it failed with.
+type BatchResult<K, V> = (FrameId, Result<Vec<(K, V)>, BatchError>);
+
+/// A batch whose `BatchFunction` is executing while the worker keeps processing ops.
+struct ExecutingBatch<K, V> {
+    frame: FrameId,
+    /// The keys passed to the `BatchFunction`, and those awaited from the in-flight store.
+    keys: BTreeSet<K>,
+    keys_to_load: Vec<K>,
+    /// The frame's load requests, followed by those received during execution whose missing keys
+    /// are all in `keys`.
+    pending: Vec<LoadRequest<K, V>>,
+    /// Keys primed or cleared during execution.
+    superseded: BTreeSet<K>,
+    cancel_on_drop: CancelOnDrop,
+    cancellation: CancellationToken,
+    outcome: Arc<Mutex<BatchOutcome<K>>>,
+    /// Instruments the batch's future.
+    span: Span,
+    #[cfg(feature = "stats")]
+    dispatched_at: Instant,
+}
+
+impl<K: Ord, V> ExecutingBatch<K, V> {
+    fn covers(&self, keys: &[K]) -> bool {
+        keys.iter().all(|key| self.keys.contains(key))
+    }
+}
+
+/// A worker driven explicitly through a [`crate::LoaderPump`] rather than on a spawned task.
+pub(crate) trait PumpedWorker: Send {
+    /// Waits for the next op and runs a frame, returning false once the request queue is closed.
+    fn next_frame(&mut self) -> BoxFuture<'_, bool>;
+
+    /// Runs a frame if an op is queued, returning false otherwise.
+    fn try_next_frame(&mut self) -> BoxFuture<'_, bool>;
+}
+
+impl<K, V, F, CacheT, ContextT> Drop for LoaderWorker<K, V, F, CacheT, ContextT>
+where
+    K: 'static + Eq + Debug + Clone + Ord + Send + Sync,
+    V: 'static + Send + Debug + Clone,
+    F: 'static + BatchFunction<K, V, Context = ContextT> + Send,
+    CacheT: AsyncCache,
+    ContextT: Send + Sync + 'static,
+{
+    /// Cancels the load requests that are still queued, staged or executing when the worker stops
+    /// (e.g. because its task was aborted), rather than leaving their callers with a dropped
+    /// response channel.
+    fn drop(&mut self) {
+        let mut cancelled = std::mem::take(&mut self.pending_request);
+        cancelled.extend(self.executing.iter_mut().flat_map(|batch| batch.pending.drain(..)));
+        while let Ok(op) = self.request_rx.try_recv() {
+            if let LoaderOp::Load(request) = op {
+                cancelled.push(request);
+            }
+        }
+        if !cancelled.is_empty() {
+            tracing::debug!(requests = cancelled.len(), "cancelling pending requests");
+        }
+        for request in cancelled {
+            request.send_failure(BatchError::Cancelled.into());
+        }
+    }
+}
+
+impl<K, V, F, CacheT, ContextT> PumpedWorker for LoaderWorker<K, V, F, CacheT, ContextT>
+where
+    K: 'static + Eq + Debug + Clone + Ord + Send + Sync,
+    V: 'static + Send + Debug + Clone,
+    F: 'static + BatchFunction<K, V, Context = ContextT> + Send,
+    CacheT: AsyncCache<K = K, V = V> + Send,
+    ContextT: Send + Sync + 'static,
+{
+    fn next_frame(&mut self) -> BoxFuture<'_, bool> {
+        Box::pin(async move {
+            if self.idle() {
+                match self.next_op().await {
+                    Some(op) => self.mux_op(op).await,
+                    None => {
+                        self.finish().await;
+                        return false;
+                    }
+                }
+            }
+            self.run_frame().await;
+            true
+        })
+    }
+
+    fn try_next_frame(&mut self) -> BoxFuture<'_, bool> {
+        Box::pin(async move {
+            if self.idle() {
+                match self.request_rx.recv().now_or_never() {
+                    Some(Some(op)) => self.mux_op(op).await,
+                    _ if !self.running.is_empty() => {
+                        self.await_batch().await;
+                        return true;
+                    }
+                    _ => return false,
+                }
+            }
+            self.run_frame().await;
+            true
+        })
+    }
 }
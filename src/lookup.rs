@@ -0,0 +1,48 @@
+/// The result of loading a key of a loader whose values are optional, i.e. a
+/// `Loader<K, Option<T>>` whose `BatchFunction` returns `(key, None)` for keys that legitimately
+/// map to nothing (e.g. a user without an avatar).
+///
+/// Unlike a flattened `Option<T>`, a `Lookup` keeps "this key maps to nothing", which is cached
+/// like any other value, apart from "this key could not be loaded", which is loaded again the next
+/// time it is requested.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Lookup<T> {
+    /// The key maps to a value.
+    Found(T),
+    /// The key is known to map to nothing.
+    Absent,
+    /// The key could not be loaded, e.g. because the `BatchFunction` did not return it.
+    Missing,
+}
+
+impl<T> Lookup<T> {
+    /// Returns the value the key maps to, if any.
+    pub fn found(self) -> Option<T> {
+        match self {
+            Lookup::Found(value) => Some(value),
+            Lookup::Absent | Lookup::Missing => None,
+        }
+    }
+
+    pub fn is_found(&self) -> bool {
+        matches!(self, Lookup::Found(_))
+    }
+
+    pub fn is_absent(&self) -> bool {
+        matches!(self, Lookup::Absent)
+    }
+
+    pub fn is_missing(&self) -> bool {
+        matches!(self, Lookup::Missing)
+    }
+}
+
+impl<T> From<Option<Option<T>>> for Lookup<T> {
+    fn from(loaded: Option<Option<T>>) -> Self {
+        match loaded {
+            Some(Some(value)) => Lookup::Found(value),
+            Some(None) => Lookup::Absent,
+            None => Lookup::Missing,
+        }
+    }
+}
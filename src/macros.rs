@@ -0,0 +1,16 @@
+/// Loads keys from several loaders concurrently and awaits all of the results together.
+///
+/// Each argument is a `(loader, key)` pair, and the macro evaluates to a tuple of the
+/// corresponding `Option<V>` results in argument order. All loads are enqueued on their loaders
+/// when the combined future is first polled, so they are staged in the loaders' current execution
+/// frames rather than being serialized across frames.
+///
+/// ```ignore
+/// let (user, org) = load_join!((user_loader, user_id), (org_loader, org_id)).await;
+/// ```
+#[macro_export]
+macro_rules! load_join {
+    ($(($loader:expr, $key:expr)),+ $(,)?) => {
+        async { $crate::__private::futures::join!($($loader.load($key)),+) }
+    };
+}
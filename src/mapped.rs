@@ -0,0 +1,70 @@
+use std::fmt::Debug;
+use std::sync::Arc;
+
+use crate::{health::LoaderHealth, loader::Loader};
+
+/// A view of a [`Loader`] that transforms values on their way out, e.g. projecting a `User` into a
+/// `UserSummary`.
+///
+/// Created with [`Loader::map_values`]. The view shares the loader's worker and cache, so
+/// projections of the same values don't need caches of their own. The mapping function runs in
+/// the calling task on every load, and is never applied to cached values.
+pub struct MappedLoader<K, V, T>
+where
+    K: 'static + Eq + Debug + Send,
+    V: 'static + Send + Debug + Clone,
+{
+    loader: Loader<K, V>,
+    map: Arc<dyn Fn(V) -> T + Send + Sync>,
+}
+
+impl<K, V, T> Clone for MappedLoader<K, V, T>
+where
+    K: 'static + Eq + Debug + Send,
+    V: 'static + Send + Debug + Clone,
+{
+    fn clone(&self) -> Self {
+        Self { loader: self.loader.clone(), map: self.map.clone() }
+    }
+}
+
+impl<K, V, T> MappedLoader<K, V, T>
+where
+    K: 'static + Eq + Debug + Ord + Clone + Send + Sync,
+    V: 'static + Send + Debug + Clone,
+{
+    pub(crate) fn new(loader: Loader<K, V>, map: Arc<dyn Fn(V) -> T + Send + Sync>) -> Self {
+        Self { loader, map }
+    }
+
+    /// Returns the underlying loader.
+    pub fn inner(&self) -> &Loader<K, V> {
+        &self.loader
+    }
+
+    /// Loads a value and maps it. See [`Loader::load`].
+    pub async fn load(&self, key: K) -> Option<T> {
+        self.loader.load(key).await.map(&*self.map)
+    }
+
+    /// Loads many values and maps them. See [`Loader::load_many`].
+    pub async fn load_many(&self, keys: Vec<K>) -> Vec<Option<T>> {
+        let values = self.loader.load_many(keys).await;
+        values.into_iter().map(|value| value.map(&*self.map)).collect()
+    }
+
+    /// Returns a view that applies `map` to the values of this view.
+    pub fn map_values<U, F>(&self, map: F) -> MappedLoader<K, V, U>
+    where
+        T: 'static,
+        F: 'static + Fn(T) -> U + Send + Sync,
+    {
+        let inner = self.map.clone();
+        MappedLoader::new(self.loader.clone(), Arc::new(move |value| map(inner(value))))
+    }
+
+    /// See [`Loader::health`].
+    pub fn health(&self) -> LoaderHealth {
+        self.loader.health()
+    }
+}
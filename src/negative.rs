@@ -0,0 +1,75 @@
+use std::collections::BTreeMap;
+use std::time::{Duration, Instant};
+
+/// Keys that the `BatchFunction` recently did not return, answered as `None` without loading
+/// them again until their entry expires.
+pub(crate) struct NegativeCache<K> {
+    ttl: Option<Duration>,
+    /// Each remembered key and the instant its entry expires, or `None` if the TTL is too long
+    /// for an expiry instant to be represented, in which case the entry never expires.
+    misses: BTreeMap<K, Option<Instant>>,
+    purged_at: Instant,
+}
+
+impl<K: Ord + Clone> NegativeCache<K> {
+    pub fn new(ttl: Option<Duration>) -> Self {
+        Self { ttl, misses: BTreeMap::new(), purged_at: Instant::now() }
+    }
+
+    /// Changes the TTL of future entries. Disabling negative caching forgets every entry.
+    pub fn set_ttl(&mut self, ttl: Option<Duration>) {
+        if ttl.is_none() {
+            self.misses.clear();
+        }
+        self.ttl = ttl;
+    }
+
+    /// Returns true if `key` is remembered as missing.
+    pub fn contains(&mut self, key: &K) -> bool {
+        match self.misses.get(key) {
+            Some(expires_at) if expires_at.is_none_or(|expires_at| expires_at > Instant::now()) => {
+                true
+            }
+            Some(_) => {
+                self.misses.remove(key);
+                false
+            }
+            None => false,
+        }
+    }
+
+    /// Remembers `keys` as missing, if negative caching is enabled.
+    pub fn record<I: IntoIterator<Item = K>>(&mut self, keys: I) {
+        if let Some(ttl) = self.ttl {
+            let expires_at = Instant::now().checked_add(ttl);
+            self.misses.extend(keys.into_iter().map(|key| (key, expires_at)));
+        }
+    }
+
+    pub fn forget(&mut self, keys: &[K]) {
+        if self.misses.is_empty() {
+            return;
+        }
+        for key in keys {
+            self.misses.remove(key);
+        }
+    }
+
+    pub fn clear(&mut self) {
+        self.misses.clear();
+    }
+
+    /// Drops expired entries, at most once per TTL.
+    pub fn purge(&mut self) {
+        let ttl = match self.ttl {
+            Some(ttl) if !self.misses.is_empty() => ttl,
+            _ => return,
+        };
+        let now = Instant::now();
+        if now.duration_since(self.purged_at) < ttl {
+            return;
+        }
+        self.purged_at = now;
+        self.misses.retain(|_, expires_at| expires_at.is_none_or(|expires_at| expires_at > now));
+    }
+}
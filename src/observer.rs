@@ -0,0 +1,84 @@
+use std::fmt::Debug;
+
+use crate::{error::BatchError, receipt::FrameId};
+
+/// A limit that can be configured with a soft threshold through the [`crate::LoaderBuilder`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Limit {
+    /// Load requests staged in a single execution frame.
+    PendingRequests,
+    /// Keys staged in a single execution frame.
+    StagedKeys,
+    /// Entries held by the cache.
+    CacheEntries,
+}
+
+/// Reported to [`LoaderObserver::on_soft_limit`] when a soft threshold is reached.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SoftLimitWarning {
+    /// Name of the loader that reached the threshold.
+    pub loader: &'static str,
+    pub limit: Limit,
+    pub threshold: usize,
+    pub current: usize,
+}
+
+/// Reported to [`LoaderObserver::on_batch`] when the worker invokes its `BatchFunction`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BatchDispatch {
+    /// Name of the loader dispatching the batch.
+    pub loader: &'static str,
+    pub frame: FrameId,
+    /// Number of keys passed to the `BatchFunction`.
+    pub keys: usize,
+}
+
+/// Reported to [`LoaderObserver::on_batch_error`] when requests fail because of an error.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BatchFailed {
+    /// Name of the loader that failed.
+    pub loader: &'static str,
+    /// The frame whose batch failed, or `None` if the requests failed before being batched (e.g.
+    /// [`BatchError::Overloaded`]).
+    pub frame: Option<FrameId>,
+    /// Number of keys that failed.
+    pub keys: usize,
+    pub error: BatchError,
+}
+
+/// Reported to [`LoaderObserver::on_caching_disabled`] when a loader configured with
+/// [`crate::LoaderBuilder::adaptive_caching`] stops caching loaded values.
+#[derive(Debug, Clone, PartialEq)]
+pub struct CachingDisabled {
+    /// Name of the loader that stopped caching.
+    pub loader: &'static str,
+    /// The hit rate observed over the last window of requested keys.
+    pub hit_rate: f64,
+}
+
+/// Receives notifications about notable events in a loader's worker.
+///
+/// All methods have empty default implementations, so implementors only override the events they
+/// care about. Methods are invoked synchronously from the worker and should return quickly.
+pub trait LoaderObserver: Send + Sync + Debug {
+    /// Called when a soft threshold is reached. Warnings are edge triggered: a limit is reported
+    /// again only after its value has dropped back below the threshold.
+    fn on_soft_limit(&self, warning: &SoftLimitWarning) {
+        let _ = warning;
+    }
+
+    /// Called when the worker dispatches a batch, before the `BatchFunction` is invoked.
+    fn on_batch(&self, batch: &BatchDispatch) {
+        let _ = batch;
+    }
+
+    /// Called when a batch fails, or when a request is rejected before it is batched.
+    fn on_batch_error(&self, failed: &BatchFailed) {
+        let _ = failed;
+    }
+
+    /// Called once when the worker stops caching loaded values because of a low hit rate.
+    fn on_caching_disabled(&self, event: &CachingDisabled) {
+        let _ = event;
+    }
+}
@@ -0,0 +1,257 @@
+use std::collections::BTreeSet;
+use std::fmt;
+use std::sync::Arc;
+use std::time::Duration;
+
+use crate::loader_op::LoadRequest;
+
+/// Extracts a monotonically increasing version from a value.
+pub type VersionFn<V> = Arc<dyn Fn(&V) -> u64 + Send + Sync>;
+
+/// Transforms a value on its way into the cache.
+pub type TransformFn<K, V> = Arc<dyn Fn(&K, V) -> V + Send + Sync>;
+
+/// Estimates the cost of loading a key, in arbitrary units.
+pub type CostFn<K> = Arc<dyn Fn(&K) -> u64 + Send + Sync>;
+
+/// Returns the keys considered neighbors of a requested key.
+pub type NeighborsFn<K> = Arc<dyn Fn(&K) -> Vec<K> + Send + Sync>;
+
+/// Determines how the `LoaderWorker` resolves a prime for a key that is already present in the
+/// cache.
+///
+/// Primes that target keys absent from the cache are always inserted.
+#[derive(Default)]
+pub enum PrimePolicy<V> {
+    /// The primed value replaces the cached value. This is the default.
+    #[default]
+    Overwrite,
+    /// The cached value is kept and the primed value is discarded.
+    KeepOldest,
+    /// The value with the greater version (as computed by the provided extractor) is kept. Ties
+    /// favor the primed value, so out-of-order primes from eventually-consistent sources never
+    /// regress the cache to a stale value.
+    KeepNewestByVersion(VersionFn<V>),
+}
+
+impl<V> PrimePolicy<V> {
+    /// Convenience constructor for [`PrimePolicy::KeepNewestByVersion`].
+    pub fn keep_newest_by_version<F>(version_of: F) -> Self
+    where
+        F: Fn(&V) -> u64 + Send + Sync + 'static,
+    {
+        PrimePolicy::KeepNewestByVersion(Arc::new(version_of))
+    }
+
+    /// Returns true if `primed` should replace `cached`.
+    pub(crate) fn should_replace(&self, cached: &V, primed: &V) -> bool {
+        match self {
+            PrimePolicy::Overwrite => true,
+            PrimePolicy::KeepOldest => false,
+            PrimePolicy::KeepNewestByVersion(version_of) => {
+                version_of(primed) >= version_of(cached)
+            }
+        }
+    }
+}
+
+impl<V> Clone for PrimePolicy<V> {
+    fn clone(&self) -> Self {
+        match self {
+            PrimePolicy::Overwrite => PrimePolicy::Overwrite,
+            PrimePolicy::KeepOldest => PrimePolicy::KeepOldest,
+            PrimePolicy::KeepNewestByVersion(version_of) => {
+                PrimePolicy::KeepNewestByVersion(version_of.clone())
+            }
+        }
+    }
+}
+
+impl<V> fmt::Debug for PrimePolicy<V> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            PrimePolicy::Overwrite => f.write_str("Overwrite"),
+            PrimePolicy::KeepOldest => f.write_str("KeepOldest"),
+            PrimePolicy::KeepNewestByVersion(_) => f.write_str("KeepNewestByVersion(..)"),
+        }
+    }
+}
+
+/// Determines what happens to load requests that arrive once an execution frame has reached one of
+/// its configured caps (see [`crate::LoaderBuilder::max_pending_requests`],
+/// [`crate::LoaderBuilder::max_staged_keys`] and [`crate::LoaderBuilder::batch_cost_budget`]).
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum OverflowPolicy {
+    /// Stop draining the request queue and execute the current frame. Remaining requests are left
+    /// on the queue and staged in subsequent frames. This is the default.
+    #[default]
+    Spill,
+    /// Resolve overflowing load requests immediately with `None`.
+    Reject,
+}
+
+/// Determines how a [`crate::Loader`] behaves when its worker is no longer running (e.g. because
+/// it was shut down or aborted).
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ClosedPolicy {
+    /// Panic in the calling task. This is the default, and is useful for surfacing bugs in tests.
+    #[default]
+    Panic,
+    /// Log an error and resolve loads with `None`. Primes and clears are discarded.
+    ReturnNone,
+}
+
+/// Determines what happens to a spawned worker once every clone of its [`crate::Loader`] has been
+/// dropped.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum DropPolicy {
+    /// The worker task is aborted, even mid-batch. Requests that are still queued, staged or
+    /// executing resolve as cancelled (`None`, or [`crate::BatchError::Cancelled`] through the
+    /// detailed APIs). This is the default.
+    #[default]
+    Abort,
+    /// The worker keeps running until it has loaded every staged request and completed every
+    /// executing batch, so that detached callers (e.g. holding a [`crate::ProgressiveLoad`]) are
+    /// still answered, then exits.
+    ///
+    /// Batches are not retried with backoff while draining: a batch that fails or times out
+    /// resolves its requests with the error as usual. Registrations made through
+    /// [`crate::Loader::subscribe_invalidations`] and [`crate::Loader::add_dependent`] do not
+    /// keep the worker running.
+    Drain,
+}
+
+/// Determines what happens to pairs returned by the `BatchFunction` for keys it was not asked to
+/// load (e.g. related rows returned by the backend).
+///
+/// Unrequested pairs are counted in the worker stats regardless of the policy.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum UnrequestedKeysPolicy {
+    /// Unrequested pairs are cached like requested ones. This is the default.
+    #[default]
+    Accept,
+    /// Unrequested pairs are cached, and a warning is logged for each batch that returned any.
+    Warn,
+    /// Unrequested pairs are discarded.
+    Reject,
+}
+
+/// Determines the order and uniqueness of the keys passed to the `BatchFunction`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum DedupStrategy {
+    /// Keys are sorted and duplicates removed. This is the default.
+    #[default]
+    SortedUnique,
+    /// Duplicates are removed, and keys keep the order in which they were first requested.
+    PreserveFirstSeen,
+    /// Keys are passed exactly as requested, in request order and including duplicates.
+    None,
+}
+
+impl DedupStrategy {
+    pub(crate) fn apply<K: Ord + Clone>(&self, keys: &mut Vec<K>) {
+        match self {
+            DedupStrategy::SortedUnique => {
+                keys.sort();
+                keys.dedup();
+            }
+            DedupStrategy::PreserveFirstSeen => {
+                let mut seen = BTreeSet::new();
+                keys.retain(|key| seen.insert(key.clone()));
+            }
+            DedupStrategy::None => {}
+        }
+    }
+}
+
+/// Determines when the worker dispatches the requests staged for a frame to the `BatchFunction`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum DispatchStrategy {
+    /// The frame is dispatched as soon as the request queue has been drained. This is the
+    /// default.
+    #[default]
+    Immediate,
+    /// Once the request queue has been drained, the worker keeps collecting requests for up to
+    /// the given duration before dispatching the frame, trading latency for larger batches under
+    /// low concurrency. The frame is dispatched early once it reaches its caps under
+    /// [`OverflowPolicy::Spill`].
+    Delay(Duration),
+    /// Once the request queue has been drained, the worker keeps collecting requests until at
+    /// least the given number of keys (not necessarily unique) are staged, for up to the given
+    /// duration, before dispatching the frame. Suits backends where tiny batches are
+    /// disproportionately expensive (e.g. per-call auth handshakes), while bounding the added
+    /// latency. The frame is dispatched early once it reaches its caps under
+    /// [`OverflowPolicy::Spill`].
+    MinBatchSize(usize, Duration),
+    /// Once the request queue has been drained, the worker yields to the executor the given
+    /// number of times, draining the queue after each yield, before dispatching the frame. This
+    /// gives sibling GraphQL resolvers a few executor ticks to enqueue their keys, without adding
+    /// a fixed delay.
+    Yields(usize),
+    /// Requests accumulate until [`crate::Loader::dispatch`] is called, for frameworks that know
+    /// exactly when a wave of resolvers has finished enqueueing keys. Cache hits are still
+    /// answered immediately.
+    Manual,
+}
+
+/// Determines the order in which the requests served by a batch are resolved once it completes.
+///
+/// Responses are sent in this order within every frame, so that tasks relying on wake-up order
+/// for fairness behave the same regardless of scheduler details.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ResolutionOrder {
+    /// Requests are resolved in the order they were enqueued. This is the default.
+    #[default]
+    Fifo,
+    /// The most recently enqueued request is resolved first.
+    Lifo,
+    /// Requests are resolved by ascending deadline (see [`crate::Loader::load_with_deadline`]),
+    /// followed by requests without a deadline. Ties are resolved in the order they were enqueued.
+    EarliestDeadline,
+}
+
+impl ResolutionOrder {
+    /// Reorders `requests`, which are in enqueue order.
+    pub(crate) fn apply<K, V>(&self, requests: &mut [LoadRequest<K, V>])
+    where
+        K: Clone + std::fmt::Debug,
+        V: Send + Clone + std::fmt::Debug,
+    {
+        match self {
+            ResolutionOrder::Fifo => {}
+            ResolutionOrder::Lifo => requests.reverse(),
+            ResolutionOrder::EarliestDeadline => {
+                requests.sort_by_key(|request| (request.deadline().is_none(), request.deadline()))
+            }
+        }
+    }
+}
+
+/// Selects the execution frames for which the worker emits op-level tracing: a span for each op
+/// it processes and trace events about staging, priming and loading individual keys.
+///
+/// Frame-level instrumentation and warnings are always emitted.
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub enum TraceSampling {
+    /// Every frame is traced. This is the default.
+    #[default]
+    All,
+    /// An evenly spaced fraction of frames is traced, e.g. every tenth frame for `Rate(0.1)`.
+    Rate(f64),
+    /// The first of every `n` frames is traced.
+    EveryNth(u64),
+}
+
+impl TraceSampling {
+    /// Returns true if the `frame`th frame run by the worker, counting from one, is traced.
+    pub(crate) fn includes(&self, frame: u64) -> bool {
+        match *self {
+            TraceSampling::All => true,
+            TraceSampling::Rate(rate) => {
+                let rate = rate.clamp(0.0, 1.0);
+                (frame as f64 * rate).floor() > ((frame - 1) as f64 * rate).floor()
+            }
+            TraceSampling::EveryNth(n) => (frame - 1).is_multiple_of(n.max(1)),
+        }
+    }
+}
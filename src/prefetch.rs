@@ -0,0 +1,29 @@
+/// The largest window accepted by [`crate::LoaderBuilder::prefetch_window`], so that a single
+/// missed key cannot stage an unbounded number of keys.
+pub(crate) const MAX_PREFETCH_WINDOW: usize = 256;
+
+/// A key with a natural ordering in which adjacent keys are likely to be stored close together by
+/// the backend (e.g. clustered integer primary keys). See
+/// [`crate::LoaderBuilder::prefetch_window`].
+pub trait PrefetchKey: Sized {
+    /// Returns the keys within `window` steps of this key on either side, excluding the key itself
+    /// and any that would fall outside the key type's range.
+    fn window(&self, window: usize) -> Vec<Self>;
+}
+
+macro_rules! impl_prefetch_key {
+    ($($int:ty),*) => {
+        $(
+            impl PrefetchKey for $int {
+                fn window(&self, window: usize) -> Vec<Self> {
+                    let window = <$int>::try_from(window).unwrap_or(<$int>::MAX);
+                    let below = (1..=window).rev().filter_map(|step| self.checked_sub(step));
+                    let above = (1..=window).filter_map(|step| self.checked_add(step));
+                    below.chain(above).collect()
+                }
+            }
+        )*
+    };
+}
+
+impl_prefetch_key!(i8, i16, i32, i64, i128, isize, u8, u16, u32, u64, u128, usize);
@@ -0,0 +1,47 @@
+use tokio::sync::oneshot;
+
+/// The result of [`crate::Loader::load_progressive`]: the values that were already cached, plus a
+/// pending response for the keys that had to be loaded.
+#[derive(Debug)]
+pub struct ProgressiveLoad<V> {
+    cached: Vec<Option<V>>,
+    remaining_rx: oneshot::Receiver<Vec<Option<V>>>,
+}
+
+impl<V> ProgressiveLoad<V> {
+    pub(crate) fn new(
+        cached: Vec<Option<V>>,
+        remaining_rx: oneshot::Receiver<Vec<Option<V>>>,
+    ) -> Self {
+        Self { cached, remaining_rx }
+    }
+
+    /// Values that were cached when the request was processed, in the order of the requested keys.
+    /// Keys that were not cached are `None`.
+    pub fn cached(&self) -> &[Option<V>] {
+        &self.cached
+    }
+
+    /// Returns true if every requested key was served from the cache.
+    pub fn is_complete(&self) -> bool {
+        self.cached.iter().all(Option::is_some)
+    }
+
+    /// Waits for the remaining keys to be loaded and returns all values in the order of the
+    /// requested keys, exactly as [`crate::Loader::load_many`] would.
+    ///
+    /// Keys whose loads never complete (e.g. because the worker stopped) resolve to `None`.
+    pub async fn complete(self) -> Vec<Option<V>> {
+        if self.is_complete() {
+            return self.cached;
+        }
+        let mut loaded = self.remaining_rx.await.unwrap_or_default().into_iter();
+        self.cached
+            .into_iter()
+            .map(|cached| match cached {
+                Some(value) => Some(value),
+                None => loaded.next().flatten(),
+            })
+            .collect()
+    }
+}
@@ -0,0 +1,41 @@
+use std::marker::PhantomData;
+
+use crate::loader_worker::PumpedWorker;
+
+/// Drives a loader's worker explicitly, on the calling task, instead of on a spawned task.
+///
+/// Created with [`crate::LoaderBuilder::build_with_pump`]. Nothing is processed until the pump is
+/// driven, so frame boundaries are determined entirely by the caller. This makes benchmarks of the
+/// batching and caching overhead reproducible on a `current_thread` runtime, free of scheduling
+/// noise. Loads must be polled concurrently with the pump (e.g. with `futures::join!`), since a
+/// load only enqueues its request once it is first polled.
+///
+/// Dropping the pump stops the worker, cancelling the requests it has not resolved yet, after
+/// which the loader behaves according to its [`crate::ClosedPolicy`].
+pub struct LoaderPump<K, V> {
+    worker: Box<dyn PumpedWorker>,
+    phantom: PhantomData<fn() -> (K, V)>,
+}
+
+impl<K, V> LoaderPump<K, V> {
+    pub(crate) fn new(worker: Box<dyn PumpedWorker>) -> Self {
+        Self { worker, phantom: PhantomData }
+    }
+
+    /// Waits for the next op and runs a single execution frame.
+    ///
+    /// Returns false, without running a frame, once every handle to the loader has been dropped.
+    pub async fn run_frame(&mut self) -> bool {
+        self.worker.next_frame().await
+    }
+
+    /// Runs execution frames until the request queue is empty, returning the number of frames
+    /// run.
+    pub async fn run_until_idle(&mut self) -> usize {
+        let mut frames = 0;
+        while self.worker.try_next_frame().await {
+            frames += 1;
+        }
+        frames
+    }
+}
@@ -0,0 +1,45 @@
+use std::fmt;
+
+/// Identifies an execution frame in which a loader's worker invoked its `BatchFunction`.
+///
+/// IDs increase monotonically, starting at 1, over the lifetime of a worker. Each worker numbers
+/// its frames independently, so IDs are only comparable between loads issued through the same
+/// loader (or its clones).
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct FrameId(u64);
+
+impl FrameId {
+    pub(crate) fn new(id: u64) -> Self {
+        Self(id)
+    }
+
+    pub fn get(self) -> u64 {
+        self.0
+    }
+}
+
+impl fmt::Display for FrameId {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "frame {}", self.0)
+    }
+}
+
+/// Describes how a load request was served. Returned by [`crate::Loader::load_with_receipt`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct LoadReceipt {
+    frame: Option<FrameId>,
+}
+
+impl LoadReceipt {
+    pub(crate) fn new(frame: Option<FrameId>) -> Self {
+        Self { frame }
+    }
+
+    /// The frame whose batch served the request, or `None` if the request was resolved without
+    /// invoking the `BatchFunction` (e.g. entirely from the cache).
+    ///
+    /// Loads that report the same frame were coalesced into a single batch.
+    pub fn frame(&self) -> Option<FrameId> {
+        self.frame
+    }
+}
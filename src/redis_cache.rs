@@ -0,0 +1,203 @@
+//! An [`AsyncCache`] stored in Redis, so that loaders in several processes can share one cache.
+use std::fmt::{self, Debug};
+use std::marker::PhantomData;
+use std::time::Duration;
+
+use async_trait::async_trait;
+use futures::StreamExt;
+use redis::aio::MultiplexedConnection;
+use redis::AsyncCommands;
+use serde::{de::DeserializeOwned, Serialize};
+
+use crate::{async_cache::AsyncCache, cache::CachePolicy};
+
+/// A cache whose entries are stored in Redis as JSON, under keys made of a prefix followed by the
+/// JSON encoding of the loader key.
+///
+/// Entries that fail to (de)serialize, and Redis errors, are logged and treated as cache misses.
+/// Requests are answered with the values their batch loaded rather than by reading them back, so
+/// a Redis outage only costs cache hits.
+/// The number of entries is not tracked, so [`AsyncCache::len`] always returns 0.
+pub struct RedisCache<K, V> {
+    connection: MultiplexedConnection,
+    prefix: String,
+    ttl: Option<Duration>,
+    phantom: PhantomData<fn() -> (K, V)>,
+}
+
+impl<K, V> RedisCache<K, V> {
+    /// Creates a cache that stores its entries through `connection`, under keys starting with
+    /// `prefix`. Loaders sharing a Redis instance should use distinct prefixes unless they are
+    /// meant to share entries.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `prefix` is empty, as flushing the cache would then remove every key in the
+    /// database.
+    pub fn new(connection: MultiplexedConnection, prefix: impl Into<String>) -> Self {
+        let prefix = prefix.into();
+        assert!(!prefix.is_empty(), "a RedisCache requires a non-empty key prefix");
+        Self { connection, prefix, ttl: None, phantom: PhantomData }
+    }
+
+    /// Expires entries `ttl` after they were inserted. Without a TTL, entries are kept until they
+    /// are cleared or evicted by Redis.
+    pub fn with_ttl(mut self, ttl: Duration) -> Self {
+        self.ttl = Some(ttl);
+        self
+    }
+
+    fn redis_key(&self, key: &K) -> Option<String>
+    where
+        K: Serialize,
+    {
+        match serde_json::to_string(key) {
+            Ok(encoded) => Some(format!("{}{}", self.prefix, encoded)),
+            Err(e) => {
+                tracing::warn!(%e, "failed to serialize cache key");
+                None
+            }
+        }
+    }
+
+    fn redis_keys(&self, keys: &[K]) -> Vec<String>
+    where
+        K: Serialize,
+    {
+        keys.iter().filter_map(|key| self.redis_key(key)).collect()
+    }
+}
+
+impl<K, V> Clone for RedisCache<K, V> {
+    fn clone(&self) -> Self {
+        Self {
+            connection: self.connection.clone(),
+            prefix: self.prefix.clone(),
+            ttl: self.ttl,
+            phantom: PhantomData,
+        }
+    }
+}
+
+impl<K, V> Debug for RedisCache<K, V> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("RedisCache").field("prefix", &self.prefix).field("ttl", &self.ttl).finish()
+    }
+}
+
+#[async_trait]
+impl<K, V> AsyncCache for RedisCache<K, V>
+where
+    K: Serialize + Send + Sync,
+    V: Serialize + DeserializeOwned + Send,
+{
+    type K = K;
+    type V = V;
+
+    async fn get(&mut self, keys: &[K]) -> Vec<Option<V>> {
+        let redis_keys = keys.iter().map(|key| self.redis_key(key)).collect::<Vec<_>>();
+        let present = redis_keys.iter().flatten().collect::<Vec<_>>();
+        if present.is_empty() {
+            return keys.iter().map(|_| None).collect();
+        }
+        let fetched: Vec<Option<String>> =
+            match redis::cmd("MGET").arg(&present).query_async(&mut self.connection).await {
+                Ok(values) => values,
+                Err(e) => {
+                    tracing::warn!(%e, "failed to read from redis");
+                    vec![None; present.len()]
+                }
+            };
+        let mut fetched = fetched.into_iter();
+        redis_keys
+            .iter()
+            .map(|redis_key| {
+                let encoded: Option<String> = redis_key.as_ref().and_then(|_| fetched.next())?;
+                match serde_json::from_str(&encoded?) {
+                    Ok(value) => Some(value),
+                    Err(e) => {
+                        tracing::warn!(%e, ?redis_key, "failed to deserialize cached value");
+                        None
+                    }
+                }
+            })
+            .collect()
+    }
+
+    async fn insert(&mut self, key: K, value: V) {
+        self.insert_many(vec![(key, value)]).await;
+    }
+
+    async fn insert_many(&mut self, key_vals: Vec<(K, V)>) {
+        let mut pipe = redis::pipe();
+        for (key, value) in key_vals.iter() {
+            let redis_key = match self.redis_key(key) {
+                Some(redis_key) => redis_key,
+                None => continue,
+            };
+            let encoded = match serde_json::to_string(value) {
+                Ok(encoded) => encoded,
+                Err(e) => {
+                    tracing::warn!(%e, ?redis_key, "failed to serialize cache value");
+                    continue;
+                }
+            };
+            let set = pipe.cmd("SET").arg(redis_key).arg(encoded);
+            if let Some(ttl) = self.ttl {
+                // Redis rejects a zero expiry, which sub-millisecond TTLs would round down to.
+                set.arg("PX").arg((ttl.as_millis() as u64).max(1));
+            }
+            set.ignore();
+        }
+        if let Err(e) = pipe.query_async::<_, ()>(&mut self.connection).await {
+            tracing::warn!(%e, "failed to write to redis");
+        }
+    }
+
+    async fn remove(&mut self, keys: &[K]) {
+        let redis_keys = self.redis_keys(keys);
+        if redis_keys.is_empty() {
+            return;
+        }
+        if let Err(e) = self.connection.del::<_, ()>(redis_keys).await {
+            tracing::warn!(%e, "failed to remove from redis");
+        }
+    }
+
+    fn len(&self) -> usize {
+        0
+    }
+
+    /// Removes every entry under the cache's prefix.
+    async fn flush(&mut self) {
+        // Glob metacharacters in the prefix are matched literally, so that a prefix does not
+        // match other loaders' keys.
+        let mut pattern = String::with_capacity(self.prefix.len() + 1);
+        for c in self.prefix.chars() {
+            if matches!(c, '*' | '?' | '[' | ']' | '\\') {
+                pattern.push('\\');
+            }
+            pattern.push(c);
+        }
+        pattern.push('*');
+        let redis_keys = match self.connection.scan_match::<_, String>(pattern).await {
+            Ok(iter) => iter.collect::<Vec<_>>().await,
+            Err(e) => {
+                tracing::warn!(%e, "failed to scan redis");
+                return;
+            }
+        };
+        if redis_keys.is_empty() {
+            return;
+        }
+        if let Err(e) = self.connection.del::<_, ()>(redis_keys).await {
+            tracing::warn!(%e, "failed to remove from redis");
+        }
+    }
+
+    async fn reconfigure(&mut self, policy: &CachePolicy) {
+        if let Some(ttl) = policy.ttl() {
+            self.ttl = Some(ttl);
+        }
+    }
+}
@@ -0,0 +1,136 @@
+//! An [`InvalidationSource`] fed by Redis pub/sub, so that clears propagate across the replicas of
+//! a service.
+use std::fmt::{self, Debug};
+use std::marker::PhantomData;
+use std::time::Duration;
+
+use futures::stream::{self, BoxStream, StreamExt};
+use redis::aio::MultiplexedConnection;
+use redis::{AsyncCommands, Client, Msg};
+use serde::{de::DeserializeOwned, Serialize};
+
+use crate::invalidation::InvalidationSource;
+
+/// An [`InvalidationSource`] that subscribes to a Redis pub/sub channel, whose messages are JSON
+/// arrays of the keys to clear (as sent by [`RedisInvalidationSource::publish`]).
+///
+/// Messages that fail to deserialize are logged and skipped. If the subscription cannot be
+/// established or the connection is lost, the source resubscribes with exponential backoff (up to
+/// 30 seconds between attempts). Keys published while it is not subscribed are missed.
+pub struct RedisInvalidationSource<K> {
+    client: Client,
+    channel: String,
+    phantom: PhantomData<fn() -> K>,
+}
+
+impl<K> RedisInvalidationSource<K> {
+    /// Creates a source that subscribes to `channel` through a dedicated connection opened from
+    /// `client` by each worker.
+    pub fn new(client: Client, channel: impl Into<String>) -> Self {
+        Self { client, channel: channel.into(), phantom: PhantomData }
+    }
+
+    /// Publishes `keys` on `channel`, so that the loaders subscribed to it clear them.
+    pub async fn publish(
+        connection: &mut MultiplexedConnection,
+        channel: &str,
+        keys: &[K],
+    ) -> redis::RedisResult<()>
+    where
+        K: Serialize,
+    {
+        let encoded = serde_json::to_string(keys).map_err(|e| {
+            redis::RedisError::from((
+                redis::ErrorKind::TypeError,
+                "failed to serialize keys",
+                e.to_string(),
+            ))
+        })?;
+        connection.publish(channel, encoded).await
+    }
+}
+
+impl<K> Debug for RedisInvalidationSource<K> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("RedisInvalidationSource").field("channel", &self.channel).finish()
+    }
+}
+
+impl<K> InvalidationSource<K> for RedisInvalidationSource<K>
+where
+    K: 'static + DeserializeOwned + Send,
+{
+    fn subscribe(&self) -> BoxStream<'static, Vec<K>> {
+        let (client, channel) = (self.client.clone(), self.channel.clone());
+        let subscription =
+            Subscription { client, channel, messages: None, backoff: Duration::ZERO };
+        stream::unfold(subscription, |mut subscription| async move {
+            let message = subscription.next().await;
+            Some((message, subscription))
+        })
+        .filter_map(|message| async move {
+            let payload = match message.get_payload::<String>() {
+                Ok(payload) => payload,
+                Err(e) => {
+                    tracing::warn!(%e, "failed to read invalidation message");
+                    return None;
+                }
+            };
+            serde_json::from_str(&payload)
+                .map_err(|e| tracing::warn!(%e, %payload, "failed to deserialize invalidated keys"))
+                .ok()
+        })
+        .boxed()
+    }
+}
+
+/// The delay before the first retry of a failed or lost subscription, doubled on each attempt.
+const INITIAL_BACKOFF: Duration = Duration::from_millis(100);
+const MAX_BACKOFF: Duration = Duration::from_secs(30);
+
+/// A pub/sub subscription that is re-established whenever it fails or its connection is lost.
+struct Subscription {
+    client: Client,
+    channel: String,
+    messages: Option<BoxStream<'static, Msg>>,
+    /// The delay before the next subscription attempt, reset once a message is received.
+    backoff: Duration,
+}
+
+impl Subscription {
+    async fn next(&mut self) -> Msg {
+        loop {
+            if let Some(messages) = &mut self.messages {
+                match messages.next().await {
+                    Some(message) => {
+                        self.backoff = Duration::ZERO;
+                        return message;
+                    }
+                    None => {
+                        tracing::warn!(channel = %self.channel, "redis subscription lost");
+                        self.messages = None;
+                    }
+                }
+            }
+            if !self.backoff.is_zero() {
+                tokio::time::sleep(self.backoff).await;
+            }
+            self.backoff = (self.backoff * 2).clamp(INITIAL_BACKOFF, MAX_BACKOFF);
+            match Self::subscribe(&self.client, &self.channel).await {
+                Ok(messages) => self.messages = Some(messages),
+                Err(e) => {
+                    tracing::warn!(%e, channel = %self.channel, "failed to subscribe to redis")
+                }
+            }
+        }
+    }
+
+    async fn subscribe(
+        client: &Client,
+        channel: &str,
+    ) -> redis::RedisResult<BoxStream<'static, Msg>> {
+        let mut pubsub = client.get_async_connection().await?.into_pubsub();
+        pubsub.subscribe(channel).await?;
+        Ok(pubsub.into_on_message().boxed())
+    }
+}
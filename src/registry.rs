@@ -0,0 +1,108 @@
+use std::any::{type_name, Any, TypeId};
+use std::collections::HashMap;
+use std::fmt::Debug;
+use std::hash::Hash;
+use std::sync::{Arc, RwLock};
+
+use crate::{
+    batch_function::BatchFunction,
+    builder::LoaderBuilder,
+    health::{HealthGauges, LoaderHealth},
+    loader::Loader,
+    shared_context::SharedContext,
+};
+
+/// A named collection of loaders, used to inspect the loaders serving an application (or a
+/// request) in one place.
+#[derive(Default)]
+pub struct Loaders {
+    entries: RwLock<Vec<(String, Arc<HealthGauges>)>>,
+    contexts: RwLock<HashMap<TypeId, Box<dyn Any + Send + Sync>>>,
+}
+
+impl Loaders {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Adds a loader to the registry under the provided name.
+    pub fn register<K, V>(&self, name: impl Into<String>, loader: &Loader<K, V>)
+    where
+        K: 'static + Eq + Debug + Send,
+        V: 'static + Send + Debug + Clone,
+    {
+        self.entries.write().unwrap().push((name.into(), loader.health_gauges()));
+    }
+
+    /// Stores a context to be injected into loaders created through [`Loaders::builder`] and
+    /// [`Loaders::loader`]. At most one context is kept per type; providing another replaces it.
+    pub fn provide_context<T>(&self, context: impl Into<SharedContext<T>>)
+    where
+        T: Send + Sync + 'static,
+    {
+        let context: SharedContext<T> = context.into();
+        self.contexts.write().unwrap().insert(TypeId::of::<T>(), Box::new(context));
+    }
+
+    /// Returns the context of type `T` previously given to [`Loaders::provide_context`].
+    pub fn context<T>(&self) -> Option<SharedContext<T>>
+    where
+        T: Send + Sync + 'static,
+    {
+        self.contexts
+            .read()
+            .unwrap()
+            .get(&TypeId::of::<T>())
+            .and_then(|context| context.downcast_ref::<SharedContext<T>>())
+            .cloned()
+    }
+
+    /// Returns a [`LoaderBuilder`] for a batch function whose context is a [`SharedContext`],
+    /// injecting the context provided to this registry.
+    ///
+    /// The built loader is not registered automatically; see [`Loaders::register`].
+    ///
+    /// # Panics
+    ///
+    /// Panics if no context of type `T` has been provided.
+    pub fn builder<K, V, F, T>(&self, batch_fn: F) -> LoaderBuilder<K, V, F, SharedContext<T>>
+    where
+        K: 'static + Eq + Debug + Ord + Clone + Hash + Send + Sync,
+        V: 'static + Send + Debug + Clone,
+        T: Send + Sync + 'static,
+        F: 'static + BatchFunction<K, V, Context = SharedContext<T>> + Send,
+    {
+        let context = self
+            .context::<T>()
+            .unwrap_or_else(|| panic!("no context of type {} was provided", type_name::<T>()));
+        Loader::builder(batch_fn, context)
+    }
+
+    /// Creates a loader with the default configuration using the context provided to this
+    /// registry, and registers it under the provided name.
+    ///
+    /// # Panics
+    ///
+    /// Panics if no context of type `T` has been provided.
+    pub fn loader<K, V, F, T>(&self, name: impl Into<String>, batch_fn: F) -> Loader<K, V>
+    where
+        K: 'static + Eq + Debug + Ord + Clone + Hash + Send + Sync,
+        V: 'static + Send + Debug + Clone,
+        T: Send + Sync + 'static,
+        F: 'static + BatchFunction<K, V, Context = SharedContext<T>> + Send,
+    {
+        let loader = self.builder(batch_fn).build();
+        self.register(name, &loader);
+        loader
+    }
+
+    /// Returns the health of every registered loader, in registration order.
+    pub fn health(&self) -> Vec<(String, LoaderHealth)> {
+        self.entries
+            .read()
+            .unwrap()
+            .iter()
+            .map(|(name, health)| (name.clone(), health.snapshot()))
+            .collect()
+    }
+}
@@ -0,0 +1,114 @@
+use std::any::Any;
+use std::ops::{Deref, DerefMut};
+use std::sync::{Arc, Mutex};
+
+use async_trait::async_trait;
+use futures::future::BoxFuture;
+
+/// Expensive per-batch setup (e.g. a checked-out session or a set of prepared statements) that a
+/// [`ResourcePool`] keeps alive across frames, so that the setup cost is amortized over many
+/// batches.
+#[async_trait]
+pub trait BatchResources: Send + Sync + 'static {
+    type Resource: Send + 'static;
+
+    /// Creates a resource. Called when a batch is dispatched and the pool has no idle resource.
+    async fn acquire(&self) -> Self::Resource;
+
+    /// Prepares a resource for reuse once its batch has completed. Returning false discards the
+    /// resource instead of returning it to the pool.
+    fn release(&self, resource: &mut Self::Resource) -> bool {
+        let _ = resource;
+        true
+    }
+}
+
+/// A pool of [`BatchResources`], leased to each batch of the loaders it is attached to with
+/// [`crate::LoaderBuilder::batch_resources`].
+///
+/// Cloning the pool is cheap, and clones share the same idle resources, so a pool can serve many
+/// loaders. The `BatchFunction` accesses its lease through [`crate::BatchInfo::resource`]; the
+/// resource returns to the pool once the `BatchInfo` is dropped.
+pub struct ResourcePool<R: BatchResources> {
+    inner: Arc<PoolInner<R>>,
+}
+
+struct PoolInner<R: BatchResources> {
+    resources: R,
+    idle: Mutex<Vec<R::Resource>>,
+    max_idle: usize,
+}
+
+impl<R: BatchResources> ResourcePool<R> {
+    /// Creates a pool that keeps at most `max_idle` idle resources.
+    pub fn new(resources: R, max_idle: usize) -> Self {
+        Self { inner: Arc::new(PoolInner { resources, idle: Mutex::new(Vec::new()), max_idle }) }
+    }
+
+    /// Number of resources currently idle in the pool.
+    pub fn idle(&self) -> usize {
+        self.inner.idle.lock().unwrap().len()
+    }
+
+    async fn lease(&self) -> Lease<R> {
+        let idle = self.inner.idle.lock().unwrap().pop();
+        let resource = match idle {
+            Some(resource) => resource,
+            None => self.inner.resources.acquire().await,
+        };
+        Lease { resource: Some(resource), pool: self.inner.clone() }
+    }
+}
+
+impl<R: BatchResources> Clone for ResourcePool<R> {
+    fn clone(&self) -> Self {
+        Self { inner: self.inner.clone() }
+    }
+}
+
+/// A resource leased from a [`ResourcePool`] for the duration of a batch.
+pub(crate) struct Lease<R: BatchResources> {
+    resource: Option<R::Resource>,
+    pool: Arc<PoolInner<R>>,
+}
+
+impl<R: BatchResources> Deref for Lease<R> {
+    type Target = R::Resource;
+
+    fn deref(&self) -> &R::Resource {
+        self.resource.as_ref().expect("resource is present until the lease is dropped")
+    }
+}
+
+impl<R: BatchResources> DerefMut for Lease<R> {
+    fn deref_mut(&mut self) -> &mut R::Resource {
+        self.resource.as_mut().expect("resource is present until the lease is dropped")
+    }
+}
+
+impl<R: BatchResources> Drop for Lease<R> {
+    fn drop(&mut self) {
+        let mut resource = match self.resource.take() {
+            Some(resource) => resource,
+            None => return,
+        };
+        if !self.pool.resources.release(&mut resource) {
+            return;
+        }
+        let mut idle = self.pool.idle.lock().unwrap();
+        if idle.len() < self.pool.max_idle {
+            idle.push(resource);
+        }
+    }
+}
+
+/// A [`ResourcePool`] with its resource type erased, as held by the worker.
+pub(crate) trait LeaseSource: Send + Sync {
+    fn lease(&self) -> BoxFuture<'_, Box<dyn Any + Send>>;
+}
+
+impl<R: BatchResources> LeaseSource for ResourcePool<R> {
+    fn lease(&self) -> BoxFuture<'_, Box<dyn Any + Send>> {
+        Box::pin(async move { Box::new(ResourcePool::lease(self).await) as Box<dyn Any + Send> })
+    }
+}
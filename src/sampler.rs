@@ -0,0 +1,60 @@
+use std::sync::Arc;
+
+use tokio::sync::mpsc;
+
+use crate::receipt::FrameId;
+
+/// Receives the batches sampled by [`crate::LoaderBuilder::sample_batches`].
+pub type SampleFn<K, V> = Arc<dyn Fn(BatchSample<K, V>) + Send + Sync>;
+
+/// Number of sampled batches that may await the callback before further samples are dropped.
+const SAMPLE_QUEUE_CAPACITY: usize = 16;
+
+/// The keys passed to a single `BatchFunction` invocation and the pairs it returned.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BatchSample<K, V> {
+    pub frame: FrameId,
+    pub keys: Vec<K>,
+    pub values: Vec<(K, V)>,
+}
+
+/// Selects a fraction of batches and hands them to the sampling callback on a separate task, so
+/// that the callback never delays the worker.
+pub(crate) struct Sampler<K, V> {
+    fraction: f64,
+    /// Accumulated fraction; a batch is sampled each time it reaches 1.
+    credit: f64,
+    sample_tx: mpsc::Sender<BatchSample<K, V>>,
+}
+
+impl<K, V> Sampler<K, V>
+where
+    K: 'static + Send,
+    V: 'static + Send,
+{
+    pub fn spawn(fraction: f64, callback: SampleFn<K, V>) -> Self {
+        let (sample_tx, mut sample_rx) = mpsc::channel(SAMPLE_QUEUE_CAPACITY);
+        tokio::task::spawn(async move {
+            while let Some(sample) = sample_rx.recv().await {
+                callback(sample);
+            }
+        });
+        Self { fraction: fraction.clamp(0.0, 1.0), credit: 0.0, sample_tx }
+    }
+
+    /// Returns true if the next batch should be sampled.
+    pub fn should_sample(&mut self) -> bool {
+        self.credit += self.fraction;
+        if self.credit < 1.0 {
+            return false;
+        }
+        self.credit -= 1.0;
+        true
+    }
+
+    pub fn submit(&self, sample: BatchSample<K, V>) {
+        if self.sample_tx.try_send(sample).is_err() {
+            tracing::debug!("sampling callback is behind, dropping batch sample");
+        }
+    }
+}
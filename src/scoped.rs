@@ -0,0 +1,145 @@
+use std::collections::hash_map::DefaultHasher;
+use std::fmt::Debug;
+use std::future::Future;
+use std::hash::{Hash, Hasher};
+
+use crate::loader::Loader;
+
+tokio::task_local! {
+    static AUTH_SCOPE: AuthScope;
+}
+
+/// Identifies the principal (e.g. a user ID, or a hash of a set of roles) on whose behalf values
+/// are loaded.
+///
+/// The scope of the current call is established with [`AuthScope::run`] and picked up
+/// automatically by [`ScopedLoader`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct AuthScope(u64);
+
+impl AuthScope {
+    pub fn new(id: u64) -> Self {
+        Self(id)
+    }
+
+    /// Derives a scope from an arbitrary principal by hashing it.
+    pub fn of<T: Hash + ?Sized>(principal: &T) -> Self {
+        let mut hasher = DefaultHasher::new();
+        principal.hash(&mut hasher);
+        Self(hasher.finish())
+    }
+
+    pub fn id(self) -> u64 {
+        self.0
+    }
+
+    /// Runs a future with this scope as the current scope.
+    pub async fn run<F: Future>(self, future: F) -> F::Output {
+        AUTH_SCOPE.scope(self, future).await
+    }
+
+    /// Returns the scope established by the innermost enclosing [`AuthScope::run`], if any.
+    pub fn current() -> Option<Self> {
+        AUTH_SCOPE.try_with(|scope| *scope).ok()
+    }
+}
+
+/// A key combined with the [`AuthScope`] it was requested under.
+///
+/// This is the key type of the loader wrapped by a [`ScopedLoader`]. Its `BatchFunction` receives
+/// the scope alongside each key, and can authorize (or group) the keys accordingly.
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Scoped<K> {
+    pub scope: AuthScope,
+    pub key: K,
+}
+
+/// A loader whose cache entries are partitioned by [`AuthScope`].
+///
+/// Every operation combines its keys with the current scope, so values loaded on behalf of one
+/// principal are never served to another, even if a single loader is shared across requests.
+/// Operations issued outside of any scope are refused: loads resolve to `None`, and primes and
+/// clears are discarded.
+///
+/// Created with [`crate::LoaderBuilder::build_scoped`] or [`ScopedLoader::new`].
+pub struct ScopedLoader<K, V>
+where
+    K: 'static + Eq + Debug + Send,
+    V: 'static + Send + Debug + Clone,
+{
+    loader: Loader<Scoped<K>, V>,
+}
+
+impl<K, V> Clone for ScopedLoader<K, V>
+where
+    K: 'static + Eq + Debug + Send,
+    V: 'static + Send + Debug + Clone,
+{
+    fn clone(&self) -> Self {
+        Self { loader: self.loader.clone() }
+    }
+}
+
+impl<K, V> ScopedLoader<K, V>
+where
+    K: 'static + Eq + Debug + Ord + Clone + Send + Sync,
+    V: 'static + Send + Debug + Clone,
+{
+    pub fn new(loader: Loader<Scoped<K>, V>) -> Self {
+        Self { loader }
+    }
+
+    /// Returns the underlying loader, whose keys carry their scope explicitly.
+    pub fn inner(&self) -> &Loader<Scoped<K>, V> {
+        &self.loader
+    }
+
+    /// Loads a value in the current scope. See [`Loader::load`].
+    pub async fn load(&self, key: K) -> Option<V> {
+        match current_scope() {
+            Some(scope) => self.loader.load(Scoped { scope, key }).await,
+            None => None,
+        }
+    }
+
+    /// Loads many values in the current scope. See [`Loader::load_many`].
+    pub async fn load_many(&self, keys: Vec<K>) -> Vec<Option<V>> {
+        match current_scope() {
+            Some(scope) => self.loader.load_many(scoped(scope, keys)).await,
+            None => vec![None; keys.len()],
+        }
+    }
+
+    /// Adds a value to the cache of the current scope. See [`Loader::prime`].
+    pub fn prime(&self, key: K, value: V) {
+        if let Some(scope) = current_scope() {
+            self.loader.prime(Scoped { scope, key }, value);
+        }
+    }
+
+    /// Removes a value from the cache of the current scope. See [`Loader::clear`].
+    pub fn clear(&self, key: K) {
+        if let Some(scope) = current_scope() {
+            self.loader.clear(Scoped { scope, key });
+        }
+    }
+
+    /// Removes many values from the cache of the current scope. See [`Loader::clear_many`].
+    pub fn clear_many(&self, keys: Vec<K>) {
+        if let Some(scope) = current_scope() {
+            self.loader.clear_many(scoped(scope, keys));
+        }
+    }
+}
+
+fn current_scope() -> Option<AuthScope> {
+    let scope = AuthScope::current();
+    if scope.is_none() {
+        tracing::error!("scoped loader used outside of an auth scope");
+    }
+    scope
+}
+
+fn scoped<K>(scope: AuthScope, keys: Vec<K>) -> Vec<Scoped<K>> {
+    keys.into_iter().map(|key| Scoped { scope, key }).collect()
+}
@@ -0,0 +1,48 @@
+use std::ops::Deref;
+use std::sync::Arc;
+
+/// A cheaply clonable handle to an application context shared by many loaders.
+///
+/// Batch functions that declare `type Context = SharedContext<AppContext>` can be created through
+/// a [`crate::Loaders`] registry that was given the context once with
+/// [`crate::Loaders::provide_context`], instead of cloning and passing the context to each loader
+/// constructor by hand.
+#[derive(Debug)]
+pub struct SharedContext<T>(Arc<T>);
+
+impl<T> SharedContext<T> {
+    pub fn new(context: T) -> Self {
+        Self(Arc::new(context))
+    }
+
+    /// Returns the underlying shared pointer.
+    pub fn into_arc(self) -> Arc<T> {
+        self.0
+    }
+}
+
+impl<T> Clone for SharedContext<T> {
+    fn clone(&self) -> Self {
+        Self(self.0.clone())
+    }
+}
+
+impl<T> From<Arc<T>> for SharedContext<T> {
+    fn from(context: Arc<T>) -> Self {
+        Self(context)
+    }
+}
+
+impl<T> Deref for SharedContext<T> {
+    type Target = T;
+
+    fn deref(&self) -> &T {
+        &self.0
+    }
+}
+
+impl<T> AsRef<T> for SharedContext<T> {
+    fn as_ref(&self) -> &T {
+        &self.0
+    }
+}
@@ -0,0 +1,39 @@
+/// The entries of a loader's cache, as returned by [`crate::Loader::export_cache`], so that a warm
+/// cache can be restored after a restart or shipped to another instance with
+/// [`crate::Loader::import_cache`] or [`crate::LoaderBuilder::restore`].
+///
+/// With the `serde` feature enabled, snapshots can be serialized.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct CacheSnapshot<K, V> {
+    pub entries: Vec<(K, V)>,
+}
+
+impl<K, V> CacheSnapshot<K, V> {
+    pub fn new(entries: Vec<(K, V)>) -> Self {
+        Self { entries }
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+}
+
+impl<K, V> From<Vec<(K, V)>> for CacheSnapshot<K, V> {
+    fn from(entries: Vec<(K, V)>) -> Self {
+        Self::new(entries)
+    }
+}
+
+impl<K, V> IntoIterator for CacheSnapshot<K, V> {
+    type Item = (K, V);
+    type IntoIter = std::vec::IntoIter<(K, V)>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.entries.into_iter()
+    }
+}
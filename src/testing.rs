@@ -0,0 +1,198 @@
+//! Utilities for testing code that uses loaders, e.g. to prove that resolvers do not reintroduce
+//! N+1 queries.
+//!
+//! Wrap a `BatchFunction` in a [`BatchCounter`] and its context in a [`Counted`], then assert on
+//! the [`BatchCounts`] shared by the context:
+//!
+//! ```ignore
+//! let context = Counted::new(context);
+//! let counts = context.counts();
+//! let loader = Loader::new(BatchCounter::new(UserLoader), context);
+//! resolve_query(&loader).await;
+//! counts.assert_batches(1);
+//! ```
+//!
+//! [`FakeTable`] and [`TableLoader`] stand in for a database table and the `BatchFunction` that
+//! queries it, for examples and tests that don't need a bespoke `BatchFunction`.
+use std::collections::HashMap;
+use std::hash::Hash;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
+
+use async_trait::async_trait;
+
+use crate::batch_function::{BatchFunction, BatchInfo};
+
+/// A `BatchFunction` that counts the invocations of the wrapped `BatchFunction` `F` and the keys
+/// passed to it, recording them in the [`BatchCounts`] of its [`Counted`] context.
+pub struct BatchCounter<F>(F);
+
+impl<F> BatchCounter<F> {
+    /// Wraps `batch_fn`.
+    pub fn new(batch_fn: F) -> Self {
+        Self(batch_fn)
+    }
+}
+
+/// The context of a [`BatchCounter`]: the wrapped `BatchFunction`'s context, plus its counts.
+pub struct Counted<C> {
+    context: C,
+    counts: BatchCounts,
+}
+
+impl<C> Counted<C> {
+    pub fn new(context: C) -> Self {
+        Self { context, counts: BatchCounts::default() }
+    }
+
+    /// Returns a handle to the counts, which remains valid after the context is moved into a
+    /// loader.
+    pub fn counts(&self) -> BatchCounts {
+        self.counts.clone()
+    }
+
+    /// Returns the wrapped context.
+    pub fn context(&self) -> &C {
+        &self.context
+    }
+}
+
+/// Invocation and key counts recorded by a [`BatchCounter`]. Cloning is cheap, and clones share
+/// the same counts.
+#[derive(Debug, Clone, Default)]
+pub struct BatchCounts(Arc<Counts>);
+
+#[derive(Debug, Default)]
+struct Counts {
+    batches: AtomicUsize,
+    keys: AtomicUsize,
+}
+
+impl BatchCounts {
+    /// Number of times the `BatchFunction` was invoked.
+    pub fn batches(&self) -> usize {
+        self.0.batches.load(Ordering::SeqCst)
+    }
+
+    /// Total number of keys passed to the `BatchFunction` across every invocation.
+    pub fn keys(&self) -> usize {
+        self.0.keys.load(Ordering::SeqCst)
+    }
+
+    /// Resets both counts to zero, e.g. between the setup and the body of a test.
+    pub fn reset(&self) {
+        self.0.batches.store(0, Ordering::SeqCst);
+        self.0.keys.store(0, Ordering::SeqCst);
+    }
+
+    /// Panics unless the `BatchFunction` was invoked exactly `expected` times.
+    #[track_caller]
+    pub fn assert_batches(&self, expected: usize) {
+        let actual = self.batches();
+        assert!(actual == expected, "expected {} batches, but {} were executed", expected, actual);
+    }
+
+    /// Panics unless exactly `expected` keys were passed to the `BatchFunction` in total.
+    #[track_caller]
+    pub fn assert_keys(&self, expected: usize) {
+        let actual = self.keys();
+        assert!(actual == expected, "expected {} keys to be loaded, but {} were", expected, actual);
+    }
+
+    fn record(&self, keys: usize) {
+        self.0.batches.fetch_add(1, Ordering::SeqCst);
+        self.0.keys.fetch_add(keys, Ordering::SeqCst);
+    }
+}
+
+#[async_trait]
+impl<K, V, F> BatchFunction<K, V> for BatchCounter<F>
+where
+    K: Send + Sync + 'static,
+    V: Send + 'static,
+    F: BatchFunction<K, V>,
+    F::Context: Send + Sync,
+{
+    type Context = Counted<F::Context>;
+    type Loaded = F::Loaded;
+
+    async fn load(&self, keys: &[K], context: &Self::Context) -> F::Loaded {
+        context.counts.record(keys.len());
+        self.0.load(keys, &context.context).await
+    }
+
+    async fn load_with_info(
+        &self,
+        keys: &[K],
+        context: &Self::Context,
+        info: BatchInfo<K, V>,
+    ) -> F::Loaded
+    where
+        K: Send + Sync + 'static,
+        V: Send + 'static,
+        Self::Context: Sync,
+    {
+        context.counts.record(keys.len());
+        self.0.load_with_info(keys, &context.context, info).await
+    }
+}
+
+/// An in-memory table of rows keyed by `K`, which records the keys of every query made against it.
+/// Cloning is cheap, and clones share the same rows and queries.
+#[derive(Debug)]
+pub struct FakeTable<K, V>(Arc<Table<K, V>>);
+
+#[derive(Debug)]
+struct Table<K, V> {
+    rows: HashMap<K, V>,
+    queries: Mutex<Vec<Vec<K>>>,
+}
+
+impl<K, V> Clone for FakeTable<K, V> {
+    fn clone(&self) -> Self {
+        Self(self.0.clone())
+    }
+}
+
+impl<K, V> FakeTable<K, V>
+where
+    K: Eq + Hash + Clone,
+    V: Clone,
+{
+    pub fn new<I>(rows: I) -> Self
+    where
+        I: IntoIterator<Item = (K, V)>,
+    {
+        Self(Arc::new(Table { rows: rows.into_iter().collect(), queries: Mutex::new(Vec::new()) }))
+    }
+
+    /// Returns the rows for the keys that exist, recording the keys as a single query.
+    pub fn select(&self, keys: &[K]) -> Vec<(K, V)> {
+        self.0.queries.lock().unwrap().push(keys.to_vec());
+        keys.iter()
+            .filter_map(|key| self.0.rows.get(key).map(|row| (key.clone(), row.clone())))
+            .collect()
+    }
+
+    /// Returns the keys of every query made so far, in order.
+    pub fn queries(&self) -> Vec<Vec<K>> {
+        self.0.queries.lock().unwrap().clone()
+    }
+}
+
+/// A `BatchFunction` that loads rows from its [`FakeTable`] context with a single query per batch.
+pub struct TableLoader;
+
+#[async_trait]
+impl<K, V> BatchFunction<K, V> for TableLoader
+where
+    K: Eq + Hash + Clone + Send + Sync + 'static,
+    V: Clone + Send + Sync + 'static,
+{
+    type Context = FakeTable<K, V>;
+    type Loaded = Vec<(K, V)>;
+
+    async fn load(&self, keys: &[K], table: &Self::Context) -> Vec<(K, V)> {
+        table.select(keys)
+    }
+}
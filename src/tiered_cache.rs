@@ -0,0 +1,133 @@
+use std::time::Duration;
+
+use async_trait::async_trait;
+
+use crate::{
+    async_cache::AsyncCache,
+    cache::{Cache, CachePolicy},
+};
+
+/// A two-tier cache that composes a fast in-process cache (L1) with a slower, typically shared,
+/// cache (L2), e.g. a `HashMap` in front of a [`crate::AsyncCache`] stored in Redis.
+///
+/// Reads check L1 first and fall back to L2 for the keys L1 is missing, copying L2 hits into L1.
+/// Writes populate both tiers, and clears invalidate both. [`AsyncCache::len`] reports the number
+/// of entries in L1.
+#[derive(Debug, Clone)]
+pub struct TieredCache<L1, L2> {
+    l1: L1,
+    l2: L2,
+}
+
+impl<L1, L2> TieredCache<L1, L2> {
+    pub fn new(l1: L1, l2: L2) -> Self {
+        Self { l1, l2 }
+    }
+
+    pub fn l1(&self) -> &L1 {
+        &self.l1
+    }
+
+    pub fn l2(&self) -> &L2 {
+        &self.l2
+    }
+}
+
+#[async_trait]
+impl<K, V, L1, L2> AsyncCache for TieredCache<L1, L2>
+where
+    K: 'static + Clone + Send + Sync,
+    V: 'static + Clone + Send,
+    L1: Cache<K = K, V = V> + Send,
+    L2: AsyncCache<K = K, V = V>,
+{
+    type K = K;
+    type V = V;
+
+    async fn get(&mut self, keys: &[K]) -> Vec<Option<V>> {
+        let mut values = self.l1.get(keys);
+        let missing = keys
+            .iter()
+            .zip(values.iter())
+            .filter_map(|(key, value)| if value.is_none() { Some(key.clone()) } else { None })
+            .collect::<Vec<_>>();
+        if missing.is_empty() {
+            return values;
+        }
+        let mut fetched = self.l2.get(&missing).await.into_iter();
+        let mut backfill = Vec::new();
+        for (key, value) in keys.iter().zip(values.iter_mut()) {
+            if value.is_some() {
+                continue;
+            }
+            *value = fetched.next().flatten();
+            if let Some(value) = value {
+                backfill.push((key.clone(), value.clone()));
+            }
+        }
+        self.l1.insert_many(backfill);
+        values
+    }
+
+    async fn get_stale(&mut self, keys: &[K], max_stale: Duration) -> Vec<Option<V>> {
+        let mut values = self.l1.get_stale(keys, max_stale);
+        let missing = keys
+            .iter()
+            .zip(values.iter())
+            .filter_map(|(key, value)| if value.is_none() { Some(key.clone()) } else { None })
+            .collect::<Vec<_>>();
+        if missing.is_empty() {
+            return values;
+        }
+        let mut fetched = self.l2.get_stale(&missing, max_stale).await.into_iter();
+        for value in values.iter_mut().filter(|value| value.is_none()) {
+            *value = fetched.next().flatten();
+        }
+        values
+    }
+
+    async fn insert(&mut self, key: K, value: V) {
+        self.l1.insert(key.clone(), value.clone());
+        self.l2.insert(key, value).await;
+    }
+
+    async fn insert_many(&mut self, key_vals: Vec<(K, V)>) {
+        self.l1.insert_many(key_vals.iter().cloned());
+        self.l2.insert_many(key_vals).await;
+    }
+
+    async fn remove(&mut self, keys: &[K]) {
+        self.l1.remove(keys);
+        self.l2.remove(keys).await;
+    }
+
+    fn len(&self) -> usize {
+        self.l1.len()
+    }
+
+    async fn flush(&mut self) {
+        self.l1.flush();
+        self.l2.flush().await;
+    }
+
+    async fn retain(&mut self, keep: &mut (dyn for<'k, 'v> FnMut(&'k K, &'v V) -> bool + Send)) {
+        self.l1.retain(keep);
+        self.l2.retain(keep).await;
+    }
+
+    async fn refresh_candidates(&mut self, within: Duration) -> Vec<K> {
+        let mut candidates = self.l1.refresh_candidates(within);
+        candidates.extend(self.l2.refresh_candidates(within).await);
+        candidates
+    }
+
+    async fn sweep(&mut self) {
+        self.l1.sweep();
+        self.l2.sweep().await;
+    }
+
+    async fn reconfigure(&mut self, policy: &CachePolicy) {
+        self.l1.reconfigure(policy);
+        self.l2.reconfigure(policy).await;
+    }
+}
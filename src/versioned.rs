@@ -0,0 +1,24 @@
+/// A value tagged with a monotonically increasing version (e.g. a row's update counter or
+/// modification timestamp).
+///
+/// Loaders of `Versioned` values can be configured with [`crate::LoaderBuilder::versioned`] so
+/// that neither primes nor late-completing loads ever replace a cached value with an older one.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Versioned<V> {
+    pub version: u64,
+    pub value: V,
+}
+
+impl<V> Versioned<V> {
+    pub fn new(version: u64, value: V) -> Self {
+        Self { version, value }
+    }
+
+    pub fn version(&self) -> u64 {
+        self.version
+    }
+
+    pub fn into_inner(self) -> V {
+        self.value
+    }
+}
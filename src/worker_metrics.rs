@@ -0,0 +1,41 @@
+//! Emits the worker's counters, gauges and histograms through the `metrics` facade, labelled with
+//! the loader's debug name.
+use metrics::{counter, gauge, histogram};
+
+use crate::error::BatchError;
+
+/// Records worker activity through whichever `metrics` recorder the application installed.
+#[derive(Debug)]
+pub struct WorkerMetrics {
+    loader: &'static str,
+}
+
+impl WorkerMetrics {
+    pub fn new(loader: &'static str) -> Self {
+        Self { loader }
+    }
+
+    pub fn record_load_request(&self, keys_requested: usize, cache_hits: usize) {
+        counter!("dataload_keys_requested_total", "loader" => self.loader)
+            .increment(keys_requested as u64);
+        counter!("dataload_cache_hits_total", "loader" => self.loader).increment(cache_hits as u64);
+    }
+
+    pub fn record_batch(&self, batch_size: usize) {
+        counter!("dataload_batches_total", "loader" => self.loader).increment(1);
+        histogram!("dataload_batch_size", "loader" => self.loader).record(batch_size as f64);
+    }
+
+    pub fn record_batch_error(&self, error: &BatchError) {
+        counter!(
+            "dataload_batch_errors_total",
+            "loader" => self.loader,
+            "category" => error.category()
+        )
+        .increment(1);
+    }
+
+    pub fn set_cache_entries(&self, cache_entries: usize) {
+        gauge!("dataload_cache_entries", "loader" => self.loader).set(cache_entries as f64);
+    }
+}
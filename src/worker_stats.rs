@@ -1,3 +1,27 @@
+use std::time::Duration;
+
+use crate::error::BatchError;
+
+/// Upper bounds of the queue wait histogram buckets. Waits at or above the last bound are counted
+/// in a final overflow bucket.
+const QUEUE_WAIT_BUCKETS: [Duration; 5] = [
+    Duration::from_micros(10),
+    Duration::from_micros(100),
+    Duration::from_millis(1),
+    Duration::from_millis(10),
+    Duration::from_millis(100),
+];
+
+/// Upper bounds of the batch duration histogram buckets. Durations at or above the last bound are
+/// counted in a final overflow bucket.
+const BATCH_DURATION_BUCKETS: [Duration; 5] = [
+    Duration::from_millis(1),
+    Duration::from_millis(10),
+    Duration::from_millis(100),
+    Duration::from_secs(1),
+    Duration::from_secs(10),
+];
+
 #[derive(Debug, Default)]
 pub struct WorkerStats {
     /// Human readable name used to identify this worker stats when it is reported.
@@ -8,6 +32,9 @@ pub struct WorkerStats {
     items_requested: u32,
     /// The number of keys that were immediately found in the loader cache.
     cache_hits: u32,
+    /// The number of keys that missed the cache but were already being loaded by an executing
+    /// batch, and were resolved with it rather than loaded again.
+    in_flight_hits: u32,
     /// Number of times that this worker executed the `LoaderWorker::execute_load` function.
     loads: u32,
     /// The average number of keys (not-unique) that were fetched during load operations.
@@ -23,22 +50,81 @@ pub struct WorkerStats {
     min_batch_unique: u32,
     /// The total number of unique items that were actually loaded.
     items_loaded: u32,
+    /// The number of items returned by the `BatchFunction` for keys it was not asked to load.
+    unrequested_items: u32,
+
+    /// The total time that `LoaderOp::Load` requests spent on the request queue before the worker
+    /// received them.
+    total_queue_wait: Duration,
+    /// The longest time a single `LoaderOp::Load` spent on the request queue.
+    max_queue_wait: Duration,
+    /// Number of `LoaderOp::Load` whose queue wait fell below each bound of `QUEUE_WAIT_BUCKETS`
+    /// (and at or above the previous one), with the final entry counting the remainder.
+    queue_wait_buckets: [u32; QUEUE_WAIT_BUCKETS.len() + 1],
+
+    /// Number of batches that completed, successfully or not.
+    batches_completed: u32,
+    /// The average wall-clock time between dispatching a batch and its completion.
+    average_batch_duration: Duration,
+    /// The longest time a single batch took to complete.
+    max_batch_duration: Duration,
+    /// The shortest time a single batch took to complete.
+    min_batch_duration: Duration,
+    /// Number of batches whose duration fell below each bound of `BATCH_DURATION_BUCKETS` (and at
+    /// or above the previous one), with the final entry counting the remainder.
+    batch_duration_buckets: [u32; BATCH_DURATION_BUCKETS.len() + 1],
+
+    /// Number of failures of each `BatchError` category.
+    timeouts: u32,
+    cancellations: u32,
+    backend_errors: u32,
+    panics: u32,
+    overloaded: u32,
 }
 
 impl WorkerStats {
     pub fn new(tag: &'static str) -> Self {
-        Self { tag, min_batch_size: u32::max_value(), ..Default::default() }
+        Self {
+            tag,
+            min_batch_size: u32::max_value(),
+            min_batch_duration: Duration::MAX,
+            ..Default::default()
+        }
     }
 
-    pub fn record_load_request(&mut self, items_requested: u32) {
+    pub fn record_load_request(&mut self, items_requested: u32, queue_wait: Duration) {
         self.load_requests += 1;
         self.items_requested += items_requested;
+
+        self.total_queue_wait += queue_wait;
+        if queue_wait > self.max_queue_wait {
+            self.max_queue_wait = queue_wait;
+        }
+        let bucket = QUEUE_WAIT_BUCKETS
+            .iter()
+            .position(|bound| queue_wait < *bound)
+            .unwrap_or(QUEUE_WAIT_BUCKETS.len());
+        self.queue_wait_buckets[bucket] += 1;
+    }
+
+    pub fn record_batch_error(&mut self, error: &BatchError) {
+        match error {
+            BatchError::Timeout => self.timeouts += 1,
+            BatchError::Cancelled => self.cancellations += 1,
+            BatchError::Backend(_) => self.backend_errors += 1,
+            BatchError::Panic => self.panics += 1,
+            BatchError::Overloaded => self.overloaded += 1,
+        }
     }
 
     pub fn record_cache_hits(&mut self, hits: u32) {
         self.cache_hits += hits;
     }
 
+    pub fn record_in_flight_hits(&mut self, hits: u32) {
+        self.in_flight_hits += hits;
+    }
+
     pub fn record_load_exec(&mut self, batch_size: u32) {
         let new_total_load = self.loads + 1;
         self.average_batch_size = (((self.average_batch_size as f64 * self.loads as f64)
@@ -53,6 +139,28 @@ impl WorkerStats {
         }
     }
 
+    pub fn record_batch_duration(&mut self, duration: Duration) {
+        let completed = self.batches_completed + 1;
+        self.average_batch_duration =
+            (self.average_batch_duration * self.batches_completed + duration) / completed;
+        self.batches_completed = completed;
+        if duration > self.max_batch_duration {
+            self.max_batch_duration = duration;
+        }
+        if duration < self.min_batch_duration {
+            self.min_batch_duration = duration;
+        }
+        let bucket = BATCH_DURATION_BUCKETS
+            .iter()
+            .position(|bound| duration < *bound)
+            .unwrap_or(BATCH_DURATION_BUCKETS.len());
+        self.batch_duration_buckets[bucket] += 1;
+    }
+
+    pub fn record_unrequested_items(&mut self, count: u32) {
+        self.unrequested_items += count;
+    }
+
     pub fn record_load_exec_completed(&mut self, unique_batch_size: u32, loaded_item_count: u32) {
         self.items_loaded += loaded_item_count;
 
@@ -0,0 +1,33 @@
+#![cfg(feature = "admin")]
+
+use async_trait::async_trait;
+use dataload_rs::{admin, BatchFunction, Loader, Loaders};
+
+struct EchoLoader;
+
+#[async_trait]
+impl BatchFunction<i64, String> for EchoLoader {
+    type Context = ();
+    type Loaded = Vec<(i64, String)>;
+    async fn load(&self, keys: &[i64], _context: &()) -> Vec<(i64, String)> {
+        keys.iter().map(|k| (*k, k.to_string())).collect::<Vec<_>>()
+    }
+}
+
+#[tokio::test]
+async fn render_registered_loaders() {
+    let users = Loader::new(EchoLoader {}, ());
+    let orgs = Loader::new(EchoLoader {}, ());
+    let loaders = Loaders::new();
+    loaders.register("users", &users);
+    loaders.register("orgs", &orgs);
+
+    users.load_many(vec![1, 2]).await;
+    users.load(1).await;
+
+    let json = admin::render_json(&loaders);
+    assert!(json.starts_with(r#"{"loaders":[{"name":"users","health":{"#), "{}", json);
+    assert!(json.contains(r#""cache_entries":2"#), "{}", json);
+    assert!(json.contains(r#""hit_rate":0.3333333333333333"#), "{}", json);
+    assert!(json.contains(r#"{"name":"orgs""#), "{}", json);
+}
@@ -0,0 +1,32 @@
+#![cfg(feature = "derive")]
+
+use std::collections::HashMap;
+
+use dataload_rs::{batch_fn, Loader};
+
+/// Loads user names from the context.
+#[batch_fn]
+async fn users(keys: &[i64], names: &HashMap<i64, String>) -> Vec<(i64, String)> {
+    keys.iter().filter_map(|k| names.get(k).map(|name| (*k, name.clone()))).collect()
+}
+
+#[tokio::test]
+async fn batch_fn_defines_batch_function() {
+    let names = HashMap::from([(1, "ada".to_owned()), (2, "grace".to_owned())]);
+    let loader = Loader::new(users, names);
+    assert_eq!(
+        loader.load_many(vec![1, 2, 3]).await,
+        vec![Some("ada".to_owned()), Some("grace".to_owned()), None]
+    );
+}
+
+#[batch_fn]
+async fn name_lengths(keys: &[String], _context: &()) -> HashMap<String, usize> {
+    keys.iter().map(|k| (k.clone(), k.len())).collect()
+}
+
+#[tokio::test]
+async fn batch_fn_may_return_a_map() {
+    let loader = Loader::new(name_lengths, ());
+    assert_eq!(loader.load("grace".to_owned()).await, Some(5));
+}
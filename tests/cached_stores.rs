@@ -0,0 +1,33 @@
+#![cfg(feature = "cached")]
+
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+
+use async_trait::async_trait;
+use cached::SizedCache;
+use dataload_rs::{BatchFunction, Loader};
+
+struct CountingLoader;
+
+#[async_trait]
+impl BatchFunction<i64, String> for CountingLoader {
+    type Context = Arc<AtomicUsize>;
+    type Loaded = Vec<(i64, String)>;
+    async fn load(&self, keys: &[i64], context: &Arc<AtomicUsize>) -> Vec<(i64, String)> {
+        context.fetch_add(keys.len(), Ordering::SeqCst);
+        keys.iter().map(|k| (*k, k.to_string())).collect::<Vec<_>>()
+    }
+}
+
+#[tokio::test]
+async fn sized_cache_evicts() {
+    let loaded = Arc::new(AtomicUsize::new(0));
+    let loader =
+        Loader::builder(CountingLoader {}, loaded.clone()).cache(SizedCache::with_size(1)).build();
+    assert_eq!(loader.load(1).await.as_deref(), Some("1"));
+    assert_eq!(loader.load(1).await.as_deref(), Some("1"));
+    assert_eq!(loader.load(2).await.as_deref(), Some("2"));
+    // Key 1 was evicted by key 2 and must be loaded again.
+    assert_eq!(loader.load(1).await.as_deref(), Some("1"));
+    assert_eq!(loaded.load(Ordering::SeqCst), 3);
+}
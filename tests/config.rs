@@ -0,0 +1,56 @@
+#![cfg(feature = "config")]
+
+use std::sync::{Arc, Mutex};
+
+use async_trait::async_trait;
+use dataload_rs::{BatchFunction, CacheConfig, Loader, LoaderConfig};
+
+struct RecordingLoader;
+
+#[async_trait]
+impl BatchFunction<i64, String> for RecordingLoader {
+    type Context = Arc<Mutex<Vec<Vec<i64>>>>;
+    type Loaded = Vec<(i64, String)>;
+    async fn load(&self, keys: &[i64], context: &Self::Context) -> Vec<(i64, String)> {
+        context.lock().unwrap().push(keys.to_vec());
+        keys.iter().map(|k| (*k, k.to_string())).collect::<Vec<_>>()
+    }
+}
+
+#[test]
+fn deserialize_config() {
+    let config: LoaderConfig = serde_json::from_str(
+        r#"{
+            "cache": {"kind": "lru", "max_entries": 2},
+            "max_staged_keys": 10,
+            "dispatch_after_ms": 5
+        }"#,
+    )
+    .unwrap();
+    assert_eq!(config.cache, CacheConfig::Lru { max_entries: 2 });
+    assert_eq!(config.max_staged_keys, Some(10));
+    assert_eq!(config.dispatch_after_ms, Some(5));
+    assert_eq!(config.batch_timeout_ms, None);
+
+    assert_eq!(serde_json::from_str::<LoaderConfig>("{}").unwrap(), LoaderConfig::default());
+    assert!(serde_json::from_str::<LoaderConfig>(r#"{"max_batch": 1}"#).is_err());
+}
+
+#[tokio::test]
+async fn loader_from_config() {
+    let config: LoaderConfig = serde_json::from_str(
+        r#"{"cache": {"kind": "lru", "max_entries": 1}, "max_staged_keys": 1}"#,
+    )
+    .unwrap();
+    let batches = Arc::new(Mutex::new(Vec::new()));
+    let loader = Loader::from_config(&config, RecordingLoader {}, batches.clone());
+
+    // Keys are staged in separate frames.
+    assert_eq!(
+        futures::future::join(loader.load(1), loader.load(2)).await,
+        (Some("1".to_owned()), Some("2".to_owned()))
+    );
+    // Only the most recently loaded key is retained.
+    assert_eq!(loader.load(1).await, Some("1".to_owned()));
+    assert_eq!(*batches.lock().unwrap(), vec![vec![1], vec![2], vec![1]]);
+}
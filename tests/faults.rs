@@ -0,0 +1,58 @@
+#![cfg(feature = "fault-injection")]
+
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use async_trait::async_trait;
+use dataload_rs::{faults::Faults, BatchFunction, ClosedPolicy, Loader, LoaderError};
+use futures::future;
+
+struct EchoLoader;
+
+#[async_trait]
+impl BatchFunction<i64, String> for EchoLoader {
+    type Context = ();
+    type Loaded = Vec<(i64, String)>;
+    async fn load(&self, keys: &[i64], _context: &()) -> Vec<(i64, String)> {
+        keys.iter().map(|k| (*k, k.to_string())).collect::<Vec<_>>()
+    }
+}
+
+#[tokio::test]
+async fn dropped_frame() {
+    let faults = Arc::new(Faults::new());
+    let loader = Loader::builder(EchoLoader {}, ()).faults(faults.clone()).build();
+
+    faults.drop_frames(1);
+    assert_eq!(loader.try_load(1).await, Err(LoaderError::Closed));
+    assert_eq!(loader.try_load(1).await, Ok(Some("1".to_owned())));
+    assert_eq!(loader.health().batches, 1);
+}
+
+#[tokio::test]
+async fn dropped_response() {
+    let faults = Arc::new(Faults::new());
+    let loader = Loader::builder(EchoLoader {}, ())
+        .closed_policy(ClosedPolicy::ReturnNone)
+        .faults(faults.clone())
+        .build();
+
+    faults.drop_responses(1);
+    let (first, second) = future::join(loader.load(1), loader.load(2)).await;
+    assert_eq!((first, second), (None, Some("2".to_owned())));
+    assert_eq!(loader.load(1).await, Some("1".to_owned()));
+}
+
+#[tokio::test]
+async fn delayed_cache_insert() {
+    let faults = Arc::new(Faults::new());
+    let loader = Loader::builder(EchoLoader {}, ()).faults(faults.clone()).build();
+
+    faults.delay_cache_inserts(Some(Duration::from_millis(20)));
+    let start = Instant::now();
+    assert_eq!(loader.load(1).await, Some("1".to_owned()));
+    assert!(start.elapsed() >= Duration::from_millis(20));
+
+    faults.delay_cache_inserts(None);
+    assert_eq!(loader.load(2).await, Some("2".to_owned()));
+}
@@ -0,0 +1,58 @@
+#![cfg(feature = "file-cache")]
+
+use std::sync::{Arc, Mutex};
+
+use async_trait::async_trait;
+use dataload_rs::{BatchFunction, Cache, FileCache, Loader};
+
+struct RecordingLoader;
+
+#[async_trait]
+impl BatchFunction<i64, String> for RecordingLoader {
+    type Context = Arc<Mutex<Vec<Vec<i64>>>>;
+    type Loaded = Vec<(i64, String)>;
+    async fn load(&self, keys: &[i64], batches: &Self::Context) -> Vec<(i64, String)> {
+        batches.lock().unwrap().push(keys.to_vec());
+        keys.iter().map(|k| (*k, k.to_string())).collect()
+    }
+}
+
+#[tokio::test]
+async fn file_cache_survives_reopening() {
+    let path = std::env::temp_dir().join(format!("dataload-file-cache-{}", std::process::id()));
+    let _ = std::fs::remove_file(&path);
+
+    let batches = Arc::new(Mutex::new(Vec::new()));
+    let loader =
+        Loader::with_cache(RecordingLoader {}, batches.clone(), FileCache::open(&path).unwrap());
+    loader.load_many(vec![1, 2, 3]).await;
+    loader.clear_acked(2).await;
+    drop(loader);
+
+    let loader =
+        Loader::with_cache(RecordingLoader {}, batches.clone(), FileCache::open(&path).unwrap());
+    assert_eq!(
+        loader.load_many(vec![1, 2, 3]).await,
+        vec![Some("1".to_owned()), Some("2".to_owned()), Some("3".to_owned())]
+    );
+    assert_eq!(*batches.lock().unwrap(), vec![vec![1, 2, 3], vec![2]]);
+    let _ = std::fs::remove_file(&path);
+}
+
+#[test]
+fn file_cache_flush_keeps_entries_of_other_clones() {
+    let path =
+        std::env::temp_dir().join(format!("dataload-file-cache-flush-{}", std::process::id()));
+    let _ = std::fs::remove_file(&path);
+
+    let mut first = FileCache::<i64, String>::open(&path).unwrap();
+    let mut second = first.clone();
+    Cache::insert(&mut first, 1, "1".to_owned());
+    Cache::insert(&mut second, 2, "2".to_owned());
+    Cache::flush(&mut first);
+    drop((first, second));
+
+    let mut reopened = FileCache::<i64, String>::open(&path).unwrap();
+    assert_eq!(Cache::get(&mut reopened, &[1, 2]), vec![None, Some("2".to_owned())]);
+    let _ = std::fs::remove_file(&path);
+}
@@ -0,0 +1,95 @@
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Mutex;
+
+use async_trait::async_trait;
+use dataload_rs::{BatchFunction, Loader};
+use futures::future;
+use tracing::span::{Attributes, Id, Record};
+use tracing::{Event, Instrument, Metadata, Subscriber};
+use tracing_core::span::Current;
+
+thread_local! {
+    static ENTERED: RefCell<Vec<Id>> = const { RefCell::new(Vec::new()) };
+}
+
+/// Records the name of every span, and the spans each span follows from.
+#[derive(Default)]
+struct FollowsFromRecorder {
+    next_id: AtomicU64,
+    spans: Mutex<HashMap<u64, &'static Metadata<'static>>>,
+    follows: Mutex<Vec<(u64, u64)>>,
+}
+
+impl FollowsFromRecorder {
+    fn named(&self, id: u64) -> &'static str {
+        self.spans.lock().unwrap()[&id].name()
+    }
+}
+
+impl Subscriber for &'static FollowsFromRecorder {
+    fn enabled(&self, _metadata: &Metadata<'_>) -> bool {
+        true
+    }
+
+    fn new_span(&self, span: &Attributes<'_>) -> Id {
+        let id = self.next_id.fetch_add(1, Ordering::SeqCst) + 1;
+        self.spans.lock().unwrap().insert(id, span.metadata());
+        Id::from_u64(id)
+    }
+
+    fn record(&self, _span: &Id, _values: &Record<'_>) {}
+
+    fn record_follows_from(&self, span: &Id, follows: &Id) {
+        self.follows.lock().unwrap().push((span.into_u64(), follows.into_u64()));
+    }
+
+    fn event(&self, _event: &Event<'_>) {}
+
+    fn enter(&self, span: &Id) {
+        ENTERED.with(|entered| entered.borrow_mut().push(span.clone()));
+    }
+
+    fn exit(&self, _span: &Id) {
+        ENTERED.with(|entered| entered.borrow_mut().pop());
+    }
+
+    fn current_span(&self) -> Current {
+        match ENTERED.with(|entered| entered.borrow().last().cloned()) {
+            Some(id) => Current::new(id.clone(), self.spans.lock().unwrap()[&id.into_u64()]),
+            None => Current::none(),
+        }
+    }
+}
+
+struct EchoLoader;
+
+#[async_trait]
+impl BatchFunction<i64, i64> for EchoLoader {
+    type Context = ();
+    type Loaded = Vec<(i64, i64)>;
+    async fn load(&self, keys: &[i64], _context: &()) -> Vec<(i64, i64)> {
+        keys.iter().map(|k| (*k, *k)).collect()
+    }
+}
+
+#[tokio::test]
+async fn batch_span_follows_from_requester_spans() {
+    let recorder: &'static FollowsFromRecorder = Box::leak(Box::default());
+    tracing::subscriber::set_global_default(recorder).unwrap();
+
+    let loader = Loader::new(EchoLoader {}, ());
+    future::join(
+        loader.load(1).instrument(tracing::info_span!("resolver")),
+        loader.load(2).instrument(tracing::info_span!("resolver")),
+    )
+    .await;
+
+    let follows = recorder.follows.lock().unwrap().clone();
+    assert_eq!(follows.len(), 2);
+    assert!(follows.iter().all(|(batch, requester)| {
+        recorder.named(*batch) == "batch" && recorder.named(*requester) == "resolver"
+    }));
+    assert_ne!(follows[0].1, follows[1].1);
+}
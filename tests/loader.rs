@@ -1,8 +1,19 @@
 use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
 
 use async_trait::async_trait;
-use dataload_rs::{BatchFunction, Loader};
-use futures::future;
+use dataload_rs::{
+    load_join, AsyncCache, AuthScope, BackendError, BatchDispatch, BatchError, BatchFailed,
+    BatchFunction, BatchInfo, BatchResources, BatchSample, Cache, CachePolicy, ClosedPolicy,
+    DedupStrategy, DispatchStrategy, DropPolicy, FailureReason, FrameId, GroupBatchFunction,
+    GroupLoader, InvalidationBus, InvalidationSource, IsolationGroup, KeyOf, Keyed, Limit,
+    LoadFailure, Loader, LoaderError, LoaderObserver, Loaders, Lookup, MissingKeys, OverflowPolicy,
+    PrefetchKey, PrimePolicy, ResolutionOrder, ResourcePool, Scoped, SharedContext, SharedInFlight,
+    SoftLimitWarning, TieredCache, TtlCache, UnrequestedKeysPolicy, Versioned,
+};
+use futures::stream::{BoxStream, StreamExt};
+use futures::{future, stream};
 
 #[derive(Debug, PartialEq, Eq, Clone)]
 struct DummyData(String);
@@ -16,7 +27,8 @@ struct DummyDataLoader;
 #[async_trait]
 impl BatchFunction<i64, DummyData> for DummyDataLoader {
     type Context = DummyContext;
-    async fn load(keys: &[i64], context: &DummyContext) -> Vec<(i64, DummyData)> {
+    type Loaded = Vec<(i64, DummyData)>;
+    async fn load(&self, keys: &[i64], context: &DummyContext) -> Vec<(i64, DummyData)> {
         keys.iter()
             .filter_map(|k| context.map.get(k).cloned().map(|v| (*k, DummyData(v))))
             .collect::<Vec<_>>()
@@ -88,3 +100,2097 @@ async fn load_async() {
         )
     );
 }
+
+#[tokio::test]
+async fn prime_policy_keep_oldest() {
+    let loader = Loader::builder(DummyDataLoader {}, DummyContext { map: HashMap::new() })
+        .prime_policy(PrimePolicy::KeepOldest)
+        .build();
+    loader.prime(1, DummyData("first".to_owned()));
+    loader.prime(1, DummyData("second".to_owned()));
+    assert_eq!(loader.load(1).await, Some(DummyData("first".to_owned())));
+}
+
+#[tokio::test]
+async fn prime_policy_keep_newest_by_version() {
+    let loader = Loader::builder(DummyDataLoader {}, DummyContext { map: HashMap::new() })
+        .prime_policy(PrimePolicy::keep_newest_by_version(|v: &DummyData| {
+            v.0.trim_start_matches('v').parse().unwrap()
+        }))
+        .build();
+    loader.prime(1, DummyData("v2".to_owned()));
+    loader.prime(1, DummyData("v1".to_owned()));
+    assert_eq!(loader.load(1).await, Some(DummyData("v2".to_owned())));
+    loader.prime(1, DummyData("v3".to_owned()));
+    assert_eq!(loader.load(1).await, Some(DummyData("v3".to_owned())));
+}
+
+struct VersionedLoader;
+
+#[async_trait]
+impl BatchFunction<i64, Versioned<String>> for VersionedLoader {
+    type Context = Mutex<Option<Versioned<String>>>;
+    type Loaded = Vec<(i64, Versioned<String>)>;
+    async fn load(&self, keys: &[i64], context: &Self::Context) -> Vec<(i64, Versioned<String>)> {
+        let value = context.lock().unwrap().clone();
+        keys.iter().filter_map(|k| value.clone().map(|v| (*k, v))).collect::<Vec<_>>()
+    }
+}
+
+#[tokio::test]
+async fn versioned_load_does_not_overwrite_newer_prime() {
+    let loader = Loader::builder(
+        VersionedLoader {},
+        Mutex::new(Some(Versioned::new(1, "stale".to_owned()))),
+    )
+    .versioned()
+    .build();
+    // The prime lands in the same frame as the load, after the key has been staged.
+    let (loaded, _) = future::join(loader.load(7), async {
+        loader.prime(7, Versioned::new(2, "fresh".to_owned()));
+    })
+    .await;
+    assert_eq!(loaded.map(Versioned::into_inner), Some("fresh".to_owned()));
+}
+
+#[tokio::test]
+async fn overflow_reject() {
+    let mut context = DummyContext { map: HashMap::new() };
+    context.map.insert(1, "one".to_owned());
+    context.map.insert(2, "two".to_owned());
+
+    let loader = Loader::builder(DummyDataLoader {}, context)
+        .max_pending_requests(1)
+        .overflow_policy(OverflowPolicy::Reject)
+        .build();
+    assert_eq!(
+        future::join(loader.load(1), loader.load(2)).await,
+        (Some(DummyData("one".to_owned())), None)
+    );
+    let health = loader.health();
+    assert_eq!((health.pending_requests, health.staged_keys), (0, 0));
+}
+
+#[tokio::test]
+async fn overflow_spill() {
+    let mut context = DummyContext { map: HashMap::new() };
+    context.map.insert(1, "one".to_owned());
+    context.map.insert(2, "two".to_owned());
+
+    let loader = Loader::builder(DummyDataLoader {}, context).max_staged_keys(1).build();
+    assert_eq!(
+        future::join(loader.load(1), loader.load(2)).await,
+        (Some(DummyData("one".to_owned())), Some(DummyData("two".to_owned())))
+    );
+}
+
+#[tokio::test]
+async fn zero_frame_caps_admit_one_request() {
+    let mut context = DummyContext { map: HashMap::new() };
+    context.map.insert(1, "one".to_owned());
+
+    let loader = Loader::builder(DummyDataLoader {}, context)
+        .max_pending_requests(0)
+        .max_staged_keys(0)
+        .overflow_policy(OverflowPolicy::Reject)
+        .build();
+    assert_eq!(loader.load(1).await, Some(DummyData("one".to_owned())));
+}
+
+#[tokio::test]
+async fn batch_cost_budget_splits_frames() {
+    let batches = Arc::new(Mutex::new(Vec::new()));
+    let loader = Loader::builder(RecordingLoader {}, batches.clone())
+        .batch_cost_budget(10, |key: &i64| *key as u64)
+        .build();
+
+    let values = future::join_all([5, 4, 3, 8].map(|k| loader.load(k))).await;
+    assert!(values.iter().all(Option::is_some));
+    assert_eq!(*batches.lock().unwrap(), vec![vec![3, 4, 5], vec![8]]);
+}
+
+struct NeighborAwareLoader;
+
+#[async_trait]
+impl BatchFunction<i64, DummyData> for NeighborAwareLoader {
+    type Context = ();
+    type Loaded = Vec<(i64, DummyData)>;
+    async fn load(&self, _keys: &[i64], _context: &()) -> Vec<(i64, DummyData)> {
+        // The worker always calls load_with_info.
+        Vec::new()
+    }
+
+    async fn load_with_info(
+        &self,
+        keys: &[i64],
+        _context: &(),
+        info: BatchInfo<i64, DummyData>,
+    ) -> Vec<(i64, DummyData)> {
+        keys.iter()
+            .map(|k| {
+                let neighbors =
+                    info.cached_neighbors().map(|(_, v)| v.0.as_str()).collect::<Vec<_>>();
+                (*k, DummyData(neighbors.join(",")))
+            })
+            .collect::<Vec<_>>()
+    }
+}
+
+#[tokio::test]
+async fn batch_info_cached_neighbors() {
+    let loader =
+        Loader::builder(NeighborAwareLoader {}, ()).neighbors(|k| vec![k - 1, k + 1]).build();
+    loader.prime(6, DummyData("six".to_owned()));
+    loader.prime(8, DummyData("eight".to_owned()));
+    loader.prime(20, DummyData("twenty".to_owned()));
+    assert_eq!(loader.load(7).await, Some(DummyData("six,eight".to_owned())));
+}
+
+struct SharedLoader;
+
+#[async_trait]
+impl BatchFunction<i64, Arc<String>> for SharedLoader {
+    type Context = HashMap<i64, Arc<String>>;
+    type Loaded = Vec<(i64, Arc<String>)>;
+    async fn load(&self, keys: &[i64], context: &Self::Context) -> Vec<(i64, Arc<String>)> {
+        keys.iter().filter_map(|k| context.get(k).cloned().map(|v| (*k, v))).collect::<Vec<_>>()
+    }
+}
+
+#[tokio::test]
+async fn load_ref_shares_cached_allocation() {
+    let value = Arc::new("large document".to_owned());
+    let mut context = HashMap::new();
+    context.insert(1, value.clone());
+
+    let loader = Loader::new(SharedLoader {}, context);
+    let first = loader.load_ref(1).await.unwrap();
+    let second = loader.load_ref(1).await.unwrap();
+    assert_eq!(first.as_str(), "large document");
+    assert!(Arc::ptr_eq(&first.into_arc(), &second.into_arc()));
+    assert_eq!(loader.load_many_ref(vec![2]).await, vec![None]);
+}
+
+#[tokio::test]
+async fn load_join_across_loaders() {
+    let mut context = DummyContext { map: HashMap::new() };
+    context.map.insert(1, "one".to_owned());
+    let dummy_loader = Loader::new(DummyDataLoader {}, context);
+
+    let mut shared_context = HashMap::new();
+    shared_context.insert(2, Arc::new("two".to_owned()));
+    let shared_loader = Loader::new(SharedLoader {}, shared_context);
+
+    let (dummy, shared, missing) =
+        load_join!((dummy_loader, 1), (shared_loader, 2), (dummy_loader, 3)).await;
+    assert_eq!(dummy, Some(DummyData("one".to_owned())));
+    assert_eq!(shared.as_deref().map(String::as_str), Some("two"));
+    assert_eq!(missing, None);
+}
+
+struct PanickingLoader;
+
+#[async_trait]
+impl BatchFunction<i64, DummyData> for PanickingLoader {
+    type Context = ();
+    type Loaded = Vec<(i64, DummyData)>;
+    #[allow(clippy::diverging_sub_expression)]
+    async fn load(&self, _keys: &[i64], _context: &()) -> Vec<(i64, DummyData)> {
+        panic!("backend exploded");
+    }
+}
+
+#[tokio::test]
+async fn closed_policy_return_none() {
+    let loader = Loader::builder(RecordingLoader {}, Arc::new(Mutex::new(Vec::new())))
+        .closed_policy(ClosedPolicy::ReturnNone)
+        .build();
+    loader.clone().shutdown().await;
+    assert_eq!(loader.load(1).await, None);
+    assert_eq!(loader.load_many(vec![1, 2]).await, vec![None, None]);
+    loader.prime(1, DummyData("ignored".to_owned()));
+}
+
+#[tokio::test]
+#[should_panic(expected = "loader worker `users_by_id` is no longer running")]
+async fn named_loader_identifies_itself_when_closed() {
+    let loader = Loader::new_named("users_by_id", RecordingLoader {}, Arc::default());
+    assert_eq!(loader.name(), "users_by_id");
+    loader.clone().shutdown().await;
+    loader.load(1).await;
+}
+
+/// Echoes every requested key and records the keys of each batch it was invoked with.
+struct RecordingLoader;
+
+#[async_trait]
+impl BatchFunction<i64, DummyData> for RecordingLoader {
+    type Context = Arc<Mutex<Vec<Vec<i64>>>>;
+    type Loaded = Vec<(i64, DummyData)>;
+    async fn load(&self, keys: &[i64], context: &Self::Context) -> Vec<(i64, DummyData)> {
+        context.lock().unwrap().push(keys.to_vec());
+        keys.iter().map(|k| (*k, DummyData(k.to_string()))).collect::<Vec<_>>()
+    }
+}
+
+#[tokio::test]
+async fn urgent_deadline_dispatched_first() {
+    let batches = Arc::new(Mutex::new(Vec::new()));
+    let loader = Loader::builder(RecordingLoader {}, batches.clone())
+        .urgent_deadline_threshold(Duration::from_millis(50))
+        .build();
+
+    let far = Instant::now() + Duration::from_secs(60);
+    let (relaxed, urgent, distant) = future::join3(
+        loader.load_many(vec![1, 2]),
+        loader.load_with_deadline(2, Instant::now()),
+        loader.load_with_deadline(3, far),
+    )
+    .await;
+    assert_eq!(relaxed, vec![Some(DummyData("1".to_owned())), Some(DummyData("2".to_owned()))]);
+    assert_eq!(urgent, Some(DummyData("2".to_owned())));
+    assert_eq!(distant, Some(DummyData("3".to_owned())));
+    assert_eq!(*batches.lock().unwrap(), vec![vec![2], vec![1, 3]]);
+}
+
+#[tokio::test]
+async fn prime_stream_in_chunks() {
+    let batches = Arc::new(Mutex::new(Vec::new()));
+    let loader = Loader::new(RecordingLoader {}, batches.clone());
+
+    let pairs = (0..10).map(|k| (k, DummyData(format!("primed {}", k))));
+    assert_eq!(loader.prime_stream(stream::iter(pairs), 3).await, 10);
+    assert_eq!(loader.load(9).await, Some(DummyData("primed 9".to_owned())));
+    assert!(batches.lock().unwrap().is_empty());
+}
+
+#[tokio::test]
+async fn health_counters() {
+    let batches = Arc::new(Mutex::new(Vec::new()));
+    let loader = Loader::new(RecordingLoader {}, batches);
+
+    loader.prime(1, DummyData("primed".to_owned()));
+    assert_eq!(loader.health().queue_depth, 1);
+
+    loader.load_many(vec![1, 2, 3, 3]).await;
+    loader.load(2).await;
+    let health = loader.health();
+    assert_eq!(health.queue_depth, 0);
+    assert_eq!(health.keys_requested, 5);
+    assert_eq!(health.cache_hits, 2);
+    assert_eq!(health.batches, 1);
+    assert_eq!(health.max_batch_size, 2);
+    assert_eq!(health.hit_rate(), 0.4);
+}
+
+#[tokio::test]
+async fn load_many_detailed_reports_failures() {
+    let mut context = DummyContext { map: HashMap::new() };
+    context.map.insert(1, "one".to_owned());
+    let loader = Loader::new(DummyDataLoader {}, context);
+
+    assert_eq!(
+        loader.load_many_detailed(vec![1, 2]).await,
+        vec![
+            Ok(DummyData("one".to_owned())),
+            Err(LoadFailure { key: 2, reason: FailureReason::Missing })
+        ]
+    );
+}
+
+#[tokio::test]
+async fn load_progressive() {
+    let batches = Arc::new(Mutex::new(Vec::new()));
+    let loader = Loader::new(RecordingLoader {}, batches.clone());
+    loader.prime(2, DummyData("primed".to_owned()));
+
+    let progressive = loader.load_progressive(vec![1, 2, 3]).await;
+    assert_eq!(progressive.cached(), &[None, Some(DummyData("primed".to_owned())), None]);
+    assert!(!progressive.is_complete());
+    assert_eq!(
+        progressive.complete().await,
+        vec![
+            Some(DummyData("1".to_owned())),
+            Some(DummyData("primed".to_owned())),
+            Some(DummyData("3".to_owned()))
+        ]
+    );
+    assert_eq!(*batches.lock().unwrap(), vec![vec![1, 3]]);
+}
+
+#[tokio::test]
+async fn clear_cascades_to_dependents() {
+    let parent_batches = Arc::new(Mutex::new(Vec::new()));
+    let parent = Loader::new(RecordingLoader {}, parent_batches.clone());
+    let child_batches = Arc::new(Mutex::new(Vec::new()));
+    let child = Loader::new(RecordingLoader {}, child_batches.clone());
+    let grandchild_batches = Arc::new(Mutex::new(Vec::new()));
+    let grandchild = Loader::new(RecordingLoader {}, grandchild_batches.clone());
+
+    parent.add_dependent(&child, |k| vec![k * 10, k * 10 + 1]);
+    child.add_dependent(&grandchild, |k| vec![k * 10]);
+
+    child.load_many(vec![10, 11, 20]).await;
+    grandchild.load_many(vec![100, 200]).await;
+    parent.clear(1);
+    child.load_many(vec![10, 11, 20]).await;
+    grandchild.load_many(vec![100, 110, 200]).await;
+
+    assert_eq!(*child_batches.lock().unwrap(), vec![vec![10, 11, 20], vec![10, 11]]);
+    assert_eq!(*grandchild_batches.lock().unwrap(), vec![vec![100, 200], vec![100, 110]]);
+    assert!(parent_batches.lock().unwrap().is_empty());
+}
+
+#[derive(Debug, Default)]
+struct RecordingObserver {
+    soft_limits: Mutex<Vec<(Limit, usize)>>,
+    batches: Mutex<Vec<(FrameId, usize)>>,
+    errors: Mutex<Vec<(&'static str, usize)>>,
+}
+
+impl LoaderObserver for RecordingObserver {
+    fn on_soft_limit(&self, warning: &SoftLimitWarning) {
+        self.soft_limits.lock().unwrap().push((warning.limit, warning.current));
+    }
+
+    fn on_batch(&self, batch: &BatchDispatch) {
+        self.batches.lock().unwrap().push((batch.frame, batch.keys));
+    }
+
+    fn on_batch_error(&self, failed: &BatchFailed) {
+        self.errors.lock().unwrap().push((failed.error.category(), failed.keys));
+    }
+}
+
+#[tokio::test]
+async fn soft_limit_warnings() {
+    let observer = Arc::new(RecordingObserver::default());
+    let loader = Loader::builder(RecordingLoader {}, Arc::new(Mutex::new(Vec::new())))
+        .observer(observer.clone())
+        .soft_limit(Limit::StagedKeys, 3)
+        .soft_limit(Limit::CacheEntries, 4)
+        .build();
+
+    loader.load_many(vec![1, 2, 3]).await;
+    loader.load_many(vec![4, 5, 6]).await;
+    loader.load(7).await;
+    loader.load_many(vec![8, 9, 10]).await;
+    assert_eq!(
+        *observer.soft_limits.lock().unwrap(),
+        vec![(Limit::StagedKeys, 3), (Limit::CacheEntries, 6), (Limit::StagedKeys, 3)]
+    );
+}
+
+struct HangingLoader;
+
+#[async_trait]
+impl BatchFunction<i64, DummyData> for HangingLoader {
+    type Context = Arc<tokio::sync::Notify>;
+    type Loaded = Vec<(i64, DummyData)>;
+    async fn load(&self, _keys: &[i64], _context: &Self::Context) -> Vec<(i64, DummyData)> {
+        Vec::new()
+    }
+
+    async fn load_with_info(
+        &self,
+        _keys: &[i64],
+        context: &Self::Context,
+        info: BatchInfo<i64, DummyData>,
+    ) -> Vec<(i64, DummyData)> {
+        // Simulates backend work that outlives the batch function's own future.
+        let cancellation = info.cancellation().clone();
+        let backend_cancelled = context.clone();
+        tokio::spawn(async move {
+            cancellation.cancelled().await;
+            backend_cancelled.notify_one();
+        });
+        future::pending().await
+    }
+}
+
+#[tokio::test]
+async fn batch_timeout_cancels_batch() {
+    let backend_cancelled = Arc::new(tokio::sync::Notify::new());
+    let loader = Loader::builder(HangingLoader {}, backend_cancelled.clone())
+        .batch_timeout(Duration::from_millis(10))
+        .build();
+
+    assert_eq!(
+        loader.load_many_detailed(vec![1]).await,
+        vec![Err(LoadFailure { key: 1, reason: FailureReason::Batch(BatchError::Timeout) })]
+    );
+    backend_cancelled.notified().await;
+}
+
+#[tokio::test]
+async fn batch_panic_fails_only_its_requests() {
+    let loader = Loader::new(PanickingLoader {}, ());
+
+    // The worker survives the panic and keeps serving requests.
+    for key in [1, 2] {
+        assert_eq!(
+            loader.load_many_detailed(vec![key]).await,
+            vec![Err(LoadFailure { key, reason: FailureReason::Batch(BatchError::Panic) })]
+        );
+    }
+    loader.prime(3, DummyData("primed".to_owned()));
+    assert_eq!(loader.load(3).await, Some(DummyData("primed".to_owned())));
+}
+
+#[tokio::test]
+async fn dedup_strategies() {
+    for (strategy, expected) in [
+        (DedupStrategy::SortedUnique, vec![1, 2, 3]),
+        (DedupStrategy::PreserveFirstSeen, vec![3, 1, 2]),
+        (DedupStrategy::None, vec![3, 1, 3, 2, 1]),
+    ] {
+        let batches = Arc::new(Mutex::new(Vec::new()));
+        let loader =
+            Loader::builder(RecordingLoader {}, batches.clone()).dedup_strategy(strategy).build();
+        future::join(loader.load_many(vec![3, 1, 3]), loader.load_many(vec![2, 1])).await;
+        assert_eq!(*batches.lock().unwrap(), vec![expected], "{:?}", strategy);
+    }
+}
+
+#[tokio::test]
+async fn clones_share_worker_and_isolated_loaders_do_not() {
+    let batches = Arc::new(Mutex::new(Vec::new()));
+    let loader = Loader::new(RecordingLoader {}, batches.clone());
+    let clone = loader.clone();
+    let isolated = loader.split_isolated();
+
+    future::join3(loader.load(1), clone.load(2), isolated.load(3)).await;
+    let mut recorded = batches.lock().unwrap().clone();
+    recorded.sort();
+    assert_eq!(recorded, vec![vec![1, 2], vec![3]]);
+
+    drop(loader);
+    assert_eq!(clone.load(1).await, Some(DummyData("1".to_owned())));
+    assert_eq!(isolated.load(1).await, Some(DummyData("1".to_owned())));
+    assert_eq!(batches.lock().unwrap().len(), 3);
+}
+
+struct AppContext {
+    prefix: &'static str,
+}
+
+struct PrefixLoader;
+
+#[async_trait]
+impl BatchFunction<i64, DummyData> for PrefixLoader {
+    type Context = SharedContext<AppContext>;
+    type Loaded = Vec<(i64, DummyData)>;
+    async fn load(&self, keys: &[i64], context: &Self::Context) -> Vec<(i64, DummyData)> {
+        keys.iter().map(|k| (*k, DummyData(format!("{}{}", context.prefix, k)))).collect()
+    }
+}
+
+#[tokio::test]
+async fn registry_injects_shared_context() {
+    let loaders = Loaders::new();
+    loaders.provide_context(Arc::new(AppContext { prefix: "user:" }));
+
+    let users = loaders.loader("users", PrefixLoader {});
+    let staged = loaders.builder(PrefixLoader {}).max_staged_keys(10).build();
+    assert_eq!(users.load(1).await, Some(DummyData("user:1".to_owned())));
+    assert_eq!(staged.load(2).await, Some(DummyData("user:2".to_owned())));
+    assert_eq!(loaders.health().len(), 1);
+    assert!(loaders.context::<DummyContext>().is_none());
+}
+
+#[tokio::test]
+async fn receipts_report_serving_frame() {
+    let observer = Arc::new(RecordingObserver::default());
+    let loader = Loader::builder(RecordingLoader {}, Arc::new(Mutex::new(Vec::new())))
+        .observer(observer.clone())
+        .build();
+
+    let receipts = future::join_all((0..5).map(|k| loader.load_with_receipt(k))).await;
+    let frame = receipts[0].1.frame().unwrap();
+    assert!(receipts
+        .iter()
+        .all(|(value, receipt)| value.is_some() && receipt.frame() == Some(frame)));
+
+    let (_, cached) = loader.load_with_receipt(3).await;
+    assert_eq!(cached.frame(), None);
+    let (_, next) = loader.load_with_receipt(5).await;
+    assert!(next.frame().unwrap() > frame);
+    assert_eq!(*observer.batches.lock().unwrap(), vec![(frame, 5), (next.frame().unwrap(), 1)]);
+}
+
+/// Filters out odd keys and returns even keys below 10.
+struct FilteringLoader;
+
+#[async_trait]
+impl BatchFunction<i64, DummyData> for FilteringLoader {
+    type Context = ();
+    type Loaded = Vec<(i64, DummyData)>;
+    async fn load(&self, _keys: &[i64], _context: &()) -> Vec<(i64, DummyData)> {
+        // The worker always calls load_with_info.
+        Vec::new()
+    }
+
+    async fn load_with_info(
+        &self,
+        keys: &[i64],
+        _context: &(),
+        info: BatchInfo<i64, DummyData>,
+    ) -> Vec<(i64, DummyData)> {
+        keys.iter()
+            .filter_map(|k| {
+                if k % 2 == 1 {
+                    info.filter(*k);
+                }
+                (*k < 10).then(|| (*k, DummyData(k.to_string())))
+            })
+            .collect()
+    }
+}
+
+#[tokio::test]
+async fn filtered_keys_are_distinguished_from_missing() {
+    let loader = Loader::new(FilteringLoader {}, ());
+
+    assert_eq!(
+        loader.load_many_detailed(vec![2, 3, 12]).await,
+        vec![
+            Ok(DummyData("2".to_owned())),
+            Err(LoadFailure { key: 3, reason: FailureReason::Filtered }),
+            Err(LoadFailure { key: 12, reason: FailureReason::Missing })
+        ]
+    );
+    assert_eq!(loader.load(3).await, None);
+    assert_eq!(loader.health().cache_entries, 1);
+}
+
+/// Echoes each key along with the scope it was requested under.
+struct ScopeEchoLoader;
+
+#[async_trait]
+impl BatchFunction<Scoped<i64>, DummyData> for ScopeEchoLoader {
+    type Context = ();
+    type Loaded = Vec<(Scoped<i64>, DummyData)>;
+    async fn load(&self, keys: &[Scoped<i64>], _context: &()) -> Vec<(Scoped<i64>, DummyData)> {
+        keys.iter().map(|k| (k.clone(), DummyData(format!("{}:{}", k.scope.id(), k.key)))).collect()
+    }
+}
+
+#[tokio::test]
+async fn scoped_loader_partitions_cache_by_scope() {
+    let loader = Loader::builder(ScopeEchoLoader {}, ()).build_scoped();
+
+    let alice = AuthScope::new(1);
+    let bob = AuthScope::of("bob");
+    alice.run(async { loader.prime(7, DummyData("alice's".to_owned())) }).await;
+    assert_eq!(alice.run(loader.load(7)).await, Some(DummyData("alice's".to_owned())));
+    assert_eq!(bob.run(loader.load(7)).await, Some(DummyData(format!("{}:7", bob.id()))));
+    assert_eq!(loader.load(7).await, None);
+    assert_eq!(loader.inner().health().cache_entries, 2);
+}
+
+#[tokio::test]
+async fn frozen_loader_serves_only_cached_values() {
+    let batches = Arc::new(Mutex::new(Vec::new()));
+    let loader = Loader::new(RecordingLoader {}, batches.clone());
+    loader.load(1).await;
+    loader.prime(2, DummyData("primed".to_owned()));
+
+    let frozen = loader.freeze();
+    assert_eq!(
+        frozen.load_many(vec![1, 2, 3]).await,
+        vec![Some(DummyData("1".to_owned())), Some(DummyData("primed".to_owned())), None]
+    );
+    assert_eq!(frozen.load(4).await, None);
+    assert_eq!(*batches.lock().unwrap(), vec![vec![1]]);
+}
+
+#[tokio::test]
+async fn adaptive_caching_disables_cache_on_low_hit_rate() {
+    let batches = Arc::new(Mutex::new(Vec::new()));
+    let loader =
+        Loader::builder(RecordingLoader {}, batches.clone()).adaptive_caching(0.5, 4).build();
+
+    loader.load_many(vec![1, 2]).await;
+    loader.load(1).await;
+    assert!(!loader.health().caching_disabled);
+    loader.load(3).await;
+    assert!(loader.health().caching_disabled);
+
+    assert_eq!(loader.load(5).await, Some(DummyData("5".to_owned())));
+    assert_eq!(loader.load(5).await, Some(DummyData("5".to_owned())));
+    assert_eq!(loader.load(2).await, Some(DummyData("2".to_owned())));
+    assert_eq!(*batches.lock().unwrap(), vec![vec![1, 2], vec![3], vec![5], vec![5]]);
+    assert_eq!(loader.health().cache_entries, 3);
+}
+
+#[tokio::test]
+async fn sample_batches_passes_fraction_of_results() {
+    let (sample_tx, mut sample_rx) = tokio::sync::mpsc::unbounded_channel();
+    let loader = Loader::builder(RecordingLoader {}, Arc::new(Mutex::new(Vec::new())))
+        .sample_batches(0.5, move |sample: BatchSample<i64, DummyData>| {
+            sample_tx.send((sample.frame.get(), sample.keys, sample.values)).unwrap();
+        })
+        .build();
+
+    for key in 1..=4 {
+        loader.load(key).await;
+    }
+    assert_eq!(sample_rx.recv().await.unwrap(), (2, vec![2], vec![(2, DummyData("2".to_owned()))]));
+    assert_eq!(sample_rx.recv().await.unwrap(), (4, vec![4], vec![(4, DummyData("4".to_owned()))]));
+    drop(loader);
+    assert_eq!(sample_rx.recv().await, None);
+}
+
+#[tokio::test(flavor = "current_thread")]
+async fn pumped_loader_runs_frames_on_demand() {
+    let batches = Arc::new(Mutex::new(Vec::new()));
+    let (loader, mut pump) = Loader::builder(RecordingLoader {}, batches.clone()).build_with_pump();
+
+    let (one, two, frames) = futures::join!(loader.load(1), loader.load(2), pump.run_until_idle());
+    assert_eq!((one.unwrap().0, two.unwrap().0, frames), ("1".to_owned(), "2".to_owned(), 1));
+    assert_eq!(pump.run_until_idle().await, 0);
+
+    let (three, ran) = futures::join!(loader.load(3), pump.run_frame());
+    assert_eq!((three, ran), (Some(DummyData("3".to_owned())), true));
+    assert_eq!(*batches.lock().unwrap(), vec![vec![1, 2], vec![3]]);
+
+    drop(loader);
+    assert!(!pump.run_frame().await);
+}
+
+/// Returns values for keys below 10 and reports the rest of the batch as failed.
+struct FailingLoader;
+
+#[async_trait]
+impl BatchFunction<i64, DummyData> for FailingLoader {
+    type Context = ();
+    type Loaded = Vec<(i64, DummyData)>;
+    async fn load(&self, _keys: &[i64], _context: &()) -> Vec<(i64, DummyData)> {
+        // The worker always calls load_with_info.
+        Vec::new()
+    }
+
+    async fn load_with_info(
+        &self,
+        keys: &[i64],
+        _context: &(),
+        info: BatchInfo<i64, DummyData>,
+    ) -> Vec<(i64, DummyData)> {
+        if keys.iter().any(|k| *k >= 10) {
+            info.fail("database exploded");
+        }
+        keys.iter().filter(|k| **k < 10).map(|k| (*k, DummyData(k.to_string()))).collect()
+    }
+}
+
+#[test]
+fn error_chain_reports_backend_error_once() {
+    let error = LoaderError::Batch(BatchError::Backend(BackendError::new("database exploded")));
+    let mut chain = vec![error.to_string()];
+    let mut source = std::error::Error::source(&error);
+    while let Some(error) = source {
+        chain.push(error.to_string());
+        source = error.source();
+    }
+    assert_eq!(chain, vec!["batch function failed", "database exploded"]);
+}
+
+#[tokio::test]
+async fn batch_errors_reach_every_caller_in_frame() {
+    let observer = Arc::new(RecordingObserver::default());
+    let loader = Loader::builder(FailingLoader {}, ()).observer(observer.clone()).build();
+
+    let (ok, failed, detailed) =
+        future::join3(loader.try_load(1), loader.try_load(10), loader.load_many_detailed(vec![11]))
+            .await;
+    assert_eq!(ok, Ok(Some(DummyData("1".to_owned()))));
+    let error = match failed {
+        Err(LoaderError::Batch(BatchError::Backend(error))) => error,
+        other => panic!("unexpected result {:?}", other),
+    };
+    assert_eq!(error.to_string(), "database exploded");
+    assert_eq!(
+        detailed,
+        vec![Err(LoadFailure {
+            key: 11,
+            reason: FailureReason::Batch(BatchError::Backend(error))
+        })]
+    );
+
+    assert_eq!(
+        loader.try_load_many(vec![1, 2]).await,
+        Ok(vec![Some(DummyData("1".to_owned())), Some(DummyData("2".to_owned()))])
+    );
+    assert_eq!(loader.load(12).await, None);
+    assert_eq!(*observer.errors.lock().unwrap(), vec![("backend", 3), ("backend", 1)]);
+}
+
+#[tokio::test]
+async fn load_grouped_zips_items_with_values() {
+    let mut context = DummyContext { map: HashMap::new() };
+    context.map.insert(1, "one".to_owned());
+    context.map.insert(2, "two".to_owned());
+    let loader = Loader::new(DummyDataLoader {}, context);
+
+    let posts = vec![("first", 2), ("second", 3), ("third", 1)];
+    assert_eq!(
+        loader.load_grouped(posts, |(_, author)| *author).await,
+        vec![
+            (("first", 2), Some(DummyData("two".to_owned()))),
+            (("second", 3), None),
+            (("third", 1), Some(DummyData("one".to_owned())))
+        ]
+    );
+}
+
+/// Uppercases string keys, demonstrating loaders keyed by non-`Copy` types.
+struct UppercaseLoader;
+
+#[async_trait]
+impl BatchFunction<String, String> for UppercaseLoader {
+    type Context = Arc<Mutex<Vec<Vec<String>>>>;
+    type Loaded = Vec<(String, String)>;
+    async fn load(&self, keys: &[String], context: &Self::Context) -> Vec<(String, String)> {
+        context.lock().unwrap().push(keys.to_vec());
+        keys.iter().map(|k| (k.clone(), k.to_uppercase())).collect()
+    }
+}
+
+#[tokio::test]
+async fn string_keys() {
+    let batches = Arc::new(Mutex::new(Vec::new()));
+    let loader = Loader::new(UppercaseLoader {}, batches.clone());
+
+    let (a, many) = future::join(
+        loader.load("a".to_owned()),
+        loader.load_many(vec!["b".to_owned(), "a".to_owned()]),
+    )
+    .await;
+    assert_eq!(a, Some("A".to_owned()));
+    assert_eq!(many, vec![Some("B".to_owned()), Some("A".to_owned())]);
+    loader.clear("a".to_owned());
+    assert_eq!(loader.load("a".to_owned()).await, Some("A".to_owned()));
+    assert_eq!(
+        *batches.lock().unwrap(),
+        vec![vec!["a".to_owned(), "b".to_owned()], vec!["a".to_owned()]]
+    );
+}
+
+#[tokio::test]
+async fn try_load_reports_closed_worker_without_panicking() {
+    let loader = Loader::new(RecordingLoader {}, Arc::new(Mutex::new(Vec::new())));
+    loader.clone().shutdown().await;
+    assert_eq!(loader.try_load(1).await, Err(LoaderError::Closed));
+    assert_eq!(loader.try_load_many(vec![1, 2]).await, Err(LoaderError::Closed));
+}
+
+/// Records each batch, then echoes its keys once the gate is opened.
+struct GatedLoader;
+
+#[async_trait]
+impl BatchFunction<i64, DummyData> for GatedLoader {
+    type Context = (Arc<Mutex<Vec<Vec<i64>>>>, Arc<tokio::sync::Notify>);
+    type Loaded = Vec<(i64, DummyData)>;
+    async fn load(&self, keys: &[i64], (batches, gate): &Self::Context) -> Vec<(i64, DummyData)> {
+        let opened = gate.notified();
+        batches.lock().unwrap().push(keys.to_vec());
+        opened.await;
+        keys.iter().map(|k| (*k, DummyData(k.to_string()))).collect()
+    }
+}
+
+async fn wait_for_batches(batches: &Mutex<Vec<Vec<i64>>>, count: usize) {
+    while batches.lock().unwrap().len() < count {
+        tokio::time::sleep(Duration::from_millis(1)).await;
+    }
+}
+
+#[tokio::test]
+async fn in_flight_store_collapses_fetches_across_loaders() {
+    let store = Arc::new(SharedInFlight::new());
+    let gate = Arc::new(tokio::sync::Notify::new());
+    let (batches_a, batches_b) =
+        (Arc::new(Mutex::new(Vec::new())), Arc::new(Mutex::new(Vec::new())));
+    let a = Loader::builder(GatedLoader {}, (batches_a.clone(), gate.clone()))
+        .in_flight_store(store.clone())
+        .build();
+    let b = Loader::builder(GatedLoader {}, (batches_b.clone(), gate.clone()))
+        .in_flight_store(store)
+        .build();
+
+    let load_a = tokio::spawn(async move { a.load_many(vec![1, 2]).await });
+    wait_for_batches(&batches_a, 1).await;
+    let load_b = tokio::spawn(async move { b.load_many(vec![2, 3]).await });
+    wait_for_batches(&batches_b, 1).await;
+    gate.notify_waiters();
+
+    let expected =
+        |keys: [i64; 2]| keys.iter().map(|k| Some(DummyData(k.to_string()))).collect::<Vec<_>>();
+    assert_eq!(load_a.await.unwrap(), expected([1, 2]));
+    assert_eq!(load_b.await.unwrap(), expected([2, 3]));
+    assert_eq!(*batches_b.lock().unwrap(), vec![vec![3]]);
+}
+
+#[tokio::test]
+async fn in_flight_store_resolves_overlapping_keys_in_opposite_orders() {
+    let store = Arc::new(SharedInFlight::new());
+    let gate = Arc::new(tokio::sync::Notify::new());
+    let (batches_a, batches_b) =
+        (Arc::new(Mutex::new(Vec::new())), Arc::new(Mutex::new(Vec::new())));
+    let a = Loader::builder(GatedLoader {}, (batches_a.clone(), gate.clone()))
+        .in_flight_store(store.clone())
+        .build();
+    let b = Loader::builder(GatedLoader {}, (batches_b.clone(), gate.clone()))
+        .in_flight_store(store)
+        .build();
+
+    let load_a = tokio::spawn(async move { a.load_many(vec![1, 2, 3]).await });
+    wait_for_batches(&batches_a, 1).await;
+    let load_b = tokio::spawn(async move { b.load_many(vec![4, 3, 2]).await });
+    wait_for_batches(&batches_b, 1).await;
+    gate.notify_waiters();
+
+    let expected =
+        |keys: [i64; 3]| keys.iter().map(|k| Some(DummyData(k.to_string()))).collect::<Vec<_>>();
+    let (loaded_a, loaded_b) = tokio::time::timeout(Duration::from_secs(1), async {
+        (load_a.await.unwrap(), load_b.await.unwrap())
+    })
+    .await
+    .expect("both loaders resolved");
+    assert_eq!(loaded_a, expected([1, 2, 3]));
+    assert_eq!(loaded_b, expected([4, 3, 2]));
+    assert_eq!(*batches_b.lock().unwrap(), vec![vec![4]]);
+}
+
+/// Hands out sessions numbered by the order in which they were set up.
+#[derive(Default)]
+struct Sessions {
+    opened: std::sync::atomic::AtomicUsize,
+}
+
+#[async_trait]
+impl BatchResources for Sessions {
+    type Resource = usize;
+    async fn acquire(&self) -> usize {
+        self.opened.fetch_add(1, std::sync::atomic::Ordering::SeqCst)
+    }
+}
+
+/// Tags each value with the session its batch ran on.
+struct SessionLoader;
+
+#[async_trait]
+impl BatchFunction<i64, DummyData> for SessionLoader {
+    type Context = ();
+    type Loaded = Vec<(i64, DummyData)>;
+    async fn load(&self, _keys: &[i64], _context: &()) -> Vec<(i64, DummyData)> {
+        // The worker always calls load_with_info.
+        Vec::new()
+    }
+
+    async fn load_with_info(
+        &self,
+        keys: &[i64],
+        _context: &(),
+        mut info: BatchInfo<i64, DummyData>,
+    ) -> Vec<(i64, DummyData)> {
+        let session = *info.resource::<Sessions>().unwrap();
+        keys.iter().map(|k| (*k, DummyData(format!("{}:{}", session, k)))).collect()
+    }
+}
+
+#[tokio::test]
+async fn batch_resources_are_pooled_across_frames() {
+    let pool = ResourcePool::new(Sessions::default(), 1);
+    let users = Loader::builder(SessionLoader {}, ()).batch_resources(pool.clone()).build();
+    let orgs = Loader::builder(SessionLoader {}, ()).batch_resources(pool.clone()).build();
+
+    assert_eq!(users.load(1).await, Some(DummyData("0:1".to_owned())));
+    assert_eq!(users.load(2).await, Some(DummyData("0:2".to_owned())));
+    assert_eq!(orgs.load(3).await, Some(DummyData("0:3".to_owned())));
+    assert_eq!(pool.idle(), 1);
+}
+
+#[tokio::test]
+async fn lru_cache_evicts_least_recently_used() {
+    let batches = Arc::new(Mutex::new(Vec::new()));
+    let loader = Loader::builder(RecordingLoader {}, batches.clone()).lru_cache(2).build();
+
+    loader.load(1).await;
+    loader.load(2).await;
+    loader.load(1).await;
+    loader.load(3).await;
+    loader.load_many(vec![1, 2]).await;
+    assert_eq!(*batches.lock().unwrap(), vec![vec![1], vec![2], vec![3], vec![2]]);
+    assert_eq!(loader.health().cache_entries, 2);
+}
+
+#[tokio::test]
+async fn lru_cache_smaller_than_batch_resolves_every_key() {
+    let loader =
+        Loader::builder(RecordingLoader {}, Arc::new(Mutex::new(Vec::new()))).lru_cache(1).build();
+    let expected = |k: i64| Some(DummyData(k.to_string()));
+    assert_eq!(loader.load_many(vec![4, 5, 6]).await, vec![expected(4), expected(5), expected(6)]);
+    assert_eq!(future::join(loader.load(1), loader.load(2)).await, (expected(1), expected(2)));
+}
+
+#[tokio::test]
+async fn ttl_cache_expires_entries() {
+    let batches = Arc::new(Mutex::new(Vec::new()));
+    let loader = Loader::builder(RecordingLoader {}, batches.clone())
+        .cache(TtlCache::new(Duration::from_millis(20)).with_sweep_interval(Duration::ZERO))
+        .build();
+
+    loader.load_many(vec![1, 2]).await;
+    loader.load(1).await;
+    tokio::time::sleep(Duration::from_millis(30)).await;
+    loader.load(1).await;
+    assert_eq!(*batches.lock().unwrap(), vec![vec![1, 2], vec![1]]);
+    assert_eq!(loader.health().cache_entries, 1);
+}
+
+#[tokio::test]
+async fn ttl_cache_shorter_than_batch_resolves_loaded_keys() {
+    let loader = Loader::builder(RecordingLoader {}, Arc::new(Mutex::new(Vec::new())))
+        .ttl_cache(Duration::from_nanos(1))
+        .build();
+    assert_eq!(loader.load(1).await, Some(DummyData("1".to_owned())));
+    assert_eq!(
+        loader.load_many(vec![2, 3]).await,
+        vec![Some(DummyData("2".to_owned())), Some(DummyData("3".to_owned()))]
+    );
+}
+
+#[test]
+fn ttl_cache_evicts_expired_entries_past_stale_window() {
+    let mut cache = TtlCache::new(Duration::from_millis(10));
+    let mut stale_cache = TtlCache::new(Duration::from_millis(10)).with_stale_window(Duration::MAX);
+    for cache in [&mut cache, &mut stale_cache] {
+        Cache::insert(cache, 1, DummyData("1".to_owned()));
+    }
+    std::thread::sleep(Duration::from_millis(20));
+
+    assert_eq!(Cache::get(&mut cache, &[1]), vec![None]);
+    assert_eq!(Cache::len(&cache), 0);
+    assert_eq!(Cache::get(&mut stale_cache, &[1]), vec![None]);
+    assert_eq!(Cache::len(&stale_cache), 1);
+    assert_eq!(
+        Cache::get_stale(&mut stale_cache, &[1], Duration::MAX),
+        vec![Some(DummyData("1".to_owned()))]
+    );
+}
+
+/// A cache that only serves the values it was seeded with and never stores loaded values.
+#[derive(Clone)]
+struct SeededCache(HashMap<i64, DummyData>);
+
+impl Cache for SeededCache {
+    type K = i64;
+    type V = DummyData;
+
+    fn get(&mut self, keys: &[i64]) -> Vec<Option<DummyData>> {
+        keys.iter().map(|k| self.0.get(k).cloned()).collect()
+    }
+
+    fn insert(&mut self, _key: i64, _value: DummyData) {}
+
+    fn insert_many<I: IntoIterator<Item = (i64, DummyData)>>(&mut self, _key_vals: I) {}
+
+    fn remove(&mut self, _keys: &[i64]) {}
+
+    fn len(&self) -> usize {
+        self.0.len()
+    }
+
+    fn flush(&mut self) {}
+}
+
+#[tokio::test]
+async fn custom_cache_backs_loader() {
+    let batches = Arc::new(Mutex::new(Vec::new()));
+    let seeded = SeededCache(HashMap::from([(1, DummyData("seeded".to_owned()))]));
+    let loader = Loader::with_cache(RecordingLoader {}, batches.clone(), seeded);
+
+    assert_eq!(loader.load(1).await, Some(DummyData("seeded".to_owned())));
+    loader.load(2).await;
+    loader.load(2).await;
+    assert_eq!(*batches.lock().unwrap(), vec![vec![2], vec![2]]);
+}
+
+#[tokio::test]
+async fn cache_failing_to_store_still_resolves_loaded_values() {
+    let unavailable = SeededCache(HashMap::new());
+    let loader =
+        Loader::with_cache(RecordingLoader {}, Arc::new(Mutex::new(Vec::new())), unavailable);
+    assert_eq!(
+        loader.load_many(vec![1, 2]).await,
+        vec![Some(DummyData("1".to_owned())), Some(DummyData("2".to_owned()))]
+    );
+}
+
+#[derive(Default)]
+struct Concurrency {
+    running: std::sync::atomic::AtomicUsize,
+    max: std::sync::atomic::AtomicUsize,
+}
+
+struct ConcurrencyLoader;
+
+#[async_trait]
+impl BatchFunction<i64, DummyData> for ConcurrencyLoader {
+    type Context = Arc<Concurrency>;
+    type Loaded = Vec<(i64, DummyData)>;
+    async fn load(&self, keys: &[i64], context: &Self::Context) -> Vec<(i64, DummyData)> {
+        use std::sync::atomic::Ordering;
+        let running = context.running.fetch_add(1, Ordering::SeqCst) + 1;
+        context.max.fetch_max(running, Ordering::SeqCst);
+        tokio::time::sleep(Duration::from_millis(10)).await;
+        context.running.fetch_sub(1, Ordering::SeqCst);
+        keys.iter().map(|k| (*k, DummyData(k.to_string()))).collect()
+    }
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn isolation_group_limits_concurrent_batches() {
+    let concurrency = Arc::new(Concurrency::default());
+    let group = IsolationGroup::new("db", 1);
+    let loaders = (0..3)
+        .map(|_| {
+            Loader::builder(ConcurrencyLoader {}, concurrency.clone())
+                .isolation_group(group.clone())
+                .build()
+        })
+        .collect::<Vec<_>>();
+
+    let values = future::join_all(loaders.iter().map(|loader| loader.load(1))).await;
+    assert!(values.iter().all(Option::is_some));
+    assert_eq!(concurrency.max.load(std::sync::atomic::Ordering::SeqCst), 1);
+    assert_eq!(group.available(), 1);
+}
+
+/// A cache shared between loaders that, like a remote cache, takes a while to respond.
+#[derive(Clone, Default)]
+struct SlowSharedCache(Arc<Mutex<HashMap<i64, DummyData>>>);
+
+#[async_trait]
+impl AsyncCache for SlowSharedCache {
+    type K = i64;
+    type V = DummyData;
+
+    async fn get(&mut self, keys: &[i64]) -> Vec<Option<DummyData>> {
+        tokio::time::sleep(Duration::from_millis(1)).await;
+        let entries = self.0.lock().unwrap();
+        keys.iter().map(|k| entries.get(k).cloned()).collect()
+    }
+
+    async fn insert(&mut self, key: i64, value: DummyData) {
+        tokio::time::sleep(Duration::from_millis(1)).await;
+        self.0.lock().unwrap().insert(key, value);
+    }
+
+    async fn insert_many(&mut self, key_vals: Vec<(i64, DummyData)>) {
+        tokio::time::sleep(Duration::from_millis(1)).await;
+        self.0.lock().unwrap().extend(key_vals);
+    }
+
+    async fn remove(&mut self, keys: &[i64]) {
+        tokio::time::sleep(Duration::from_millis(1)).await;
+        let mut entries = self.0.lock().unwrap();
+        for key in keys {
+            entries.remove(key);
+        }
+    }
+
+    fn len(&self) -> usize {
+        self.0.lock().unwrap().len()
+    }
+
+    async fn flush(&mut self) {
+        self.0.lock().unwrap().clear();
+    }
+}
+
+#[tokio::test]
+async fn async_cache_shared_between_loaders() {
+    let batches = Arc::new(Mutex::new(Vec::new()));
+    let shared = SlowSharedCache::default();
+    let first = Loader::builder(RecordingLoader {}, batches.clone()).cache(shared.clone()).build();
+    let second = Loader::builder(RecordingLoader {}, batches.clone()).cache(shared).build();
+
+    assert_eq!(first.load_many(vec![1, 2]).await.len(), 2);
+    assert_eq!(second.load(2).await, Some(DummyData("2".to_owned())));
+    second.clear(2);
+    // Ops are applied in order, so the clear has reached the shared cache once this resolves.
+    second.load(3).await;
+    assert_eq!(first.load(2).await, Some(DummyData("2".to_owned())));
+    assert_eq!(*batches.lock().unwrap(), vec![vec![1, 2], vec![3], vec![2]]);
+}
+
+#[tokio::test]
+async fn reconfigure_applies_between_frames() {
+    let batches = Arc::new(Mutex::new(Vec::new()));
+    let loader = Loader::builder(RecordingLoader {}, batches.clone()).lru_cache(10).build();
+
+    loader.load_many(vec![1, 2, 3]).await;
+    loader.reconfigure(CachePolicy::new().with_max_entries(2));
+    loader.load(3).await;
+    assert_eq!(loader.health().cache_entries, 2);
+    loader.load(1).await;
+    assert_eq!(*batches.lock().unwrap(), vec![vec![1, 2, 3], vec![1]]);
+
+    let batches = Arc::new(Mutex::new(Vec::new()));
+    let loader = Loader::builder(RecordingLoader {}, batches.clone())
+        .ttl_cache(Duration::from_millis(20))
+        .build();
+    loader.load(1).await;
+    loader.reconfigure(CachePolicy::new().with_ttl(Duration::from_secs(60)));
+    tokio::time::sleep(Duration::from_millis(30)).await;
+    loader.load(1).await;
+    assert_eq!(*batches.lock().unwrap(), vec![vec![1]]);
+}
+
+#[tokio::test]
+async fn probe_round_trips_without_caching() {
+    let batches = Arc::new(Mutex::new(Vec::new()));
+    let loader = Loader::new(RecordingLoader {}, batches.clone());
+    assert_eq!(loader.probe().await, Ok(()));
+    assert_eq!(loader.probe_with(vec![7]).await, Ok(vec![Some(DummyData("7".to_owned()))]));
+    loader.load(7).await;
+    assert_eq!(*batches.lock().unwrap(), vec![vec![], vec![7], vec![7]]);
+
+    let loader = Loader::new(FailingLoader {}, ());
+    assert!(loader.probe().await.is_ok());
+    match loader.probe_with(vec![10]).await {
+        Err(LoaderError::Batch(BatchError::Backend(error))) => {
+            assert_eq!(error.to_string(), "database exploded")
+        }
+        other => panic!("unexpected result {:?}", other),
+    }
+}
+
+#[tokio::test]
+async fn panicking_probe_fails_without_stopping_worker() {
+    let loader = Loader::new(PanickingLoader {}, ());
+    assert_eq!(loader.probe_with(vec![1]).await, Err(LoaderError::Batch(BatchError::Panic)));
+    assert_eq!(loader.probe_with(vec![2]).await, Err(LoaderError::Batch(BatchError::Panic)));
+}
+
+#[tokio::test]
+async fn tiered_cache_falls_back_to_shared_tier() {
+    let batches = Arc::new(Mutex::new(Vec::new()));
+    let shared = SlowSharedCache::default();
+    let first = Loader::builder(RecordingLoader {}, batches.clone())
+        .cache(TieredCache::new(HashMap::new(), shared.clone()))
+        .build();
+    let second = Loader::builder(RecordingLoader {}, batches.clone())
+        .cache(TieredCache::new(HashMap::new(), shared.clone()))
+        .build();
+
+    first.load_many(vec![1, 2]).await;
+    assert_eq!(second.load_many(vec![1, 2]).await.len(), 2);
+    assert_eq!(second.health().cache_entries, 2);
+    assert_eq!(*batches.lock().unwrap(), vec![vec![1, 2]]);
+
+    second.clear(1);
+    second.load(2).await;
+    assert!(!shared.0.lock().unwrap().contains_key(&1));
+    second.load(1).await;
+    assert_eq!(*batches.lock().unwrap(), vec![vec![1, 2], vec![1]]);
+}
+
+#[tokio::test]
+async fn prefetch_window_stages_neighboring_keys() {
+    let batches = Arc::new(Mutex::new(Vec::new()));
+    let loader = Loader::builder(RecordingLoader {}, batches.clone()).prefetch_window(2).build();
+
+    assert_eq!(loader.load(10).await, Some(DummyData("10".to_owned())));
+    loader.load_many(vec![11, 12, 8]).await;
+    loader.load(13).await;
+    assert_eq!(*batches.lock().unwrap(), vec![vec![8, 9, 10, 11, 12], vec![13, 14, 15]]);
+    assert_eq!(0u8.window(2), vec![1, 2]);
+}
+
+#[tokio::test]
+async fn prefetch_window_is_capped() {
+    let batches = Arc::new(Mutex::new(Vec::new()));
+    let loader =
+        Loader::builder(RecordingLoader {}, batches.clone()).prefetch_window(usize::MAX).build();
+    loader.load(0).await;
+    assert_eq!(batches.lock().unwrap()[0].len(), 513);
+
+    let batches = Arc::new(Mutex::new(Vec::new()));
+    let loader = Loader::builder(RecordingLoader {}, batches.clone())
+        .prefetch_window(10)
+        .max_staged_keys(4)
+        .build();
+    loader.load(0).await;
+    assert_eq!(*batches.lock().unwrap(), vec![vec![-10, -9, -8, 0]]);
+}
+
+#[tokio::test]
+async fn negative_caching_remembers_misses() {
+    let mut context = DummyContext { map: HashMap::new() };
+    context.map.insert(1, "one".to_owned());
+    let loader = Loader::builder(DummyDataLoader {}, context)
+        .negative_caching(Duration::from_millis(20))
+        .build();
+
+    assert_eq!(loader.load_many(vec![1, 2]).await, vec![Some(DummyData("one".to_owned())), None]);
+    assert_eq!(loader.load(2).await, None);
+    assert_eq!(loader.health().batches, 1);
+
+    tokio::time::sleep(Duration::from_millis(30)).await;
+    assert_eq!(loader.load(2).await, None);
+    assert_eq!(loader.health().batches, 2);
+
+    loader.clear(2);
+    assert_eq!(loader.load(2).await, None);
+    assert_eq!(loader.health().batches, 3);
+
+    loader.reconfigure(CachePolicy::new().without_negative_caching());
+    assert_eq!(loader.load(2).await, None);
+    assert_eq!(loader.health().batches, 4);
+}
+
+#[tokio::test]
+async fn negative_caching_with_unbounded_ttl_never_expires() {
+    let loader = Loader::builder(DummyDataLoader {}, DummyContext { map: HashMap::new() })
+        .negative_caching(Duration::MAX)
+        .build();
+    assert_eq!(loader.load(1).await, None);
+    assert_eq!(loader.load(1).await, None);
+    assert_eq!(loader.health().batches, 1);
+}
+
+async fn resolution_order_of(resolution_order: ResolutionOrder) -> Vec<i64> {
+    let loader = Loader::builder(RecordingLoader {}, Arc::new(Mutex::new(Vec::new())))
+        .resolution_order(resolution_order)
+        .build();
+    let resolved = Arc::new(Mutex::new(Vec::new()));
+    let far = Instant::now() + Duration::from_secs(60);
+    let tasks = [(1, None), (2, Some(far + Duration::from_secs(1))), (3, Some(far)), (4, None)]
+        .into_iter()
+        .map(|(key, deadline)| {
+            let loader = loader.clone();
+            let resolved = resolved.clone();
+            tokio::spawn(async move {
+                match deadline {
+                    Some(deadline) => loader.load_with_deadline(key, deadline).await,
+                    None => loader.load(key).await,
+                };
+                resolved.lock().unwrap().push(key);
+            })
+        })
+        .collect::<Vec<_>>();
+    future::join_all(tasks).await;
+    let resolved = resolved.lock().unwrap().clone();
+    resolved
+}
+
+#[tokio::test]
+async fn resolution_order_is_configurable() {
+    assert_eq!(resolution_order_of(ResolutionOrder::Fifo).await, vec![1, 2, 3, 4]);
+    assert_eq!(resolution_order_of(ResolutionOrder::Lifo).await, vec![4, 3, 2, 1]);
+    assert_eq!(resolution_order_of(ResolutionOrder::EarliestDeadline).await, vec![3, 2, 1, 4]);
+}
+
+#[tokio::test]
+async fn requests_are_processed_while_batch_executes() {
+    let batches = Arc::new(Mutex::new(Vec::new()));
+    let gate = Arc::new(tokio::sync::Notify::new());
+    let loader = Loader::new(GatedLoader {}, (batches.clone(), gate.clone()));
+    loader.prime(10, DummyData("primed".to_owned()));
+
+    let first = tokio::spawn({
+        let loader = loader.clone();
+        async move { loader.load_many(vec![1, 2]).await }
+    });
+    wait_for_batches(&batches, 1).await;
+
+    // Cache hits are answered while the batch is still executing.
+    assert_eq!(loader.load(10).await, Some(DummyData("primed".to_owned())));
+    let joined = tokio::spawn({
+        let loader = loader.clone();
+        async move { loader.load(2).await }
+    });
+    let next = tokio::spawn({
+        let loader = loader.clone();
+        async move { loader.load_many(vec![2, 3]).await }
+    });
+    tokio::time::sleep(Duration::from_millis(10)).await;
+    gate.notify_waiters();
+    wait_for_batches(&batches, 2).await;
+    gate.notify_waiters();
+
+    let expected =
+        |keys: &[i64]| keys.iter().map(|k| Some(DummyData(k.to_string()))).collect::<Vec<_>>();
+    assert_eq!(first.await.unwrap(), expected(&[1, 2]));
+    assert_eq!(joined.await.unwrap(), Some(DummyData("2".to_owned())));
+    assert_eq!(next.await.unwrap(), expected(&[2, 3]));
+    assert_eq!(*batches.lock().unwrap(), vec![vec![1, 2], vec![3]]);
+}
+
+#[tokio::test]
+async fn late_requests_attach_to_batch_in_flight() {
+    let batches = Arc::new(Mutex::new(Vec::new()));
+    let gate = Arc::new(tokio::sync::Notify::new());
+    let loader = Loader::new(GatedLoader {}, (batches.clone(), gate.clone()));
+
+    let first = tokio::spawn({
+        let loader = loader.clone();
+        async move { loader.load(7).await }
+    });
+    wait_for_batches(&batches, 1).await;
+
+    // Key 7 is in flight, so neither request fetches it again.
+    let late = tokio::spawn({
+        let loader = loader.clone();
+        async move { loader.load(7).await }
+    });
+    let partial = tokio::spawn({
+        let loader = loader.clone();
+        async move { loader.load_many(vec![7, 8]).await }
+    });
+    tokio::time::sleep(Duration::from_millis(10)).await;
+    gate.notify_waiters();
+    wait_for_batches(&batches, 2).await;
+    gate.notify_waiters();
+
+    assert_eq!(first.await.unwrap(), Some(DummyData("7".to_owned())));
+    assert_eq!(late.await.unwrap(), Some(DummyData("7".to_owned())));
+    assert_eq!(
+        partial.await.unwrap(),
+        vec![Some(DummyData("7".to_owned())), Some(DummyData("8".to_owned()))]
+    );
+    assert_eq!(*batches.lock().unwrap(), vec![vec![7], vec![8]]);
+}
+
+#[tokio::test]
+async fn max_concurrent_batches_dispatches_while_batch_executes() {
+    let batches = Arc::new(Mutex::new(Vec::new()));
+    let gate = Arc::new(tokio::sync::Notify::new());
+    let loader = Loader::builder(GatedLoader {}, (batches.clone(), gate.clone()))
+        .max_concurrent_batches(2)
+        .build();
+
+    let first = tokio::spawn({
+        let loader = loader.clone();
+        async move { loader.load(1).await }
+    });
+    wait_for_batches(&batches, 1).await;
+    let second = tokio::spawn({
+        let loader = loader.clone();
+        async move { loader.load_many(vec![1, 2]).await }
+    });
+    wait_for_batches(&batches, 2).await;
+    gate.notify_waiters();
+
+    assert_eq!(first.await.unwrap(), Some(DummyData("1".to_owned())));
+    assert_eq!(
+        second.await.unwrap(),
+        vec![Some(DummyData("1".to_owned())), Some(DummyData("2".to_owned()))]
+    );
+    assert_eq!(*batches.lock().unwrap(), vec![vec![1], vec![2]]);
+}
+
+#[tokio::test]
+async fn transform_values_applies_to_loaded_and_primed_values() {
+    let loader = Loader::builder(UppercaseLoader {}, Arc::new(Mutex::new(Vec::new())))
+        .transform_values(|_key, value: String| value.trim().to_owned())
+        .transform_values(|key, value| format!("{}={}", key.len(), value))
+        .build();
+
+    assert_eq!(loader.load(" ab ".to_owned()).await, Some("4=AB".to_owned()));
+    loader.prime("c".to_owned(), "  primed ".to_owned());
+    assert_eq!(loader.load("c".to_owned()).await, Some("1=primed".to_owned()));
+}
+
+#[tokio::test]
+async fn dispatch_after_collects_requests_into_one_batch() {
+    let batches = Arc::new(Mutex::new(Vec::new()));
+    let loader = Loader::builder(UppercaseLoader {}, batches.clone())
+        .dispatch_after(Duration::from_millis(50))
+        .build();
+
+    let first = tokio::spawn({
+        let loader = loader.clone();
+        async move { loader.load("a".to_owned()).await }
+    });
+    tokio::time::sleep(Duration::from_millis(10)).await;
+    assert_eq!(loader.load("b".to_owned()).await, Some("B".to_owned()));
+    assert_eq!(first.await.unwrap(), Some("A".to_owned()));
+    assert_eq!(*batches.lock().unwrap(), vec![vec!["a".to_owned(), "b".to_owned()]]);
+}
+
+#[tokio::test]
+async fn min_batch_size_waits_for_more_keys() {
+    let batches = Arc::new(Mutex::new(Vec::new()));
+    let loader = Loader::builder(UppercaseLoader {}, batches.clone())
+        .min_batch_size(2, Duration::from_secs(60))
+        .build();
+
+    let first = tokio::spawn({
+        let loader = loader.clone();
+        async move { loader.load("a".to_owned()).await }
+    });
+    tokio::time::sleep(Duration::from_millis(10)).await;
+    assert_eq!(loader.load("b".to_owned()).await, Some("B".to_owned()));
+    assert_eq!(first.await.unwrap(), Some("A".to_owned()));
+    assert_eq!(*batches.lock().unwrap(), vec![vec!["a".to_owned(), "b".to_owned()]]);
+
+    // Frames that stay below the minimum are dispatched once the maximum wait elapses.
+    let loader = Loader::builder(UppercaseLoader {}, batches.clone())
+        .min_batch_size(2, Duration::from_millis(10))
+        .build();
+    assert_eq!(loader.load("c".to_owned()).await, Some("C".to_owned()));
+}
+
+#[tokio::test]
+async fn cold_start_smoothing_caps_first_batches() {
+    let batches = Arc::new(Mutex::new(Vec::new()));
+    let loader = Loader::builder(UppercaseLoader {}, batches.clone())
+        .cold_start_smoothing(2, 2, Duration::from_millis(5))
+        .build();
+    let keys = |keys: &str| keys.chars().map(String::from).collect::<Vec<_>>();
+
+    let values = loader.load_many(keys("abcde")).await;
+    assert_eq!(values, keys("ABCDE").into_iter().map(Some).collect::<Vec<_>>());
+    loader.load_many(keys("fgh")).await;
+    assert_eq!(*batches.lock().unwrap(), vec![keys("ab"), keys("cd"), keys("e"), keys("fgh")]);
+}
+
+#[tokio::test]
+async fn yields_dispatch_strategy_waits_for_sibling_loads() {
+    let batches = Arc::new(Mutex::new(Vec::new()));
+    let loader = Loader::builder(UppercaseLoader {}, batches.clone())
+        .dispatch_strategy(DispatchStrategy::Yields(8))
+        .build();
+
+    let sibling = async {
+        tokio::task::yield_now().await;
+        tokio::task::yield_now().await;
+        loader.load("b".to_owned()).await
+    };
+    let (a, b) = future::join(loader.load("a".to_owned()), sibling).await;
+    assert_eq!((a, b), (Some("A".to_owned()), Some("B".to_owned())));
+    assert_eq!(*batches.lock().unwrap(), vec![vec!["a".to_owned(), "b".to_owned()]]);
+}
+
+/// Returns a related row alongside each requested key.
+struct RelatedRowsLoader;
+
+#[async_trait]
+impl BatchFunction<i64, DummyData> for RelatedRowsLoader {
+    type Context = ();
+    type Loaded = Vec<(i64, DummyData)>;
+    async fn load(&self, keys: &[i64], _context: &()) -> Vec<(i64, DummyData)> {
+        keys.iter()
+            .flat_map(|k| [(*k, DummyData(k.to_string())), (k + 100, DummyData(format!("~{}", k)))])
+            .collect()
+    }
+}
+
+#[tokio::test]
+async fn unrequested_keys_policy() {
+    let accepting = Loader::new(RelatedRowsLoader {}, ());
+    let rejecting = Loader::builder(RelatedRowsLoader {}, ())
+        .unrequested_keys(UnrequestedKeysPolicy::Reject)
+        .build();
+
+    for loader in [&accepting, &rejecting] {
+        assert_eq!(loader.load(1).await, Some(DummyData("1".to_owned())));
+    }
+    assert_eq!(accepting.freeze().load(101).await, Some(DummyData("~1".to_owned())));
+    assert_eq!(rejecting.freeze().load(101).await, None);
+}
+
+#[tokio::test]
+async fn manual_dispatch_waits_for_dispatch() {
+    let batches = Arc::new(Mutex::new(Vec::new()));
+    let loader = Loader::builder(RecordingLoader {}, batches.clone())
+        .dispatch_strategy(DispatchStrategy::Manual)
+        .build();
+
+    let (one, two, ()) = future::join3(loader.load(1), loader.load(2), loader.dispatch()).await;
+    assert_eq!((one, two), (Some(DummyData("1".to_owned())), Some(DummyData("2".to_owned()))));
+
+    let three = tokio::spawn({
+        let loader = loader.clone();
+        async move { loader.load(3).await }
+    });
+    tokio::time::sleep(Duration::from_millis(20)).await;
+    assert_eq!(loader.load(1).await, Some(DummyData("1".to_owned())));
+    assert_eq!(*batches.lock().unwrap(), vec![vec![1, 2]]);
+    loader.dispatch().await;
+    assert_eq!(three.await.unwrap(), Some(DummyData("3".to_owned())));
+    assert_eq!(*batches.lock().unwrap(), vec![vec![1, 2], vec![3]]);
+}
+
+#[tokio::test]
+async fn load_now_skips_dispatch_strategy() {
+    let batches = Arc::new(Mutex::new(Vec::new()));
+    let manual = Loader::builder(RecordingLoader {}, batches.clone())
+        .dispatch_strategy(DispatchStrategy::Manual)
+        .build();
+    let (staged, now) = future::join(manual.load(2), manual.load_now(1)).await;
+    assert_eq!((staged, now), (Some(DummyData("2".to_owned())), Some(DummyData("1".to_owned()))));
+    assert_eq!(*batches.lock().unwrap(), vec![vec![1, 2]]);
+
+    let delayed = Loader::builder(RecordingLoader {}, batches.clone())
+        .dispatch_after(Duration::from_secs(60))
+        .build();
+    let now = tokio::time::timeout(Duration::from_secs(1), delayed.load_now(3)).await;
+    assert_eq!(now.unwrap(), Some(DummyData("3".to_owned())));
+}
+
+#[tokio::test]
+async fn drain_drop_policy_resolves_staged_requests() {
+    let batches = Arc::new(Mutex::new(Vec::new()));
+    let loader = Loader::builder(RecordingLoader {}, batches.clone())
+        .dispatch_strategy(DispatchStrategy::Manual)
+        .drop_policy(DropPolicy::Drain)
+        .build();
+
+    let progressive = loader.load_progressive(vec![1, 2]).await;
+    drop(loader);
+    assert_eq!(
+        progressive.complete().await,
+        vec![Some(DummyData("1".to_owned())), Some(DummyData("2".to_owned()))]
+    );
+    assert_eq!(*batches.lock().unwrap(), vec![vec![1, 2]]);
+}
+
+#[tokio::test]
+async fn drain_drop_policy_exits_despite_subscriptions() {
+    let bus = InvalidationBus::new();
+    let parent = Loader::new(RecordingLoader {}, Arc::new(Mutex::new(Vec::new())));
+    let loader = Loader::builder(RecordingLoader {}, Arc::new(Mutex::new(Vec::new())))
+        .drop_policy(DropPolicy::Drain)
+        .build();
+    loader.subscribe_invalidations(&bus, |key: &i64| vec![*key]);
+    parent.add_dependent(&loader, |key: &i64| vec![*key]);
+    let mut updates = loader.watch(1).await;
+    drop(loader);
+
+    let closed = tokio::time::timeout(Duration::from_secs(1), updates.recv()).await;
+    assert!(matches!(closed, Ok(Err(tokio::sync::broadcast::error::RecvError::Closed))));
+    bus.invalidate(&1);
+    assert_eq!(bus.subscribers(), 0);
+    parent.clear(1);
+}
+
+#[tokio::test]
+async fn shutdown_resolves_pending_requests() {
+    let batches = Arc::new(Mutex::new(Vec::new()));
+    let loader = Loader::builder(RecordingLoader {}, batches.clone())
+        .dispatch_strategy(DispatchStrategy::Manual)
+        .closed_policy(ClosedPolicy::ReturnNone)
+        .build();
+    let handle = loader.clone();
+
+    let staged = tokio::spawn({
+        let loader = loader.clone();
+        async move { loader.load(1).await }
+    });
+    tokio::time::sleep(Duration::from_millis(10)).await;
+    loader.shutdown().await;
+    assert_eq!(staged.await.unwrap(), Some(DummyData("1".to_owned())));
+    assert_eq!(*batches.lock().unwrap(), vec![vec![1]]);
+    assert_eq!(handle.load(2).await, None);
+}
+
+#[tokio::test]
+async fn stopped_worker_cancels_pending_requests() {
+    let batches = Arc::new(Mutex::new(Vec::new()));
+    let gate = Arc::new(tokio::sync::Notify::new());
+    let (loader, mut pump) =
+        Loader::builder(GatedLoader {}, (batches.clone(), gate)).build_with_pump();
+
+    let executing = loader.load(1);
+    futures::pin_mut!(executing);
+    assert!(futures::poll!(&mut executing).is_pending());
+    assert!(tokio::time::timeout(Duration::from_millis(10), pump.run_frame()).await.is_err());
+    assert_eq!(*batches.lock().unwrap(), vec![vec![1]]);
+    let queued = loader.load_many_detailed(vec![2]);
+    futures::pin_mut!(queued);
+    assert!(futures::poll!(&mut queued).is_pending());
+
+    drop(pump);
+    assert_eq!(executing.await, None);
+    assert_eq!(
+        queued.await,
+        vec![Err(LoadFailure { key: 2, reason: FailureReason::Batch(BatchError::Cancelled) })]
+    );
+}
+
+/// Maps even keys to their value and odd keys to nothing, and does not return negative keys.
+struct AvatarLoader;
+
+#[async_trait]
+impl BatchFunction<i64, Option<String>> for AvatarLoader {
+    type Context = Arc<Mutex<Vec<Vec<i64>>>>;
+    type Loaded = Vec<(i64, Option<String>)>;
+    async fn load(&self, keys: &[i64], context: &Self::Context) -> Vec<(i64, Option<String>)> {
+        context.lock().unwrap().push(keys.to_vec());
+        keys.iter()
+            .filter(|k| **k >= 0)
+            .map(|k| (*k, if k % 2 == 0 { Some(k.to_string()) } else { None }))
+            .collect()
+    }
+}
+
+#[tokio::test]
+async fn lookup_distinguishes_absent_from_missing() {
+    let batches = Arc::new(Mutex::new(Vec::new()));
+    let loader = Loader::new(AvatarLoader {}, batches.clone());
+
+    assert_eq!(
+        loader.lookup_many(vec![2, 3, -1]).await,
+        vec![Lookup::Found("2".to_owned()), Lookup::Absent, Lookup::Missing]
+    );
+    // Absent keys are cached, missing keys are loaded again.
+    assert_eq!(loader.lookup(3).await, Lookup::Absent);
+    assert_eq!(loader.lookup(-1).await, Lookup::Missing);
+    assert_eq!(*batches.lock().unwrap(), vec![vec![-1, 2, 3], vec![-1]]);
+}
+
+/// Prefixes loaded values with its own `prefix`, rather than anything from its context.
+struct PrefixedLoader {
+    prefix: String,
+}
+
+#[async_trait]
+impl BatchFunction<i64, DummyData> for PrefixedLoader {
+    type Context = ();
+    type Loaded = Vec<(i64, DummyData)>;
+    async fn load(&self, keys: &[i64], _context: &()) -> Vec<(i64, DummyData)> {
+        keys.iter().map(|k| (*k, DummyData(format!("{}{}", self.prefix, k)))).collect()
+    }
+}
+
+#[tokio::test]
+async fn batch_function_state_is_retained() {
+    let loader = Loader::new(PrefixedLoader { prefix: "user-".to_owned() }, ());
+    assert_eq!(loader.load(1).await, Some(DummyData("user-1".to_owned())));
+    // Isolated loaders share the batch function.
+    let isolated = loader.split_isolated();
+    assert_eq!(isolated.load(2).await, Some(DummyData("user-2".to_owned())));
+}
+
+#[tokio::test]
+async fn from_fn_loads_through_closure() {
+    let offset = Arc::new(100);
+    let loader = Loader::from_fn(offset, |keys, offset| async move {
+        keys.into_iter().filter(|k| *k > 0).map(|k| (k, k + *offset)).collect::<Vec<_>>()
+    });
+    assert_eq!(loader.load_many(vec![1, -1, 2]).await, vec![Some(101), None, Some(102)]);
+}
+
+/// Builds its result as a map, as a batch function backed by a key-value store would.
+struct MapLoader;
+
+#[async_trait]
+impl BatchFunction<i64, DummyData> for MapLoader {
+    type Context = ();
+    type Loaded = HashMap<i64, DummyData>;
+    async fn load(&self, keys: &[i64], _context: &()) -> HashMap<i64, DummyData> {
+        keys.iter().filter(|k| **k > 0).map(|k| (*k, DummyData(k.to_string()))).collect()
+    }
+}
+
+#[tokio::test]
+async fn batch_function_may_return_a_map() {
+    let loader = Loader::new(MapLoader {}, ());
+    assert_eq!(
+        loader.load_many(vec![1, -1, 2]).await,
+        vec![Some(DummyData("1".to_owned())), None, Some(DummyData("2".to_owned()))]
+    );
+}
+
+/// Returns the comments of the requested posts, as rows keyed by post.
+struct CommentsLoader;
+
+#[async_trait]
+impl GroupBatchFunction<i64, String> for CommentsLoader {
+    type Context = Arc<Mutex<Vec<Vec<i64>>>>;
+    type Rows = Vec<(i64, String)>;
+    async fn load(&self, posts: &[i64], context: &Self::Context) -> Vec<(i64, String)> {
+        context.lock().unwrap().push(posts.to_vec());
+        posts
+            .iter()
+            .flat_map(|post| (0..*post).map(move |i| (*post, format!("{}.{}", post, i))))
+            .collect()
+    }
+}
+
+#[tokio::test]
+async fn group_loader_collects_rows_per_key() {
+    let batches = Arc::new(Mutex::new(Vec::new()));
+    let loader = GroupLoader::new(CommentsLoader {}, batches.clone());
+
+    assert_eq!(
+        loader.load_many(vec![2, 0, 1]).await,
+        vec![vec!["2.0".to_owned(), "2.1".to_owned()], vec![], vec!["1.0".to_owned()]]
+    );
+    // Empty groups are cached like any other.
+    assert_eq!(loader.load(0).await, Vec::<String>::new());
+    assert_eq!(*batches.lock().unwrap(), vec![vec![0, 1, 2]]);
+}
+
+#[tokio::test]
+async fn mapped_view_shares_cache() {
+    let batches = Arc::new(Mutex::new(Vec::new()));
+    let loader = Loader::new(RecordingLoader {}, batches.clone());
+    let lengths = loader.map_values(|data| data.0.len());
+    let doubled = lengths.map_values(|len| len * 2);
+
+    assert_eq!(lengths.load_many(vec![7, 12]).await, vec![Some(1), Some(2)]);
+    assert_eq!(doubled.load(12).await, Some(4));
+    assert_eq!(loader.load(7).await, Some(DummyData("7".to_owned())));
+    assert_eq!(*batches.lock().unwrap(), vec![vec![7, 12]]);
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct UserRow {
+    id: i64,
+    email: String,
+}
+
+impl KeyOf<i64> for UserRow {
+    fn key_of(&self) -> i64 {
+        self.id
+    }
+}
+
+/// Returns rows that carry their own key, without pairing them.
+struct UserRowLoader;
+
+#[async_trait]
+impl BatchFunction<i64, UserRow> for UserRowLoader {
+    type Context = ();
+    type Loaded = Keyed<i64, UserRow>;
+    async fn load(&self, keys: &[i64], _context: &()) -> Keyed<i64, UserRow> {
+        keys.iter()
+            .filter(|id| **id > 0)
+            .map(|id| UserRow { id: *id, email: format!("{}@example.com", id) })
+            .collect()
+    }
+}
+
+#[tokio::test]
+async fn keyed_values_are_paired_with_their_keys() {
+    let loader = Loader::new(UserRowLoader {}, ());
+    let users = loader.load_many(vec![2, -1]).await;
+    assert_eq!(users, vec![Some(UserRow { id: 2, email: "2@example.com".to_owned() }), None]);
+}
+
+#[tokio::test]
+async fn load_many_accepts_any_collection_of_keys() {
+    let batches = Arc::new(Mutex::new(Vec::new()));
+    let loader = Loader::new(RecordingLoader {}, batches.clone());
+    let posts = [(1, "first"), (2, "second")];
+
+    let authors = loader.load_many(posts.iter().map(|(author, _)| *author)).await;
+    assert_eq!(authors, vec![Some(DummyData("1".to_owned())), Some(DummyData("2".to_owned()))]);
+    assert_eq!(loader.load_many([3]).await, vec![Some(DummyData("3".to_owned()))]);
+    assert_eq!(loader.load_slice(&[1, 3]).await.len(), 2);
+    assert_eq!(*batches.lock().unwrap(), vec![vec![1, 2], vec![3]]);
+}
+
+#[tokio::test]
+async fn load_map_omits_missing_keys() {
+    let loader = Loader::new(MapLoader {}, ());
+    let values = loader.load_map(vec![1, -1, 2, 1]).await;
+    assert_eq!(
+        values,
+        HashMap::from([(1, DummyData("1".to_owned())), (2, DummyData("2".to_owned()))])
+    );
+}
+
+#[tokio::test]
+async fn strict_loads_report_missing_keys() {
+    let loader = Loader::new(MapLoader {}, ());
+    assert_eq!(
+        loader.load_many_strict(vec![1, 2]).await,
+        Ok(vec![DummyData("1".to_owned()), DummyData("2".to_owned())])
+    );
+    assert_eq!(loader.load_many_strict(vec![-1, 1, -2]).await, Err(MissingKeys(vec![-1, -2])));
+    assert_eq!(loader.load_strict(-3).await, Err(MissingKeys(vec![-3])));
+}
+
+#[tokio::test]
+async fn load_or_else_primes_fallback_value() {
+    let loader = Loader::new(MapLoader {}, ());
+    let created = loader.load_or_else(-1, || async { DummyData("created".to_owned()) }).await;
+    assert_eq!(created, DummyData("created".to_owned()));
+    assert_eq!(loader.load(-1).await, Some(DummyData("created".to_owned())));
+    // The fallback only runs for keys that could not be loaded.
+    let loaded = loader.load_or_else(1, || async { unreachable!() }).await;
+    assert_eq!(loaded, DummyData("1".to_owned()));
+}
+
+#[tokio::test]
+async fn get_cached_never_loads() {
+    let batches = Arc::new(Mutex::new(Vec::new()));
+    let loader = Loader::new(RecordingLoader {}, batches.clone());
+    assert_eq!(loader.get_cached(1).await, None);
+    loader.load(2).await;
+    assert_eq!(
+        loader.get_many_cached(vec![1, 2]).await,
+        vec![None, Some(DummyData("2".to_owned()))]
+    );
+    assert_eq!(*batches.lock().unwrap(), vec![vec![2]]);
+}
+
+#[tokio::test]
+async fn cache_introspection_reflects_enqueued_ops() {
+    let loader = Loader::new(RecordingLoader {}, Arc::default());
+    loader.load_many(vec![1, 2]).await;
+    loader.prime(3, DummyData("3".to_owned()));
+    assert_eq!(loader.cache_len().await, 3);
+    loader.clear(1);
+    assert!(!loader.cache_contains(1).await);
+    assert!(loader.cache_contains(3).await);
+    assert_eq!(loader.cache_len().await, 2);
+}
+
+#[tokio::test]
+async fn clear_all_flushes_cache() {
+    let batches = Arc::new(Mutex::new(Vec::new()));
+    let loader = Loader::new(RecordingLoader {}, batches.clone());
+    loader.load_many(vec![1, 2]).await;
+    loader.clear_all();
+    assert_eq!(loader.cache_len().await, 0);
+    loader.load_many(vec![1, 2]).await;
+    assert_eq!(*batches.lock().unwrap(), vec![vec![1, 2], vec![1, 2]]);
+}
+
+#[tokio::test]
+async fn clear_matching_removes_selected_entries() {
+    let batches = Arc::new(Mutex::new(Vec::new()));
+    let loader = Loader::new(RecordingLoader {}, batches.clone());
+    loader.load_many(vec![1, 2, 3, 4]).await;
+    loader.clear_matching(|key, _| key % 2 == 0);
+    assert_eq!(loader.cache_len().await, 2);
+    loader.load_many(vec![1, 2, 3, 4]).await;
+    assert_eq!(*batches.lock().unwrap(), vec![vec![1, 2, 3, 4], vec![2, 4]]);
+}
+
+#[tokio::test]
+async fn prime_if_absent_only_fills_gaps() {
+    let batches = Arc::new(Mutex::new(Vec::new()));
+    let gate = Arc::new(tokio::sync::Notify::new());
+    let loader = Loader::new(GatedLoader {}, (batches.clone(), gate.clone()));
+    loader.prime(1, DummyData("cached".to_owned()));
+    let in_flight = tokio::spawn({
+        let loader = loader.clone();
+        async move { loader.load(2).await }
+    });
+    wait_for_batches(&batches, 1).await;
+
+    for key in 1..=3 {
+        loader.prime_if_absent(key, DummyData("primed".to_owned()));
+    }
+    gate.notify_waiters();
+    assert_eq!(in_flight.await.unwrap(), Some(DummyData("2".to_owned())));
+    assert_eq!(
+        loader.load_many(vec![1, 2, 3]).await,
+        vec![
+            Some(DummyData("cached".to_owned())),
+            Some(DummyData("2".to_owned())),
+            Some(DummyData("primed".to_owned())),
+        ]
+    );
+}
+
+#[tokio::test]
+async fn acked_prime_and_clear_are_applied_on_return() {
+    let batches = Arc::new(Mutex::new(Vec::new()));
+    let loader = Loader::new(RecordingLoader {}, batches.clone());
+    loader.prime_acked(1, DummyData("primed".to_owned())).await;
+    assert_eq!(loader.load(1).await, Some(DummyData("primed".to_owned())));
+    loader.clear_acked(1).await;
+    assert!(!loader.cache_contains(1).await);
+    assert_eq!(loader.load(1).await, Some(DummyData("1".to_owned())));
+    assert_eq!(*batches.lock().unwrap(), vec![vec![1]]);
+}
+
+#[tokio::test]
+async fn clear_after_removes_key_once_delay_elapses() {
+    let batches = Arc::new(Mutex::new(Vec::new()));
+    let loader = Loader::new(RecordingLoader {}, batches.clone());
+    loader.load_many(vec![1, 2]).await;
+    loader.clear_after(1, Duration::from_millis(20));
+    loader.clear_after(2, Duration::MAX);
+    assert!(loader.cache_contains(1).await);
+    tokio::time::sleep(Duration::from_millis(40)).await;
+    assert!(!loader.cache_contains(1).await);
+    assert!(loader.cache_contains(2).await);
+}
+
+#[tokio::test]
+async fn watch_observes_loads_primes_and_clears() {
+    let loader = Loader::new(RecordingLoader {}, Arc::new(Mutex::new(Vec::new())));
+    let mut updates = loader.watch(1).await;
+    loader.load_many(vec![1, 2]).await;
+    loader.prime(1, DummyData("primed".to_owned()));
+    loader.prime(2, DummyData("other".to_owned()));
+    loader.clear(1);
+    assert_eq!(updates.recv().await.unwrap(), Some(DummyData("1".to_owned())));
+    assert_eq!(updates.recv().await.unwrap(), Some(DummyData("primed".to_owned())));
+    assert_eq!(updates.recv().await.unwrap(), None);
+    drop(loader);
+    assert!(updates.recv().await.is_err());
+}
+
+#[tokio::test]
+async fn invalidation_bus_clears_subscribed_loaders() {
+    let (by_id_batches, by_team_batches) =
+        (Arc::new(Mutex::new(Vec::new())), Arc::new(Mutex::new(Vec::new())));
+    let by_id = Loader::new(RecordingLoader {}, by_id_batches.clone());
+    let by_team = Loader::new(RecordingLoader {}, by_team_batches.clone());
+    let bus = InvalidationBus::new();
+    by_id.subscribe_invalidations(&bus, |user: &i64| vec![*user]);
+    by_team.subscribe_invalidations(&bus, |user: &i64| vec![user / 10]);
+    assert_eq!(bus.subscribers(), 2);
+
+    by_id.load_many(vec![11, 12]).await;
+    by_team.load(1).await;
+    bus.invalidate(&11);
+    by_id.load_many(vec![11, 12]).await;
+    by_team.load(1).await;
+    assert_eq!(*by_id_batches.lock().unwrap(), vec![vec![11, 12], vec![11]]);
+    assert_eq!(*by_team_batches.lock().unwrap(), vec![vec![1], vec![1]]);
+
+    by_team.shutdown().await;
+    bus.invalidate(&12);
+    assert_eq!(bus.subscribers(), 1);
+}
+
+struct ChannelInvalidations(Mutex<Option<tokio::sync::mpsc::UnboundedReceiver<Vec<i64>>>>);
+
+impl InvalidationSource<i64> for ChannelInvalidations {
+    fn subscribe(&self) -> BoxStream<'static, Vec<i64>> {
+        let keys_rx = self.0.lock().unwrap().take().expect("subscribed once");
+        stream::unfold(keys_rx, |mut keys_rx| async move {
+            keys_rx.recv().await.map(|keys| (keys, keys_rx))
+        })
+        .boxed()
+    }
+}
+
+#[tokio::test]
+async fn invalidation_source_clears_keys() {
+    let (keys_tx, keys_rx) = tokio::sync::mpsc::unbounded_channel();
+    let batches = Arc::new(Mutex::new(Vec::new()));
+    let loader = Loader::builder(RecordingLoader {}, batches.clone())
+        .invalidation_source(Arc::new(ChannelInvalidations(Mutex::new(Some(keys_rx)))))
+        .build();
+    loader.load_many(vec![1, 2]).await;
+    let mut updates = loader.watch(1).await;
+    keys_tx.send(vec![1]).unwrap();
+    assert_eq!(updates.recv().await.unwrap(), None);
+    loader.load_many(vec![1, 2]).await;
+    assert_eq!(*batches.lock().unwrap(), vec![vec![1, 2], vec![1]]);
+}
+
+#[tokio::test]
+async fn exported_cache_restores_warm_loader() {
+    let loader = Loader::new(RecordingLoader {}, Arc::new(Mutex::new(Vec::new())));
+    loader.load_many(vec![1, 2]).await;
+    loader.prime(3, DummyData("primed".to_owned()));
+    let snapshot = loader.export_cache().await;
+    assert_eq!(snapshot.len(), 3);
+
+    let batches = Arc::new(Mutex::new(Vec::new()));
+    let restored =
+        Loader::builder(RecordingLoader {}, batches.clone()).restore(snapshot.clone()).build();
+    assert_eq!(
+        restored.load_many(vec![1, 3]).await,
+        vec![Some(DummyData("1".to_owned())), Some(DummyData("primed".to_owned()))]
+    );
+    let imported = Loader::new(RecordingLoader {}, batches.clone());
+    imported.import_cache(snapshot);
+    imported.load_many(vec![1, 2, 3, 4]).await;
+    assert_eq!(*batches.lock().unwrap(), vec![vec![4]]);
+}
+
+#[tokio::test]
+async fn warm_with_primes_before_first_frame() {
+    let batches = Arc::new(Mutex::new(Vec::new()));
+    let loader = Loader::builder(RecordingLoader {}, batches.clone())
+        .warm_with((1..=2).map(|k| (k, DummyData(format!("warm {}", k)))))
+        .warm_with([(3, DummyData("warm 3".to_owned()))])
+        .build();
+    assert_eq!(loader.cache_len().await, 3);
+    assert_eq!(
+        loader.load_many(vec![2, 3, 4]).await,
+        vec![
+            Some(DummyData("warm 2".to_owned())),
+            Some(DummyData("warm 3".to_owned())),
+            Some(DummyData("4".to_owned())),
+        ]
+    );
+    assert_eq!(*batches.lock().unwrap(), vec![vec![4]]);
+}
+
+#[tokio::test]
+async fn refresh_ahead_reloads_hot_entries_before_expiry() {
+    let batches = Arc::new(Mutex::new(Vec::new()));
+    let loader = Loader::builder(RecordingLoader {}, batches.clone())
+        .ttl_cache(Duration::from_millis(200))
+        .refresh_ahead(Duration::from_millis(150))
+        .build();
+    loader.load(1).await;
+    // Only entries read from the cache are hot.
+    loader.load(1).await;
+    tokio::time::sleep(Duration::from_millis(80)).await;
+    loader.load(2).await;
+    wait_for_batches(&batches, 3).await;
+    assert_eq!(*batches.lock().unwrap(), vec![vec![1], vec![2], vec![1]]);
+    assert_eq!(loader.load(1).await, Some(DummyData("1".to_owned())));
+    assert_eq!(batches.lock().unwrap().len(), 3);
+}
+
+#[tokio::test]
+async fn refresh_ahead_stages_each_hot_entry_once_per_read() {
+    let mut cache = TtlCache::new(Duration::from_secs(60));
+    Cache::insert(&mut cache, 1, DummyData("1".to_owned()));
+    Cache::get(&mut cache, &[1]);
+    assert_eq!(Cache::refresh_candidates(&mut cache, Duration::MAX), vec![1]);
+    assert_eq!(Cache::refresh_candidates(&mut cache, Duration::MAX), Vec::<i64>::new());
+
+    let batches = Arc::new(Mutex::new(Vec::new()));
+    let loader = Loader::builder(RecordingLoader {}, batches.clone())
+        .ttl_cache(Duration::from_secs(60))
+        .refresh_ahead(Duration::MAX)
+        .build();
+    loader.load(1).await;
+    loader.load(1).await;
+    loader.load(2).await;
+    wait_for_batches(&batches, 3).await;
+    assert_eq!(loader.load(3).await, Some(DummyData("3".to_owned())));
+}
+
+#[tokio::test]
+async fn stale_while_revalidate_serves_expired_value() {
+    let batches = Arc::new(Mutex::new(Vec::new()));
+    let gate = Arc::new(tokio::sync::Notify::new());
+    let loader = Loader::builder(GatedLoader {}, (batches.clone(), gate.clone()))
+        .ttl_cache(Duration::from_millis(30))
+        .stale_while_revalidate(Duration::from_secs(60))
+        .build();
+    let first = tokio::spawn({
+        let loader = loader.clone();
+        async move { loader.load(1).await }
+    });
+    wait_for_batches(&batches, 1).await;
+    gate.notify_waiters();
+    assert_eq!(first.await.unwrap(), Some(DummyData("1".to_owned())));
+
+    tokio::time::sleep(Duration::from_millis(50)).await;
+    assert_eq!(loader.load(1).await, Some(DummyData("1".to_owned())));
+    wait_for_batches(&batches, 2).await;
+    assert_eq!(*batches.lock().unwrap(), vec![vec![1], vec![1]]);
+    gate.notify_waiters();
+}
@@ -0,0 +1,47 @@
+#![cfg(feature = "metrics")]
+
+use std::collections::HashMap;
+
+use async_trait::async_trait;
+use dataload_rs::{BatchFunction, Loader};
+use futures::future;
+use metrics_util::debugging::{DebugValue, DebuggingRecorder};
+
+struct EchoLoader;
+
+#[async_trait]
+impl BatchFunction<i64, String> for EchoLoader {
+    type Context = ();
+    type Loaded = Vec<(i64, String)>;
+    async fn load(&self, keys: &[i64], _context: &()) -> Vec<(i64, String)> {
+        keys.iter().map(|k| (*k, k.to_string())).collect::<Vec<_>>()
+    }
+}
+
+#[tokio::test]
+async fn worker_emits_metrics() {
+    let recorder = DebuggingRecorder::new();
+    let snapshotter = recorder.snapshotter();
+    recorder.install().unwrap();
+
+    let loader = Loader::new(EchoLoader {}, ());
+    future::join_all([1, 2, 3].map(|k| loader.load(k))).await;
+    loader.load(1).await;
+
+    let metrics = snapshotter
+        .snapshot()
+        .into_vec()
+        .into_iter()
+        .map(|(key, _, _, value)| {
+            let labels = key.key().labels().map(|label| label.value().to_owned()).collect();
+            (key.key().name().to_owned(), (labels, value))
+        })
+        .collect::<HashMap<String, (Vec<String>, DebugValue)>>();
+    let value = |name: &str| &metrics[name].1;
+
+    assert_eq!(metrics["dataload_batches_total"].0, vec!["(i64, alloc::string::String)"]);
+    assert_eq!(value("dataload_batches_total"), &DebugValue::Counter(1));
+    assert_eq!(value("dataload_keys_requested_total"), &DebugValue::Counter(4));
+    assert_eq!(value("dataload_cache_hits_total"), &DebugValue::Counter(1));
+    assert_eq!(value("dataload_batch_size"), &DebugValue::Histogram(vec![3.0.into()]));
+}
@@ -0,0 +1,39 @@
+#![cfg(feature = "redis")]
+
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+
+use async_trait::async_trait;
+use dataload_rs::{BatchFunction, Loader, RedisCache};
+
+struct CountingLoader;
+
+#[async_trait]
+impl BatchFunction<i64, String> for CountingLoader {
+    type Context = Arc<AtomicUsize>;
+    type Loaded = Vec<(i64, String)>;
+    async fn load(&self, keys: &[i64], context: &Arc<AtomicUsize>) -> Vec<(i64, String)> {
+        context.fetch_add(keys.len(), Ordering::SeqCst);
+        keys.iter().map(|k| (*k, k.to_string())).collect::<Vec<_>>()
+    }
+}
+
+/// Requires a Redis server at `REDIS_URL` (defaults to `redis://127.0.0.1/`).
+#[tokio::test]
+#[ignore]
+async fn loaders_share_redis_cache() {
+    let url = std::env::var("REDIS_URL").unwrap_or_else(|_| "redis://127.0.0.1/".to_owned());
+    let client = redis::Client::open(url).unwrap();
+    let connection = client.get_multiplexed_tokio_connection().await.unwrap();
+    let cache = RedisCache::new(connection, "dataload-rs-test:").with_ttl(Duration::from_secs(5));
+
+    let loaded = Arc::new(AtomicUsize::new(0));
+    let first = Loader::builder(CountingLoader {}, loaded.clone()).cache(cache.clone()).build();
+    let second = Loader::builder(CountingLoader {}, loaded.clone()).cache(cache).build();
+
+    first.clear_many(vec![1, 2]);
+    assert_eq!(first.load_many(vec![1, 2]).await, vec![Some("1".to_owned()), Some("2".to_owned())]);
+    assert_eq!(second.load(2).await.as_deref(), Some("2"));
+    assert_eq!(loaded.load(Ordering::SeqCst), 2);
+}
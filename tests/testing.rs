@@ -0,0 +1,57 @@
+#![cfg(feature = "testing")]
+
+use async_trait::async_trait;
+use dataload_rs::{
+    testing::{BatchCounter, Counted, FakeTable, TableLoader},
+    BatchFunction, Loader,
+};
+use futures::future;
+
+struct EchoLoader;
+
+#[async_trait]
+impl BatchFunction<i64, String> for EchoLoader {
+    type Context = String;
+    type Loaded = Vec<(i64, String)>;
+    async fn load(&self, keys: &[i64], prefix: &String) -> Vec<(i64, String)> {
+        keys.iter().map(|k| (*k, format!("{}{}", prefix, k))).collect::<Vec<_>>()
+    }
+}
+
+#[tokio::test]
+async fn batch_counter_counts_batches_and_keys() {
+    let context = Counted::new("user-".to_owned());
+    let counts = context.counts();
+    let loader = Loader::new(BatchCounter::new(EchoLoader), context);
+
+    let values = future::join_all((0..10).map(|k| loader.load(k % 5))).await;
+    assert_eq!(values[7].as_deref(), Some("user-2"));
+    counts.assert_batches(1);
+    counts.assert_keys(5);
+
+    counts.reset();
+    loader.load(11).await;
+    counts.assert_batches(1);
+    counts.assert_keys(1);
+}
+
+#[tokio::test]
+#[should_panic(expected = "expected 1 batches, but 2 were executed")]
+async fn assert_batches_reports_mismatch() {
+    let context = Counted::new(String::new());
+    let counts = context.counts();
+    let loader = Loader::new(BatchCounter::new(EchoLoader), context);
+    loader.load(1).await;
+    loader.load(2).await;
+    counts.assert_batches(1);
+}
+
+#[tokio::test]
+async fn table_loader_queries_fake_table() {
+    let table = FakeTable::new([(1, "one".to_owned()), (2, "two".to_owned())]);
+    let loader = Loader::new(TableLoader, table.clone());
+
+    let values = future::join_all([1, 2, 3].map(|k| loader.load(k))).await;
+    assert_eq!(values, vec![Some("one".to_owned()), Some("two".to_owned()), None]);
+    assert_eq!(table.queries(), vec![vec![1, 2, 3]]);
+}
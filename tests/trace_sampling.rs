@@ -0,0 +1,70 @@
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+use async_trait::async_trait;
+use dataload_rs::{BatchFunction, Loader, TraceSampling};
+use tracing::span::{Attributes, Id, Record};
+use tracing::{Event, Metadata, Subscriber};
+
+/// Counts the op spans created by every worker in the process.
+struct OpSpanCounter;
+
+static OP_SPANS: AtomicUsize = AtomicUsize::new(0);
+
+impl Subscriber for OpSpanCounter {
+    fn enabled(&self, _metadata: &Metadata<'_>) -> bool {
+        true
+    }
+
+    fn new_span(&self, span: &Attributes<'_>) -> Id {
+        if span.metadata().name() == "mux_op" {
+            OP_SPANS.fetch_add(1, Ordering::SeqCst);
+        }
+        Id::from_u64(1)
+    }
+
+    fn record(&self, _span: &Id, _values: &Record<'_>) {}
+
+    fn record_follows_from(&self, _span: &Id, _follows: &Id) {}
+
+    fn event(&self, _event: &Event<'_>) {}
+
+    fn enter(&self, _span: &Id) {}
+
+    fn exit(&self, _span: &Id) {}
+}
+
+struct EchoLoader;
+
+#[async_trait]
+impl BatchFunction<i64, i64> for EchoLoader {
+    type Context = ();
+    type Loaded = Vec<(i64, i64)>;
+    async fn load(&self, keys: &[i64], _context: &()) -> Vec<(i64, i64)> {
+        keys.iter().map(|k| (*k, *k)).collect()
+    }
+}
+
+#[tokio::test]
+async fn trace_sampling_limits_op_spans() {
+    tracing::subscriber::set_global_default(OpSpanCounter).unwrap();
+
+    let loader = Loader::new(EchoLoader {}, ());
+    for key in 0..4 {
+        loader.load(key).await;
+    }
+    assert_eq!(OP_SPANS.swap(0, Ordering::SeqCst), 4);
+
+    let loader =
+        Loader::builder(EchoLoader {}, ()).trace_sampling(TraceSampling::EveryNth(2)).build();
+    for key in 0..4 {
+        loader.load(key).await;
+    }
+    assert_eq!(OP_SPANS.swap(0, Ordering::SeqCst), 2);
+
+    let loader =
+        Loader::builder(EchoLoader {}, ()).trace_sampling(TraceSampling::Rate(0.25)).build();
+    for key in 0..8 {
+        loader.load(key).await;
+    }
+    assert_eq!(OP_SPANS.swap(0, Ordering::SeqCst), 2);
+}